@@ -0,0 +1,104 @@
+//! Measures the throughput of the cryptostreams with buffers of varying capacities.
+//!
+//! Run with `cargo bench`. A capacity of 64 bytes matches the fixed-size buffer the `bufread`
+//! cryptostreams were previously limited to, transforming a couple of blocks per `read()`.
+
+use cryptostream::transform::Transformer;
+use cryptostream::{read, write, Cipher, Mode};
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+const PAYLOAD_LEN: usize = 64 * 1024 * 1024;
+const READ_LEN: usize = 256 * 1024;
+const CAPACITIES: &[usize] = &[64, 8 * 1024, 64 * 1024, 256 * 1024];
+
+fn report(name: &str, capacity: usize, elapsed: Duration) {
+    let mib = PAYLOAD_LEN as f64 / (1024.0 * 1024.0);
+    println!(
+        "{:<28} capacity {:>7}: {:>8.1} MiB/s",
+        name,
+        capacity,
+        mib / elapsed.as_secs_f64()
+    );
+}
+
+fn read_decrypt(cipher: Cipher, key: &[u8], iv: &[u8], encrypted: &[u8]) {
+    let mut buf = vec![0u8; READ_LEN];
+    for &capacity in CAPACITIES {
+        let start = Instant::now();
+        let mut decryptor =
+            read::Decryptor::with_capacity(capacity, encrypted, cipher, key, iv).unwrap();
+        let mut total = 0;
+        loop {
+            match decryptor.read(&mut buf).unwrap() {
+                0 => break,
+                n => total += n,
+            }
+        }
+        assert_eq!(total, PAYLOAD_LEN);
+        report("read::Decryptor", capacity, start.elapsed());
+    }
+}
+
+fn read_encrypt(cipher: Cipher, key: &[u8], iv: &[u8], plaintext: &[u8]) {
+    let mut buf = vec![0u8; READ_LEN];
+    for &capacity in CAPACITIES {
+        let start = Instant::now();
+        let mut encryptor =
+            read::Encryptor::with_capacity(capacity, plaintext, cipher, key, iv).unwrap();
+        while encryptor.read(&mut buf).unwrap() != 0 {}
+        report("read::Encryptor", capacity, start.elapsed());
+    }
+}
+
+fn write_encrypt(cipher: Cipher, key: &[u8], iv: &[u8], plaintext: &[u8]) {
+    for &capacity in CAPACITIES {
+        let start = Instant::now();
+        let output = Vec::with_capacity(PAYLOAD_LEN + 32);
+        let mut encryptor =
+            write::Encryptor::with_capacity(capacity, output, cipher, key, iv).unwrap();
+        for chunk in plaintext.chunks(READ_LEN) {
+            encryptor.write_all(chunk).unwrap();
+        }
+        encryptor.finish().unwrap();
+        report("write::Encryptor", capacity, start.elapsed());
+    }
+}
+
+/// Transforms the payload in place in reads of `READ_LEN` bytes, which the OpenSSL backend does
+/// without copying through a scratch buffer, so that it should keep pace with `read_decrypt()`.
+fn transform_in_place(cipher: Cipher, key: &[u8], iv: &[u8], encrypted: &[u8]) {
+    let mut data = encrypted.to_vec();
+    let start = Instant::now();
+    let mut transformer = Transformer::new(Mode::Decrypt, cipher, key, iv).unwrap();
+    for chunk in data.chunks_mut(READ_LEN) {
+        transformer.update_in_place(chunk).unwrap();
+    }
+    transformer.finish_in_place(&mut []).unwrap();
+    report("Transformer (in place)", READ_LEN, start.elapsed());
+}
+
+fn main() {
+    let plaintext: Vec<u8> = (0..PAYLOAD_LEN).map(|i| i as u8).collect();
+    let key = [0x42u8; 16];
+    let iv = [0x24u8; 16];
+
+    for &(name, cipher) in &[
+        ("aes-128-cbc", Cipher::aes_128_cbc()),
+        ("aes-128-ctr", Cipher::aes_128_ctr()),
+    ] {
+        println!("{}", name);
+        let mut encrypted = Vec::new();
+        read::Encryptor::new(&plaintext[..], cipher, &key, &iv)
+            .unwrap()
+            .read_to_end(&mut encrypted)
+            .unwrap();
+        read_decrypt(cipher, &key, &iv, &encrypted);
+        read_encrypt(cipher, &key, &iv, &plaintext);
+        write_encrypt(cipher, &key, &iv, &plaintext);
+        if cipher.block_size() == 1 {
+            transform_in_place(cipher, &key, &iv, &encrypted);
+        }
+        println!();
+    }
+}
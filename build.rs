@@ -0,0 +1,17 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(ossl111)");
+    println!("cargo:rustc-check-cfg=cfg(ossl300)");
+
+    // Passed on by openssl-sys, as the version of OpenSSL we are linked against in hexadecimal.
+    if let Ok(version) = env::var("DEP_OPENSSL_VERSION_NUMBER") {
+        let version = u64::from_str_radix(&version, 16).unwrap();
+        if version >= 0x1010_1000 {
+            println!("cargo:rustc-cfg=ossl111");
+        }
+        if version >= 0x3000_0000 {
+            println!("cargo:rustc-cfg=ossl300");
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use base64::decode;
 use cryptostream::read;
-use openssl::symm::Cipher;
+use cryptostream::Cipher;
 use std::io::Read;
 
 fn main() {
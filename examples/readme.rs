@@ -1,6 +1,6 @@
 use base64::decode;
+use cryptostream::Cipher;
 use cryptostream::{read, write};
-use openssl::symm::Cipher;
 use std::io::prelude::*;
 
 fn main() {
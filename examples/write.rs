@@ -1,6 +1,6 @@
 use base64::decode;
 use cryptostream::write;
-use openssl::symm::Cipher;
+use cryptostream::Cipher;
 use std::io::Write;
 
 fn main() {
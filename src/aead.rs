@@ -0,0 +1,172 @@
+//! Internal support shared by the authenticated (AEAD) cryptostream variants.
+//!
+//! AEAD ciphers such as AES-GCM or ChaCha20-Poly1305 produce an authentication tag when the
+//! cipher is finalized. The encrypting cryptostreams append this tag to the end of the
+//! ciphertext, while the decrypting cryptostreams must withhold the trailing bytes of the
+//! ciphertext from the cipher (as they can't know which bytes are the tag until the end of the
+//! stream has been reached) and verify them when finalizing.
+
+use crate::backend::{self, StreamCipher};
+use crate::cipher::{Cipher, Id, Mode};
+use crate::error;
+use crate::siv;
+use std::io::{Error, Read};
+
+/// The length of the authentication tag appended to the ciphertext by the AEAD cryptostreams.
+pub(crate) const TAG_LEN: usize = backend::TAG_LEN;
+
+/// The length of the longest tag a [`Trailer`] can withhold, that of the encrypt-then-MAC
+/// cryptostreams.
+pub(crate) const MAX_TAG_LEN: usize = 32;
+
+/// Whether or not `cipher` is an AEAD cipher, which produces an authentication tag.
+pub(crate) fn is_aead(cipher: Cipher) -> bool {
+    let aead = matches!(
+        cipher.0,
+        Id::Aes128Gcm
+            | Id::Aes192Gcm
+            | Id::Aes256Gcm
+            | Id::Aes128Ccm
+            | Id::Aes192Ccm
+            | Id::Aes256Ccm
+            | Id::Aes128Ocb
+            | Id::Aes192Ocb
+            | Id::Aes256Ocb
+            | Id::ChaCha20Poly1305
+    );
+
+    aead || siv::is_gcm_siv(cipher)
+}
+
+/// Creates a `StreamCipher` for use with an AEAD cipher, feeding it the associated data up front.
+pub(crate) fn new_crypter(
+    mode: Mode,
+    cipher: Cipher,
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+) -> Result<Box<dyn StreamCipher>, crate::Error> {
+    error::check_lengths(cipher, key, iv, true)?;
+    // GCM-SIV can only seal a message in its entirety, as the segmented cryptostreams do.
+    if siv::is_gcm_siv(cipher) {
+        return Err(crate::Error::UnsupportedCipher);
+    }
+    // Only AEAD ciphers accept associated data.
+    if !aad.is_empty() && !is_aead(cipher) {
+        return Err(crate::Error::UnsupportedCipher);
+    }
+    let mut crypter = backend::new_cipher(cipher, mode, key, iv)?;
+    if !aad.is_empty() {
+        crypter.aad_update(aad)?;
+    }
+
+    Ok(crypter)
+}
+
+/// The error returned when the authentication tag does not match the decrypted ciphertext.
+pub(crate) fn authentication_failed() -> Error {
+    crate::Error::AuthenticationFailed.into()
+}
+
+/// The error returned when the ciphertext ends before its authentication tag is complete.
+pub(crate) fn truncated() -> Error {
+    crate::Error::Truncated.into()
+}
+
+/// How the authentication tag is handled by an AEAD cryptostream.
+pub(crate) enum Tag {
+    /// The tag, of the given length, is retrieved after finalizing and appended to the ciphertext.
+    Append(usize),
+    /// The trailing bytes of the ciphertext are withheld and verified as the tag when finalizing.
+    Verify(Trailer),
+}
+
+/// The last (up to) `tag_len` bytes of a ciphertext stream, which may turn out to be the tag.
+pub(crate) struct Trailer {
+    buffer: [u8; MAX_TAG_LEN],
+    len: usize,
+    tag_len: usize,
+}
+
+impl Default for Trailer {
+    fn default() -> Self {
+        Self::new(TAG_LEN)
+    }
+}
+
+impl Trailer {
+    pub fn new(tag_len: usize) -> Self {
+        debug_assert!(tag_len <= MAX_TAG_LEN);
+        Self {
+            buffer: [0u8; MAX_TAG_LEN],
+            len: 0,
+            tag_len,
+        }
+    }
+
+    /// Returns the tag if enough of the stream has been seen to contain one.
+    pub fn tag(&self) -> Option<&[u8]> {
+        match self.len == self.tag_len {
+            true => Some(&self.buffer[..self.tag_len]),
+            false => None,
+        }
+    }
+
+    /// Reads from `reader` into `buf`, only returning bytes that are known not to be part of the
+    /// trailing tag. As with `Read::read()`, zero is only returned once `reader` is exhausted.
+    pub fn read<R: Read>(&mut self, reader: &mut R, buf: &mut [u8]) -> Result<usize, Error> {
+        // Make sure the trailer is full before reading anything else, as only then will each byte
+        // read from the source displace exactly one byte out of the trailer.
+        let tag_len = self.tag_len;
+        while self.len < tag_len {
+            match reader.read(&mut self.buffer[self.len..tag_len])? {
+                0 => return Ok(0),
+                n => self.len += n,
+            }
+        }
+
+        let n = reader.read(buf)?;
+        if n >= tag_len {
+            // The end of `buf` becomes the new trailer, and the old trailer is prepended to `buf`.
+            let mut trailer = [0u8; MAX_TAG_LEN];
+            trailer[..tag_len].copy_from_slice(&buf[n - tag_len..n]);
+            buf.copy_within(0..n - tag_len, tag_len);
+            buf[..tag_len].copy_from_slice(&self.buffer[..tag_len]);
+            self.buffer = trailer;
+        } else {
+            let mut joined = [0u8; MAX_TAG_LEN * 2];
+            joined[..tag_len].copy_from_slice(&self.buffer[..tag_len]);
+            joined[tag_len..][..n].copy_from_slice(&buf[..n]);
+            buf[..n].copy_from_slice(&joined[..n]);
+            self.buffer[..tag_len].copy_from_slice(&joined[n..][..tag_len]);
+        }
+
+        Ok(n)
+    }
+
+    /// Appends `input` to the stream, calling `release` with each run of bytes that is pushed out
+    /// of the trailer (and is therefore known not to be part of the tag).
+    pub fn write<F>(&mut self, input: &[u8], mut release: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        let excess = (self.len + input.len()).saturating_sub(self.tag_len);
+        let from_trailer = std::cmp::min(self.len, excess);
+        let from_input = excess - from_trailer;
+
+        if from_trailer > 0 {
+            release(&self.buffer[..from_trailer])?;
+        }
+        if from_input > 0 {
+            release(&input[..from_input])?;
+        }
+
+        self.buffer.copy_within(from_trailer..self.len, 0);
+        self.len -= from_trailer;
+        let remaining = &input[from_input..];
+        self.buffer[self.len..][..remaining.len()].copy_from_slice(remaining);
+        self.len += remaining.len();
+
+        Ok(())
+    }
+}
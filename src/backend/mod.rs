@@ -0,0 +1,337 @@
+//! The cryptographic backends the cryptostreams are built on.
+//!
+//! Every cryptostream drives its cipher through the [`StreamCipher`] trait, which captures the
+//! subset of OpenSSL's `Crypter` interface the cryptostreams rely on (incremental updates,
+//! finalization, padding, and AEAD tags). A [`Backend`] creates `StreamCipher` instances for a
+//! given [`Cipher`], key, and IV, and seals or opens whole messages with an AEAD cipher, as the
+//! segmented cryptostreams do for each segment.
+//!
+//! Two backends are available, each behind a feature of the same name:
+//!
+//! * `OpenSsl`, enabled by default, which wraps `openssl::symm::Crypter` and supports every cipher
+//!   OpenSSL does.
+//! * `RustCrypto`, a pure-Rust implementation built on the
+//!   [RustCrypto](https://github.com/RustCrypto) crates. It implements AES-CBC, AES-CTR, ChaCha20,
+//!   and streams of AES-GCM and ChaCha20-Poly1305 (from their underlying keystream and universal
+//!   hash), and seals and opens whole messages with the `aes-gcm`, `aes-gcm-siv`, and
+//!   `chacha20poly1305` crates. Anything else is passed on to OpenSSL if the `openssl` feature is
+//!   enabled, and is otherwise rejected with [`Error::UnsupportedCipher`](crate::Error).
+//!
+//! The cryptostreams use the [`DefaultBackend`], which is `RustCrypto` when the `rustcrypto`
+//! feature is enabled and `OpenSsl` otherwise. The other primitives the cryptostreams rely on
+//! (HMAC, HKDF, Poly1305, the password KDFs, and the random number generator) come from the same
+//! library as the default backend. Ciphers are identified by [`Cipher`] regardless of the backend
+//! in use, so switching backends requires no changes to any code using the cryptostreams, and
+//! building with `--no-default-features --features rustcrypto` leaves OpenSSL out entirely.
+
+#[cfg(not(any(feature = "openssl", feature = "rustcrypto")))]
+compile_error!("At least one of the `openssl` and `rustcrypto` features must be enabled!");
+
+#[cfg(feature = "openssl")]
+mod openssl;
+#[cfg(feature = "rustcrypto")]
+mod rustcrypto;
+
+#[cfg(feature = "openssl")]
+pub use self::openssl::OpenSsl;
+#[cfg(feature = "rustcrypto")]
+pub use self::rustcrypto::RustCrypto;
+
+#[cfg(feature = "openssl")]
+pub(crate) use self::openssl::fetch_gcm_siv;
+#[cfg(not(feature = "rustcrypto"))]
+pub(crate) use self::openssl::{
+    constant_time_eq, hash, hkdf_sha256, pbkdf2_hmac, poly1305, random_bytes, scrypt, HmacSha256,
+};
+#[cfg(feature = "rustcrypto")]
+pub(crate) use self::rustcrypto::{
+    constant_time_eq, hash, hkdf_sha256, pbkdf2_hmac, poly1305, random_bytes, scrypt, HmacSha256,
+};
+
+use crate::cipher::{Cipher, Mode};
+use crate::secret::Secret;
+use std::fmt;
+
+/// The length of the tags produced and verified by [`Backend::seal()`] and [`Backend::open()`].
+pub const TAG_LEN: usize = 16;
+
+/// The size of the chunks the default `StreamCipher::update_in_place()` transforms at a time.
+const IN_PLACE_CHUNK: usize = 512;
+
+/// The backend used by the cryptostreams, selected by the `rustcrypto` feature.
+#[cfg(feature = "rustcrypto")]
+pub type DefaultBackend = RustCrypto;
+
+/// The backend used by the cryptostreams, selected by the `rustcrypto` feature.
+#[cfg(not(feature = "rustcrypto"))]
+pub type DefaultBackend = OpenSsl;
+
+/// A source of [`StreamCipher`] instances.
+pub trait Backend {
+    /// Creates a `StreamCipher` that encrypts or decrypts (per `mode`) with `cipher`, keyed with
+    /// `key` and initialized with `iv`. Padding is enabled by default, as with `Crypter`.
+    fn new_cipher(
+        cipher: Cipher,
+        mode: Mode,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Box<dyn StreamCipher>, crate::Error>;
+
+    /// Encrypts `plaintext` as a single message with the AEAD `cipher`, authenticating it along
+    /// with `aad`, and appends the ciphertext followed by its [`TAG_LEN`]-byte tag to `output`.
+    ///
+    /// The default implementation drives a `StreamCipher` created with `new_cipher()`.
+    fn seal(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        seal_incrementally::<Self>(cipher, key, nonce, aad, plaintext, output)
+    }
+
+    /// Authenticates and decrypts a message sealed with [`seal()`](Self::seal), given its
+    /// `ciphertext` and `tag`, and appends the plaintext to `output`.
+    ///
+    /// Fails with [`Error::AuthenticationFailed`] without appending anything to `output` if the
+    /// message is not authentic, which includes any `tag` that isn't exactly [`TAG_LEN`] bytes
+    /// long. The default implementation drives a `StreamCipher` created with `new_cipher()`.
+    fn open(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+        tag: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        open_incrementally::<Self>(cipher, key, nonce, aad, ciphertext, tag, output)
+    }
+}
+
+/// Seals a message with a `StreamCipher` created by `B`, as [`Backend::seal()`] does by default.
+fn seal_incrementally<B: Backend + ?Sized>(
+    cipher: Cipher,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let mut crypter = B::new_cipher(cipher, Mode::Encrypt, key, nonce).map_err(setup_error)?;
+    if !aad.is_empty() {
+        crypter.aad_update(aad)?;
+    }
+
+    let start = output.len();
+    output.resize(start + plaintext.len() + TAG_LEN, 0);
+    let result = crypter
+        .update(plaintext, &mut output[start..])
+        .and_then(|n| Ok(n + crypter.finalize(&mut output[start + n..])?))
+        .and_then(|n| {
+            crypter.get_tag(&mut output[start + n..][..TAG_LEN])?;
+            Ok(n)
+        });
+    match result {
+        Ok(written) => output.truncate(start + written + TAG_LEN),
+        Err(_) => output.truncate(start),
+    }
+
+    result.map(|_| ())
+}
+
+/// Opens a message with a `StreamCipher` created by `B`, as [`Backend::open()`] does by default.
+fn open_incrementally<B: Backend + ?Sized>(
+    cipher: Cipher,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let mut crypter = B::new_cipher(cipher, Mode::Decrypt, key, nonce).map_err(setup_error)?;
+    if tag.len() != TAG_LEN {
+        return Err(Error::AuthenticationFailed);
+    }
+    if !aad.is_empty() {
+        crypter.aad_update(aad)?;
+    }
+
+    let start = output.len();
+    output.resize(start + ciphertext.len(), 0);
+    let result = crypter
+        .set_tag(tag)
+        .and_then(|_| crypter.update(ciphertext, &mut output[start..]))
+        .and_then(|n| crypter.finalize(&mut output[start + n..]));
+    if result.is_err() {
+        // Never hand out unauthenticated plaintext
+        output.truncate(start);
+        return Err(Error::AuthenticationFailed);
+    }
+
+    Ok(())
+}
+
+/// Converts an error creating the `StreamCipher` to seal or open a message with, passing those
+/// reported by OpenSSL on as they are.
+fn setup_error(e: crate::Error) -> Error {
+    match e {
+        #[cfg(feature = "openssl")]
+        crate::Error::Backend(e) => Error::OpenSsl(e),
+        _ => Error::Unsupported,
+    }
+}
+
+/// An in-progress encryption or decryption operation, modelled after `openssl::symm::Crypter`.
+pub trait StreamCipher: Send + Sync {
+    /// The block size of the cipher, or 1 if it operates on a byte at a time (e.g. CTR mode).
+    fn block_size(&self) -> usize;
+
+    /// Enables or disables PKCS#7 padding for block ciphers. Padding is enabled by default.
+    fn pad(&mut self, padding: bool);
+
+    /// Feeds associated data to an AEAD cipher. Must be called before the first `update()`.
+    fn aad_update(&mut self, aad: &[u8]) -> Result<(), Error>;
+
+    /// Transforms `input` into `output`, returning the number of bytes written. Block ciphers may
+    /// retain a partial (or, when decrypting with padding, a complete) block until the next call.
+    ///
+    /// `output` must be at least `input.len() + block_size()` bytes long.
+    fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, Error>;
+
+    /// Transforms `data` in place. Only valid for ciphers with a `block_size()` of 1 (keystream
+    /// ciphers such as CTR, CFB, OFB or ChaCha20), which neither retain input nor pad it.
+    ///
+    /// The default implementation passes `data` through `update()` in chunks via a scratch buffer.
+    fn update_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        debug_assert_eq!(self.block_size(), 1);
+
+        let mut scratch = Secret::new([0u8; IN_PLACE_CHUNK + 1]);
+        for chunk in data.chunks_mut(IN_PLACE_CHUNK) {
+            let written = self.update(chunk, &mut scratch[..])?;
+            debug_assert_eq!(written, chunk.len());
+            chunk.copy_from_slice(&scratch[..written]);
+        }
+
+        Ok(())
+    }
+
+    /// Transforms any retained input into `output`, padding or unpadding the final block and
+    /// verifying the tag of an AEAD cipher when decrypting. Returns the number of bytes written.
+    ///
+    /// `output` must be at least `block_size()` bytes long.
+    fn finalize(&mut self, output: &mut [u8]) -> Result<usize, Error>;
+
+    /// Sets the expected tag of an AEAD cipher when decrypting. Must be called before
+    /// `finalize()`.
+    fn set_tag(&mut self, tag: &[u8]) -> Result<(), Error>;
+
+    /// Retrieves the tag of an AEAD cipher after encrypting. Must be called after `finalize()`.
+    fn get_tag(&self, tag: &mut [u8]) -> Result<(), Error>;
+}
+
+/// The errors that may be returned by a [`StreamCipher`].
+#[derive(Debug)]
+pub enum Error {
+    /// An error reported by OpenSSL
+    #[cfg(feature = "openssl")]
+    OpenSsl(::openssl::error::ErrorStack),
+    /// The input ended partway through a block without padding to complete it
+    IncompleteBlock,
+    /// The plaintext ended partway through a block when encrypting without padding
+    IncompletePlaintext,
+    /// The padding of the final block was invalid
+    BadPadding,
+    /// The authentication tag did not match the decrypted ciphertext
+    AuthenticationFailed,
+    /// The operation is not supported by the cipher (e.g. tags with a non-AEAD cipher)
+    Unsupported,
+    /// The operating system's random number generator failed
+    Random(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "openssl")]
+            Error::OpenSsl(e) => e.fmt(f),
+            Error::IncompleteBlock => f.write_str("The input is not a multiple of the block size!"),
+            Error::IncompletePlaintext => {
+                f.write_str("The plaintext is not a multiple of the block size!")
+            }
+            Error::BadPadding => f.write_str("The padding of the final block is invalid!"),
+            Error::AuthenticationFailed => f.write_str("Authentication tag verification failed!"),
+            Error::Unsupported => f.write_str("The operation is not supported by this cipher!"),
+            Error::Random(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "openssl")]
+            Error::OpenSsl(e) => Some(e),
+            Error::Random(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl From<::openssl::error::ErrorStack> for Error {
+    fn from(e: ::openssl::error::ErrorStack) -> Self {
+        Error::OpenSsl(e)
+    }
+}
+
+impl From<Error> for std::io::Error {
+    /// Wraps the corresponding [`crate::Error`] in an I/O error.
+    fn from(e: Error) -> Self {
+        crate::Error::from(e).into()
+    }
+}
+
+/// Generates a random IV of the length required by `cipher`.
+pub(crate) fn random_iv(cipher: Cipher) -> Result<Vec<u8>, Error> {
+    let mut iv = vec![0u8; cipher.iv_len().unwrap_or(0)];
+    random_bytes(&mut iv)?;
+    Ok(iv)
+}
+
+/// Creates a `StreamCipher` with the [`DefaultBackend`].
+pub(crate) fn new_cipher(
+    cipher: Cipher,
+    mode: Mode,
+    key: &[u8],
+    iv: &[u8],
+) -> Result<Box<dyn StreamCipher>, crate::Error> {
+    DefaultBackend::new_cipher(cipher, mode, key, iv)
+}
+/// Seals a message with the [`DefaultBackend`].
+pub(crate) fn seal(
+    cipher: Cipher,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    DefaultBackend::seal(cipher, key, nonce, aad, plaintext, output)
+}
+
+/// Opens a message with the [`DefaultBackend`].
+pub(crate) fn open(
+    cipher: Cipher,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), Error> {
+    DefaultBackend::open(cipher, key, nonce, aad, ciphertext, tag, output)
+}
@@ -0,0 +1,138 @@
+//! The OpenSSL backend, a thin wrapper around OpenSSL's `EVP_CIPHER_CTX`, along with the other
+//! primitives the cryptostreams use when OpenSSL is the default backend.
+
+use super::{Backend, Error, StreamCipher};
+use crate::cipher::{Cipher, Id, Mode};
+use foreign_types::ForeignTypeRef;
+use openssl::cipher::CipherRef;
+use openssl::cipher_ctx::{CipherCtx, CipherCtxRef};
+use openssl::error::ErrorStack;
+use openssl::symm;
+use std::sync::OnceLock;
+
+#[cfg(not(feature = "rustcrypto"))]
+mod primitives;
+#[cfg(not(feature = "rustcrypto"))]
+pub(crate) use self::primitives::*;
+
+/// The backend built on OpenSSL's `EVP_CIPHER` interface, supporting every cipher OpenSSL does.
+pub struct OpenSsl;
+
+impl Backend for OpenSsl {
+    fn new_cipher(
+        cipher: Cipher,
+        mode: Mode,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Box<dyn StreamCipher>, crate::Error> {
+        let openssl_cipher = cipher.to_openssl().ok_or(crate::Error::UnsupportedCipher)?;
+        let init = match mode {
+            Mode::Encrypt => CipherCtxRef::encrypt_init,
+            Mode::Decrypt => CipherCtxRef::decrypt_init,
+        };
+
+        // As `openssl::symm::Crypter::new()` does, but keeping the `CipherCtx` itself so that
+        // `update_in_place()` can pass the same buffer as both input and output.
+        let mut ctx = CipherCtx::new()?;
+        let openssl_cipher = unsafe { CipherRef::from_ptr(openssl_cipher.as_ptr() as *mut _) };
+        init(&mut ctx, Some(openssl_cipher), None, None)?;
+        ctx.set_key_length(key.len())?;
+        if let Some(iv_len) = cipher.iv_len() {
+            if iv.len() != iv_len {
+                ctx.set_iv_length(iv.len())?;
+            }
+        }
+        let iv = cipher.iv_len().map(|_| iv);
+        init(&mut ctx, None, Some(key), iv)?;
+        ctx.set_padding(true);
+
+        Ok(Box::new(OpenSslCipher { ctx, cipher }))
+    }
+}
+
+struct OpenSslCipher {
+    ctx: CipherCtx,
+    cipher: Cipher,
+}
+
+impl StreamCipher for OpenSslCipher {
+    fn block_size(&self) -> usize {
+        self.cipher.block_size()
+    }
+
+    fn pad(&mut self, padding: bool) {
+        self.ctx.set_padding(padding)
+    }
+
+    fn aad_update(&mut self, aad: &[u8]) -> Result<(), Error> {
+        self.ctx.cipher_update(aad, None)?;
+        Ok(())
+    }
+
+    fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        Ok(self.ctx.cipher_update(input, Some(output))?)
+    }
+
+    fn update_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        debug_assert_eq!(self.block_size(), 1);
+
+        // OpenSSL transforms the buffer in place when the input and output are one and the same.
+        let written = self.ctx.cipher_update_inplace(data, data.len())?;
+        debug_assert_eq!(written, data.len());
+        Ok(())
+    }
+
+    fn finalize(&mut self, output: &mut [u8]) -> Result<usize, Error> {
+        Ok(self.ctx.cipher_final(output)?)
+    }
+
+    fn set_tag(&mut self, tag: &[u8]) -> Result<(), Error> {
+        Ok(self.ctx.set_tag(tag)?)
+    }
+
+    fn get_tag(&self, tag: &mut [u8]) -> Result<(), Error> {
+        Ok(self.ctx.tag(tag)?)
+    }
+}
+
+static AES_128_GCM_SIV: OnceLock<Option<symm::Cipher>> = OnceLock::new();
+static AES_256_GCM_SIV: OnceLock<Option<symm::Cipher>> = OnceLock::new();
+
+/// Fetches the GCM-SIV `cipher` from OpenSSL's providers the first time it is requested, or
+/// returns `None` if the linked OpenSSL doesn't provide it. GCM-SIV has no legacy `EVP_CIPHER`
+/// that `openssl::symm` could refer to.
+pub(crate) fn fetch_gcm_siv(cipher: Cipher) -> Option<symm::Cipher> {
+    match cipher.0 {
+        Id::Aes128GcmSiv => fetch(&AES_128_GCM_SIV, b"AES-128-GCM-SIV\0"),
+        Id::Aes256GcmSiv => fetch(&AES_256_GCM_SIV, b"AES-256-GCM-SIV\0"),
+        _ => None,
+    }
+}
+
+/// Fetches the `algorithm` (a NUL-terminated name) into `cache`.
+#[cfg(ossl300)]
+fn fetch(cache: &'static OnceLock<Option<symm::Cipher>>, algorithm: &[u8]) -> Option<symm::Cipher> {
+    *cache.get_or_init(|| unsafe {
+        // The fetched cipher is never freed, as every copy of the `Cipher` refers to it.
+        let ptr = openssl_sys::EVP_CIPHER_fetch(
+            std::ptr::null_mut(),
+            algorithm.as_ptr().cast(),
+            std::ptr::null(),
+        );
+        if ptr.is_null() {
+            // Clear the error queue of the failed fetch, lest it be reported by a later call.
+            ErrorStack::get();
+            return None;
+        }
+        Some(symm::Cipher::from_ptr(ptr))
+    })
+}
+
+/// OpenSSL releases prior to 3.0 have no providers to fetch GCM-SIV from.
+#[cfg(not(ossl300))]
+fn fetch(
+    _cache: &'static OnceLock<Option<symm::Cipher>>,
+    _algorithm: &[u8],
+) -> Option<symm::Cipher> {
+    None
+}
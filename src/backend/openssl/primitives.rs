@@ -0,0 +1,141 @@
+//! The primitives the cryptostreams use alongside the ciphers when OpenSSL is the default
+//! backend.
+
+use super::super::Error;
+use crate::password::Digest;
+use openssl::hash::MessageDigest;
+use openssl::md::Md;
+use openssl::md_ctx::MdCtx;
+use openssl::pkey::PKey;
+use openssl::pkey_ctx::PkeyCtx;
+
+/// Fills `buf` with random bytes from OpenSSL's CSPRNG.
+pub(crate) fn random_bytes(buf: &mut [u8]) -> Result<(), Error> {
+    Ok(openssl::rand::rand_bytes(buf)?)
+}
+
+/// Compares `a` and `b` in constant time (but for their lengths).
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && openssl::memcmp::eq(a, b)
+}
+
+fn message_digest(digest: Digest) -> MessageDigest {
+    match digest {
+        Digest::Md5 => MessageDigest::md5(),
+        Digest::Sha1 => MessageDigest::sha1(),
+        Digest::Sha256 => MessageDigest::sha256(),
+        Digest::Sha384 => MessageDigest::sha384(),
+        Digest::Sha512 => MessageDigest::sha512(),
+    }
+}
+
+/// Hashes the concatenation of `parts` with `digest` into `output`, which must be at least
+/// [`Digest::MAX_LEN`] bytes long, returning the length of the hash.
+pub(crate) fn hash(
+    digest: Digest,
+    parts: &[&[u8]],
+    output: &mut [u8],
+) -> Result<usize, crate::Error> {
+    let mut hasher = openssl::hash::Hasher::new(message_digest(digest))?;
+    for part in parts {
+        hasher.update(part)?;
+    }
+    let hash = hasher.finish()?;
+    output[..hash.len()].copy_from_slice(&hash);
+    Ok(hash.len())
+}
+
+/// An incremental HMAC-SHA256 computation.
+pub(crate) struct HmacSha256(MdCtx);
+
+impl HmacSha256 {
+    pub fn new(key: &[u8]) -> Result<Self, Error> {
+        // The context takes its own reference to the key, which needn't outlive it.
+        let key = PKey::hmac(key)?;
+        let mut ctx = MdCtx::new()?;
+        ctx.digest_sign_init(Some(Md::sha256()), &key)?;
+        Ok(Self(ctx))
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        Ok(self.0.digest_sign_update(data)?)
+    }
+
+    pub fn finalize(&mut self) -> Result<[u8; 32], Error> {
+        let mut mac = [0u8; 32];
+        self.0.digest_sign_final(Some(&mut mac))?;
+        Ok(mac)
+    }
+}
+
+/// Derives `output` from `key` with HKDF-SHA256.
+pub(crate) fn hkdf_sha256(
+    key: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    output: &mut [u8],
+) -> Result<(), crate::Error> {
+    let mut ctx = PkeyCtx::new_id(openssl::pkey::Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(Md::sha256())?;
+    ctx.set_hkdf_key(key)?;
+    ctx.set_hkdf_salt(salt)?;
+    ctx.add_hkdf_info(info)?;
+    ctx.derive(Some(output))?;
+    Ok(())
+}
+
+/// Computes the Poly1305 MAC of the concatenation of `parts` under the one-time `key`.
+#[cfg(ossl111)]
+pub(crate) fn poly1305(key: &[u8], parts: &[&[u8]]) -> Result<[u8; 16], crate::Error> {
+    use openssl::sign::Signer;
+
+    let key = PKey::private_key_from_raw_bytes(key, openssl::pkey::Id::POLY1305)?;
+    let mut signer = Signer::new_without_digest(&key)?;
+    for part in parts {
+        signer.update(part)?;
+    }
+    let mut mac = [0u8; 16];
+    signer.sign(&mut mac)?;
+    Ok(mac)
+}
+
+/// OpenSSL releases prior to 1.1.1 do not expose Poly1305 on its own.
+#[cfg(not(ossl111))]
+pub(crate) fn poly1305(_key: &[u8], _parts: &[&[u8]]) -> Result<[u8; 16], crate::Error> {
+    Err(crate::Error::UnsupportedCipher)
+}
+
+/// Derives `output` from `password` with PBKDF2-HMAC-`digest`.
+pub(crate) fn pbkdf2_hmac(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    digest: Digest,
+    output: &mut [u8],
+) -> Result<(), crate::Error> {
+    let digest = message_digest(digest);
+    Ok(openssl::pkcs5::pbkdf2_hmac(
+        password,
+        salt,
+        iterations as usize,
+        digest,
+        output,
+    )?)
+}
+
+/// Derives `output` from `password` with scrypt, with a cost parameter of `N = 2^log_n`.
+pub(crate) fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+    output: &mut [u8],
+) -> Result<(), crate::Error> {
+    let (n, r, p) = (1u64 << log_n, r as u64, p as u64);
+    // OpenSSL refuses to use more than 32 MiB unless told otherwise.
+    let max_memory = 128 * r * (n + p + 2);
+    openssl::pkcs5::scrypt(password, salt, n, r, p, max_memory, output)
+        .map_err(|_| crate::Error::InvalidKdfParameters)
+}
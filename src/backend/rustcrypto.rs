@@ -0,0 +1,812 @@
+//! The pure-Rust backend, built on the [RustCrypto](https://github.com/RustCrypto) crates, along
+//! with the other primitives the cryptostreams use when it is the default backend.
+//!
+//! The RustCrypto AEAD crates (`aes-gcm`, `aes-gcm-siv`, and `chacha20poly1305`) only seal or open
+//! a message in one go, which doesn't suit a stream of unknown length. They are used to seal and
+//! open whole messages (i.e. the segments of the segmented cryptostreams), while AES-GCM and
+//! ChaCha20-Poly1305 streams are put together from the keystream ciphers (`ctr` and `chacha20`)
+//! and universal hashes (`ghash` and `poly1305`) those crates are themselves built on.
+
+use super::{Backend, Error, StreamCipher, TAG_LEN};
+use crate::cipher::{Cipher, Id, Mode};
+use crate::password::Digest;
+use crate::secret::Secret;
+use aes::cipher::consts::{U12, U16};
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockCipher, BlockDecryptMut, BlockEncrypt, BlockEncryptMut, BlockSizeUser};
+use aes::cipher::{InnerIvInit, KeyInit, KeyIvInit, StreamCipherSeek};
+use aes::{Aes128, Aes192, Aes256};
+use aes_gcm::aead::AeadInPlace;
+use aes_gcm::{Aes128Gcm, Aes256Gcm, AesGcm};
+use aes_gcm_siv::{Aes128GcmSiv, Aes256GcmSiv};
+use chacha20::ChaCha20;
+use chacha20poly1305::ChaCha20Poly1305;
+use ghash::universal_hash::UniversalHash;
+use ghash::GHash;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::convert::TryInto;
+
+/// The block size of AES, and of the GHASH and Poly1305 universal hashes.
+const BLOCK_SIZE: usize = 16;
+
+/// The nonce length supported for AES-GCM and ChaCha20-Poly1305, which is also the default.
+const NONCE_LEN: usize = 12;
+
+/// The backend built on the RustCrypto crates, available with the `rustcrypto` feature.
+///
+/// AES-CBC, AES-CTR, ChaCha20, AES-GCM, and ChaCha20-Poly1305 (with 128, 192, or 256-bit keys for
+/// AES) are implemented in pure Rust, as is sealing and opening whole messages with AES-GCM-SIV.
+/// Every other cipher is handed off to `OpenSsl` when the `openssl` feature is enabled, as is any
+/// key or IV of a length other than the cipher's default, so that errors are reported the same
+/// way regardless of the backend. Without it, they are rejected.
+pub struct RustCrypto;
+
+impl Backend for RustCrypto {
+    fn new_cipher(
+        cipher: Cipher,
+        mode: Mode,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Box<dyn StreamCipher>, crate::Error> {
+        let decrypt = matches!(mode, Mode::Decrypt);
+        let rust_cipher = match cipher.0 {
+            Id::Aes128Cbc if decrypt => Cbc::<cbc::Decryptor<Aes128>>::boxed(key, iv, decrypt),
+            Id::Aes192Cbc if decrypt => Cbc::<cbc::Decryptor<Aes192>>::boxed(key, iv, decrypt),
+            Id::Aes256Cbc if decrypt => Cbc::<cbc::Decryptor<Aes256>>::boxed(key, iv, decrypt),
+            Id::Aes128Cbc => Cbc::<cbc::Encryptor<Aes128>>::boxed(key, iv, decrypt),
+            Id::Aes192Cbc => Cbc::<cbc::Encryptor<Aes192>>::boxed(key, iv, decrypt),
+            Id::Aes256Cbc => Cbc::<cbc::Encryptor<Aes256>>::boxed(key, iv, decrypt),
+            Id::Aes128Ctr => Keystream::<ctr::Ctr128BE<Aes128>>::boxed(key, iv),
+            Id::Aes192Ctr => Keystream::<ctr::Ctr128BE<Aes192>>::boxed(key, iv),
+            Id::Aes256Ctr => Keystream::<ctr::Ctr128BE<Aes256>>::boxed(key, iv),
+            Id::ChaCha20 => Keystream::chacha20(key, iv),
+            Id::Aes128Gcm => Aead::<ctr::Ctr32BE<Aes128>, GHash>::gcm(key, iv, decrypt),
+            Id::Aes192Gcm => Aead::<ctr::Ctr32BE<Aes192>, GHash>::gcm(key, iv, decrypt),
+            Id::Aes256Gcm => Aead::<ctr::Ctr32BE<Aes256>, GHash>::gcm(key, iv, decrypt),
+            Id::ChaCha20Poly1305 => Aead::chacha20_poly1305(key, iv, decrypt),
+            _ => None,
+        };
+
+        match rust_cipher {
+            Some(rust_cipher) => Ok(rust_cipher),
+            None => fall_back(cipher, mode, key, iv),
+        }
+    }
+
+    fn seal(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let sealed = match cipher.0 {
+            Id::Aes128Gcm => seal::<Aes128Gcm>(key, nonce, aad, plaintext, output),
+            Id::Aes192Gcm => seal::<AesGcm<Aes192, U12>>(key, nonce, aad, plaintext, output),
+            Id::Aes256Gcm => seal::<Aes256Gcm>(key, nonce, aad, plaintext, output),
+            Id::Aes128GcmSiv => seal::<Aes128GcmSiv>(key, nonce, aad, plaintext, output),
+            Id::Aes256GcmSiv => seal::<Aes256GcmSiv>(key, nonce, aad, plaintext, output),
+            Id::ChaCha20Poly1305 => seal::<ChaCha20Poly1305>(key, nonce, aad, plaintext, output),
+            _ => None,
+        };
+
+        match sealed {
+            Some(result) => result,
+            None => super::seal_incrementally::<Self>(cipher, key, nonce, aad, plaintext, output),
+        }
+    }
+
+    fn open(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+        tag: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let opened = match cipher.0 {
+            Id::Aes128Gcm => open::<Aes128Gcm>(key, nonce, aad, ciphertext, tag, output),
+            Id::Aes192Gcm => open::<AesGcm<Aes192, U12>>(key, nonce, aad, ciphertext, tag, output),
+            Id::Aes256Gcm => open::<Aes256Gcm>(key, nonce, aad, ciphertext, tag, output),
+            Id::Aes128GcmSiv => open::<Aes128GcmSiv>(key, nonce, aad, ciphertext, tag, output),
+            Id::Aes256GcmSiv => open::<Aes256GcmSiv>(key, nonce, aad, ciphertext, tag, output),
+            Id::ChaCha20Poly1305 => {
+                open::<ChaCha20Poly1305>(key, nonce, aad, ciphertext, tag, output)
+            }
+            _ => None,
+        };
+
+        match opened {
+            Some(result) => result,
+            None => {
+                super::open_incrementally::<Self>(cipher, key, nonce, aad, ciphertext, tag, output)
+            }
+        }
+    }
+}
+
+/// Creates a `StreamCipher` for a cipher (or key or IV length) not implemented here with OpenSSL.
+#[cfg(feature = "openssl")]
+fn fall_back(
+    cipher: Cipher,
+    mode: Mode,
+    key: &[u8],
+    iv: &[u8],
+) -> Result<Box<dyn StreamCipher>, crate::Error> {
+    super::OpenSsl::new_cipher(cipher, mode, key, iv)
+}
+
+/// Rejects a cipher (or key or IV length) not implemented here, without OpenSSL to fall back to.
+#[cfg(not(feature = "openssl"))]
+fn fall_back(
+    cipher: Cipher,
+    _mode: Mode,
+    key: &[u8],
+    iv: &[u8],
+) -> Result<Box<dyn StreamCipher>, crate::Error> {
+    crate::error::check_lengths(cipher, key, iv, false)?;
+    Err(crate::Error::UnsupportedCipher)
+}
+
+/// Creates an AEAD cipher keyed with `key`, or `None` if either `key` or `nonce` is of a length
+/// it doesn't support (which is left to a `StreamCipher` to handle or report).
+fn new_aead<A>(key: &[u8], nonce: &[u8]) -> Option<A>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    match nonce.len() {
+        NONCE_LEN => A::new_from_slice(key).ok(),
+        _ => None,
+    }
+}
+
+fn seal<A>(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+    output: &mut Vec<u8>,
+) -> Option<Result<(), Error>>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    let aead = new_aead::<A>(key, nonce)?;
+
+    let start = output.len();
+    output.extend_from_slice(plaintext);
+    let nonce = GenericArray::from_slice(nonce);
+    let result = match aead.encrypt_in_place_detached(nonce, aad, &mut output[start..]) {
+        Ok(tag) => {
+            output.extend_from_slice(&tag);
+            Ok(())
+        }
+        // Only returned for messages too long to be encrypted under a single nonce.
+        Err(_) => {
+            output.truncate(start);
+            Err(Error::Unsupported)
+        }
+    };
+
+    Some(result)
+}
+
+fn open<A>(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+    output: &mut Vec<u8>,
+) -> Option<Result<(), Error>>
+where
+    A: AeadInPlace<NonceSize = U12, TagSize = U16> + KeyInit,
+{
+    let aead = new_aead::<A>(key, nonce)?;
+    // A truncated tag would make forgeries that much easier.
+    if tag.len() != TAG_LEN {
+        return Some(Err(Error::AuthenticationFailed));
+    }
+
+    let start = output.len();
+    output.extend_from_slice(ciphertext);
+    let nonce = GenericArray::from_slice(nonce);
+    let tag = GenericArray::from_slice(tag);
+    let result = aead.decrypt_in_place_detached(nonce, aad, &mut output[start..], tag);
+    if result.is_err() {
+        // Never hand out unauthenticated plaintext
+        output.truncate(start);
+        return Some(Err(Error::AuthenticationFailed));
+    }
+
+    Some(Ok(()))
+}
+
+/// A block cipher mode processing one block at a time, i.e. either direction of CBC.
+trait BlockMode: Send + Sync {
+    fn process(&mut self, block: &mut [u8; BLOCK_SIZE]);
+}
+
+impl<C> BlockMode for cbc::Encryptor<C>
+where
+    C: BlockEncryptMut + BlockCipher + BlockSizeUser<BlockSize = U16> + Send + Sync,
+{
+    fn process(&mut self, block: &mut [u8; BLOCK_SIZE]) {
+        self.encrypt_block_mut(block.into())
+    }
+}
+
+impl<C> BlockMode for cbc::Decryptor<C>
+where
+    C: BlockDecryptMut + BlockCipher + BlockSizeUser<BlockSize = U16> + Send + Sync,
+{
+    fn process(&mut self, block: &mut [u8; BLOCK_SIZE]) {
+        self.decrypt_block_mut(block.into())
+    }
+}
+
+/// AES-CBC with optional PKCS#7 padding, buffering partial blocks between updates.
+struct Cbc<M> {
+    mode: M,
+    decrypt: bool,
+    padding: bool,
+    block: Secret<[u8; BLOCK_SIZE]>,
+    len: usize,
+}
+
+impl<M: BlockMode + KeyIvInit + 'static> Cbc<M> {
+    fn boxed(key: &[u8], iv: &[u8], decrypt: bool) -> Option<Box<dyn StreamCipher>> {
+        Some(Box::new(Self {
+            mode: M::new_from_slices(key, iv).ok()?,
+            decrypt,
+            padding: true,
+            block: Secret::new([0u8; BLOCK_SIZE]),
+            len: 0,
+        }))
+    }
+}
+
+impl<M: BlockMode> Cbc<M> {
+    /// Processes the (complete) buffered block, writing it to `output`.
+    fn flush(&mut self, output: &mut [u8]) -> usize {
+        debug_assert_eq!(self.len, BLOCK_SIZE);
+        self.mode.process(&mut self.block);
+        output[..BLOCK_SIZE].copy_from_slice(&self.block[..]);
+        self.len = 0;
+        BLOCK_SIZE
+    }
+}
+
+impl<M: BlockMode> StreamCipher for Cbc<M> {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn pad(&mut self, padding: bool) {
+        self.padding = padding;
+    }
+
+    fn aad_update(&mut self, _aad: &[u8]) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn update(&mut self, mut input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        let mut written = 0;
+        while !input.is_empty() {
+            if self.len == BLOCK_SIZE {
+                // A block retained for unpadding that turned out not to be the last
+                written += self.flush(&mut output[written..]);
+            }
+
+            let len = std::cmp::min(BLOCK_SIZE - self.len, input.len());
+            self.block[self.len..][..len].copy_from_slice(&input[..len]);
+            self.len += len;
+            input = &input[len..];
+
+            // When decrypting with padding, the last complete block must be retained until it's
+            // known whether or not it is the final block.
+            if self.len == BLOCK_SIZE && !(self.decrypt && self.padding) {
+                written += self.flush(&mut output[written..]);
+            }
+        }
+
+        Ok(written)
+    }
+
+    fn finalize(&mut self, output: &mut [u8]) -> Result<usize, Error> {
+        if !self.padding {
+            return match self.len {
+                0 => Ok(0),
+                _ => Err(Error::IncompleteBlock),
+            };
+        }
+
+        if !self.decrypt {
+            let padding = BLOCK_SIZE - self.len;
+            self.block[self.len..].fill(padding as u8);
+            self.len = BLOCK_SIZE;
+            return Ok(self.flush(output));
+        }
+
+        if self.len != BLOCK_SIZE {
+            return Err(Error::IncompleteBlock);
+        }
+        self.mode.process(&mut self.block);
+        self.len = 0;
+        let padding = self.block[BLOCK_SIZE - 1] as usize;
+        if padding == 0
+            || padding > BLOCK_SIZE
+            || self.block[BLOCK_SIZE - padding..]
+                .iter()
+                .any(|&b| b as usize != padding)
+        {
+            return Err(Error::BadPadding);
+        }
+        let len = BLOCK_SIZE - padding;
+        output[..len].copy_from_slice(&self.block[..len]);
+
+        Ok(len)
+    }
+
+    fn set_tag(&mut self, _tag: &[u8]) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn get_tag(&self, _tag: &mut [u8]) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+}
+
+/// A keystream cipher, i.e. AES-CTR (treating the entire IV as a 128-bit big-endian counter as
+/// OpenSSL does) or ChaCha20.
+struct Keystream<C>(C);
+
+impl<C: KeyIvInit + aes::cipher::StreamCipher + Send + Sync + 'static> Keystream<C> {
+    fn boxed(key: &[u8], iv: &[u8]) -> Option<Box<dyn StreamCipher>> {
+        Some(Box::new(Self(C::new_from_slices(key, iv).ok()?)))
+    }
+}
+
+impl Keystream<ChaCha20> {
+    /// ChaCha20 with the 16-byte IV of OpenSSL: a 32-bit little-endian block counter followed by
+    /// the 96-bit nonce.
+    fn chacha20(key: &[u8], iv: &[u8]) -> Option<Box<dyn StreamCipher>> {
+        if iv.len() != 16 {
+            return None;
+        }
+        let mut chacha = ChaCha20::new_from_slices(key, &iv[4..]).ok()?;
+        let counter = u32::from_le_bytes(iv[..4].try_into().unwrap());
+        chacha.seek(counter as u64 * 64);
+
+        Some(Box::new(Self(chacha)))
+    }
+}
+
+impl<C: aes::cipher::StreamCipher + Send + Sync> StreamCipher for Keystream<C> {
+    fn block_size(&self) -> usize {
+        1
+    }
+
+    fn pad(&mut self, _padding: bool) {}
+
+    fn aad_update(&mut self, _aad: &[u8]) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        let output = &mut output[..input.len()];
+        output.copy_from_slice(input);
+        self.update_in_place(output)?;
+        Ok(input.len())
+    }
+
+    fn update_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        // Only fails once the keystream is exhausted, e.g. past 256 GiB of ChaCha20.
+        self.0
+            .try_apply_keystream(data)
+            .map_err(|_| Error::Unsupported)
+    }
+
+    fn finalize(&mut self, _output: &mut [u8]) -> Result<usize, Error> {
+        Ok(0)
+    }
+
+    fn set_tag(&mut self, _tag: &[u8]) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn get_tag(&self, _tag: &mut [u8]) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+}
+
+/// A universal hash (GHASH or Poly1305) over a sequence of messages, buffering partial blocks
+/// between updates.
+struct BlockMac<U> {
+    mac: U,
+    block: [u8; BLOCK_SIZE],
+    len: usize,
+}
+
+impl<U: UniversalHash<BlockSize = U16> + Clone> BlockMac<U> {
+    fn new(mac: U) -> Self {
+        Self {
+            mac,
+            block: [0u8; BLOCK_SIZE],
+            len: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        if self.len > 0 {
+            let len = std::cmp::min(BLOCK_SIZE - self.len, data.len());
+            self.block[self.len..][..len].copy_from_slice(&data[..len]);
+            self.len += len;
+            data = &data[len..];
+            if self.len < BLOCK_SIZE {
+                return;
+            }
+            self.mac.update(&[self.block.into()]);
+            self.len = 0;
+        }
+
+        let (blocks, remainder) = data.split_at(data.len() - data.len() % BLOCK_SIZE);
+        // Whole blocks are never padded.
+        self.mac.update_padded(blocks);
+        self.block[..remainder.len()].copy_from_slice(remainder);
+        self.len = remainder.len();
+    }
+
+    /// Pads the message fed in so far with zeroes to a whole number of blocks.
+    fn pad(&mut self) {
+        self.mac.update_padded(&self.block[..self.len]);
+        self.len = 0;
+    }
+
+    fn finalize(&self) -> [u8; TAG_LEN] {
+        debug_assert_eq!(self.len, 0);
+        self.mac.clone().finalize().into()
+    }
+}
+
+/// An AEAD cipher built from a keystream cipher and a universal hash, i.e. AES-GCM or
+/// ChaCha20-Poly1305 encrypting or decrypting a stream incrementally. The associated data and
+/// the ciphertext are each padded to a whole number of blocks, and followed by their lengths.
+struct Aead<K, U> {
+    keystream: K,
+    mac: BlockMac<U>,
+    /// XORed into the output of the universal hash to produce the tag.
+    tag_mask: Secret<[u8; TAG_LEN]>,
+    /// Encodes the lengths of the associated data and of the ciphertext as the final block.
+    lengths: fn(u64, u64) -> [u8; BLOCK_SIZE],
+    decrypt: bool,
+    aad_len: u64,
+    text_len: u64,
+    /// Whether the associated data is complete, which it is once the ciphertext begins.
+    started: bool,
+    /// When encrypting, the tag computed by `finalize()`. When decrypting, the expected tag
+    /// provided by `set_tag()`.
+    tag: Option<[u8; TAG_LEN]>,
+}
+
+impl<C> Aead<ctr::Ctr32BE<C>, GHash>
+where
+    C: BlockEncrypt + BlockEncryptMut + BlockCipher + BlockSizeUser<BlockSize = U16> + KeyInit,
+    C: Send + Sync + 'static,
+{
+    /// AES-GCM, per NIST SP 800-38D, with a nonce of any (non-zero) length.
+    fn gcm(key: &[u8], nonce: &[u8], decrypt: bool) -> Option<Box<dyn StreamCipher>> {
+        let cipher = C::new_from_slice(key).ok()?;
+        if nonce.is_empty() {
+            return None;
+        }
+
+        let mut hash_key = Secret::new([0u8; BLOCK_SIZE]);
+        cipher.encrypt_block(GenericArray::from_mut_slice(&mut hash_key[..]));
+        let ghash = GHash::new(GenericArray::from_slice(&hash_key[..]));
+
+        // The pre-counter block, from which the tag mask and the keystream are derived.
+        let mut counter = Secret::new([0u8; BLOCK_SIZE]);
+        if nonce.len() == NONCE_LEN {
+            counter[..NONCE_LEN].copy_from_slice(nonce);
+            counter[BLOCK_SIZE - 1] = 1;
+        } else {
+            let mut nonce_mac = BlockMac::new(ghash.clone());
+            nonce_mac.update(nonce);
+            nonce_mac.pad();
+            nonce_mac.update(&gcm_lengths(0, nonce.len() as u64));
+            *counter = nonce_mac.finalize();
+        }
+
+        let mut tag_mask = Secret::new(*counter);
+        cipher.encrypt_block(GenericArray::from_mut_slice(&mut tag_mask[..]));
+        let core = ctr::CtrCore::inner_iv_init(cipher, GenericArray::from_slice(&counter[..]));
+        let keystream = ctr::Ctr32BE::from_core(core);
+        let mut gcm = Self::new(keystream, ghash, tag_mask, gcm_lengths, decrypt);
+        // The keystream begins with the counter block following the pre-counter block.
+        gcm.keystream.seek(BLOCK_SIZE as u64);
+
+        Some(Box::new(gcm))
+    }
+}
+
+impl Aead<ChaCha20, ::poly1305::Poly1305> {
+    /// ChaCha20-Poly1305, per RFC 8439.
+    fn chacha20_poly1305(key: &[u8], nonce: &[u8], decrypt: bool) -> Option<Box<dyn StreamCipher>> {
+        if nonce.len() != NONCE_LEN {
+            return None;
+        }
+        let mut chacha = ChaCha20::new_from_slices(key, nonce).ok()?;
+
+        // The Poly1305 key is the start of the first block of keystream, and the ciphertext is
+        // encrypted with the rest of the keystream from the second block on.
+        let mut mac_key = Secret::new([0u8; 64]);
+        aes::cipher::StreamCipher::apply_keystream(&mut chacha, &mut mac_key[..]);
+        let poly1305 = ::poly1305::Poly1305::new(GenericArray::from_slice(&mac_key[..32]));
+        let tag_mask = Secret::new([0u8; TAG_LEN]);
+
+        Some(Box::new(Self::new(
+            chacha,
+            poly1305,
+            tag_mask,
+            chacha20_poly1305_lengths,
+            decrypt,
+        )))
+    }
+}
+
+/// The lengths block of AES-GCM: the lengths in bits, big-endian.
+fn gcm_lengths(aad_len: u64, text_len: u64) -> [u8; BLOCK_SIZE] {
+    let mut block = [0u8; BLOCK_SIZE];
+    block[..8].copy_from_slice(&(aad_len * 8).to_be_bytes());
+    block[8..].copy_from_slice(&(text_len * 8).to_be_bytes());
+    block
+}
+
+/// The lengths block of ChaCha20-Poly1305: the lengths in bytes, little-endian.
+fn chacha20_poly1305_lengths(aad_len: u64, text_len: u64) -> [u8; BLOCK_SIZE] {
+    let mut block = [0u8; BLOCK_SIZE];
+    block[..8].copy_from_slice(&aad_len.to_le_bytes());
+    block[8..].copy_from_slice(&text_len.to_le_bytes());
+    block
+}
+
+impl<K, U> Aead<K, U>
+where
+    K: aes::cipher::StreamCipher,
+    U: UniversalHash<BlockSize = U16> + Clone,
+{
+    fn new(
+        keystream: K,
+        mac: U,
+        tag_mask: Secret<[u8; TAG_LEN]>,
+        lengths: fn(u64, u64) -> [u8; BLOCK_SIZE],
+        decrypt: bool,
+    ) -> Self {
+        Self {
+            keystream,
+            mac: BlockMac::new(mac),
+            tag_mask,
+            lengths,
+            decrypt,
+            aad_len: 0,
+            text_len: 0,
+            started: false,
+            tag: None,
+        }
+    }
+
+    /// Completes the associated data ahead of the ciphertext.
+    fn start(&mut self) {
+        if !self.started {
+            self.mac.pad();
+            self.started = true;
+        }
+    }
+}
+
+impl<K, U> StreamCipher for Aead<K, U>
+where
+    K: aes::cipher::StreamCipher + Send + Sync,
+    U: UniversalHash<BlockSize = U16> + Clone + Send + Sync,
+{
+    fn block_size(&self) -> usize {
+        1
+    }
+
+    fn pad(&mut self, _padding: bool) {}
+
+    fn aad_update(&mut self, aad: &[u8]) -> Result<(), Error> {
+        if self.started {
+            return Err(Error::Unsupported);
+        }
+
+        self.mac.update(aad);
+        self.aad_len += aad.len() as u64;
+        Ok(())
+    }
+
+    fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        let output = &mut output[..input.len()];
+        output.copy_from_slice(input);
+        self.update_in_place(output)?;
+        Ok(input.len())
+    }
+
+    fn update_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.start();
+        if self.decrypt {
+            self.mac.update(data);
+        }
+        // Only fails once the keystream is exhausted, past 64 GiB of AES-GCM.
+        self.keystream
+            .try_apply_keystream(data)
+            .map_err(|_| Error::Unsupported)?;
+        if !self.decrypt {
+            self.mac.update(data);
+        }
+        self.text_len += data.len() as u64;
+
+        Ok(())
+    }
+
+    fn finalize(&mut self, _output: &mut [u8]) -> Result<usize, Error> {
+        self.start();
+        self.mac.pad();
+        self.mac
+            .update(&(self.lengths)(self.aad_len, self.text_len));
+        let mut tag = self.mac.finalize();
+        for (tag, mask) in tag.iter_mut().zip(self.tag_mask.iter()) {
+            *tag ^= mask;
+        }
+
+        if !self.decrypt {
+            self.tag = Some(tag);
+            return Ok(0);
+        }
+        match self.tag {
+            Some(expected) if constant_time_eq(&tag, &expected) => Ok(0),
+            _ => Err(Error::AuthenticationFailed),
+        }
+    }
+
+    fn set_tag(&mut self, tag: &[u8]) -> Result<(), Error> {
+        // A truncated tag would make forgeries that much easier.
+        if !self.decrypt || tag.len() != TAG_LEN {
+            return Err(Error::AuthenticationFailed);
+        }
+
+        self.tag = Some(tag.try_into().unwrap());
+        Ok(())
+    }
+
+    fn get_tag(&self, tag: &mut [u8]) -> Result<(), Error> {
+        match &self.tag {
+            Some(computed) if !self.decrypt && tag.len() == TAG_LEN => {
+                tag.copy_from_slice(computed);
+                Ok(())
+            }
+            _ => Err(Error::Unsupported),
+        }
+    }
+}
+
+/// Fills `buf` with random bytes from the operating system's CSPRNG.
+pub(crate) fn random_bytes(buf: &mut [u8]) -> Result<(), Error> {
+    getrandom::getrandom(buf).map_err(|e| Error::Random(e.into()))
+}
+
+/// Compares `a` and `b` in constant time (but for their lengths).
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    subtle::ConstantTimeEq::ct_eq(a, b).into()
+}
+
+/// Hashes the concatenation of `parts` with `digest` into `output`, which must be at least
+/// [`Digest::MAX_LEN`] bytes long, returning the length of the hash.
+pub(crate) fn hash(
+    digest: Digest,
+    parts: &[&[u8]],
+    output: &mut [u8],
+) -> Result<usize, crate::Error> {
+    fn hash<D: sha2::Digest>(parts: &[&[u8]], output: &mut [u8]) -> usize {
+        let mut hasher = D::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        let hash = hasher.finalize();
+        output[..hash.len()].copy_from_slice(&hash);
+        hash.len()
+    }
+
+    Ok(match digest {
+        Digest::Md5 => hash::<md5::Md5>(parts, output),
+        Digest::Sha1 => hash::<sha1::Sha1>(parts, output),
+        Digest::Sha256 => hash::<sha2::Sha256>(parts, output),
+        Digest::Sha384 => hash::<sha2::Sha384>(parts, output),
+        Digest::Sha512 => hash::<sha2::Sha512>(parts, output),
+    })
+}
+
+/// An incremental HMAC-SHA256 computation.
+pub(crate) struct HmacSha256(Hmac<Sha256>);
+
+impl HmacSha256 {
+    pub fn new(key: &[u8]) -> Result<Self, Error> {
+        // HMAC accepts keys of any length.
+        Ok(Self(<Hmac<Sha256> as Mac>::new_from_slice(key).unwrap()))
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.0.update(data);
+        Ok(())
+    }
+
+    pub fn finalize(&mut self) -> Result<[u8; 32], Error> {
+        Ok(self.0.clone().finalize().into_bytes().into())
+    }
+}
+
+/// Derives `output` from `key` with HKDF-SHA256.
+pub(crate) fn hkdf_sha256(
+    key: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    output: &mut [u8],
+) -> Result<(), crate::Error> {
+    // Only fails for outputs longer than 255 hashes, far longer than any key.
+    hkdf::Hkdf::<Sha256>::new(Some(salt), key)
+        .expand(info, output)
+        .map_err(|_| crate::Error::InvalidKeyLength)
+}
+
+/// Computes the Poly1305 MAC of the concatenation of `parts` under the one-time `key`.
+pub(crate) fn poly1305(key: &[u8], parts: &[&[u8]]) -> Result<[u8; 16], crate::Error> {
+    let poly1305 =
+        ::poly1305::Poly1305::new_from_slice(key).map_err(|_| crate::Error::InvalidKeyLength)?;
+    let mut mac = BlockMac::new(poly1305);
+    for part in parts {
+        mac.update(part);
+    }
+
+    // Unlike ChaCha20-Poly1305, the final partial block is not padded.
+    let BlockMac { mac, block, len } = mac;
+    Ok(mac.compute_unpadded(&block[..len]).into())
+}
+
+/// Derives `output` from `password` with PBKDF2-HMAC-`digest`.
+pub(crate) fn pbkdf2_hmac(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    digest: Digest,
+    output: &mut [u8],
+) -> Result<(), crate::Error> {
+    match digest {
+        Digest::Md5 => pbkdf2::pbkdf2_hmac::<md5::Md5>(password, salt, iterations, output),
+        Digest::Sha1 => pbkdf2::pbkdf2_hmac::<sha1::Sha1>(password, salt, iterations, output),
+        Digest::Sha256 => pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, output),
+        Digest::Sha384 => pbkdf2::pbkdf2_hmac::<sha2::Sha384>(password, salt, iterations, output),
+        Digest::Sha512 => pbkdf2::pbkdf2_hmac::<sha2::Sha512>(password, salt, iterations, output),
+    }
+
+    Ok(())
+}
+
+/// Derives `output` from `password` with scrypt, with a cost parameter of `N = 2^log_n`.
+pub(crate) fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+    output: &mut [u8],
+) -> Result<(), crate::Error> {
+    let invalid = |_| crate::Error::InvalidKdfParameters;
+    // The output length of the parameters only applies to password hashes.
+    let params =
+        ::scrypt::Params::new(log_n, r, p, ::scrypt::Params::RECOMMENDED_LEN).map_err(invalid)?;
+    ::scrypt::scrypt(password, salt, &params, output)
+        .map_err(|_| crate::Error::InvalidKdfParameters)
+}
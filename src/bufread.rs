@@ -15,18 +15,38 @@
 //! The `bufread::Cryptostream` variants in this module handle the buffering for you, and ensure
 //! that reads always return (when and where possible) nice, round buffers divisible by the
 //! enryption algorithm's block size.
+//!
+//! Keystream ciphers with a block size of one byte (CTR, CFB, OFB, ChaCha20, ...) need none of
+//! this: they are transformed in place in the caller's buffer, so each `read()` returns exactly as
+//! many bytes as were read from the underlying source.
 
-use openssl::error::ErrorStack;
-use openssl::symm::{Cipher, Crypter, Mode};
-use std::io::{BufRead, Error, ErrorKind, Read};
+use crate::aead::{self, Tag, Trailer, TAG_LEN};
+use crate::backend::{self, StreamCipher};
+use crate::cipher::{Cipher, Mode};
+use crate::commit::{self, COMMITMENT_LEN};
+use crate::error;
+use crate::etm::{self, MAC_LEN};
+use crate::header::Header;
+use crate::padding::Padding;
+use crate::password::{EncKdf, Format, Kdf, KdfHeader, KdfLimits};
+use crate::secret::Secret;
+use crate::secretstream::{Chunker, DEFAULT_CHUNK_SIZE};
+use crate::segment::{Segmenter, Segments, DEFAULT_SEGMENT_SIZE, NONCE_PREFIX_LEN};
+use crate::transform::Crypter;
+use std::convert::TryFrom;
+use std::io::{BufRead, Cursor, Error, ErrorKind, IoSliceMut, Read, Seek, SeekFrom};
 
 /// EVP_MAX_BLOCK_LENGTH in OpenSSL is 32 bytes, and we require at least 2*n-1 for the worst case
 /// where we start off with just a byte shy of a block and then read an entire block.
 const EVP_MAX_BLOCK_LENGTH: usize = 32;
 const BUFFER_SIZE: usize = EVP_MAX_BLOCK_LENGTH * 2;
 
+/// The default capacity of the buffer ciphertext or plaintext is read into from the source, which
+/// bounds how much input is transformed by a single `read()`.
+pub(crate) const DEFAULT_CAPACITY: usize = 8 * 1024;
+
 struct Buffer {
-    buffer: [u8; BUFFER_SIZE],
+    buffer: Secret<[u8; BUFFER_SIZE]>,
     write_index: usize,
     read_index: usize,
 }
@@ -35,14 +55,14 @@ struct Buffer {
 impl Default for Buffer {
     fn default() -> Self {
         Self {
-            buffer: [0u8; BUFFER_SIZE],
+            buffer: Secret::new([0u8; BUFFER_SIZE]),
             write_index: 0,
             read_index: 0,
         }
     }
 }
 
-impl<'a> Buffer {
+impl Buffer {
     fn len(&self) -> usize {
         self.write_index - self.read_index
     }
@@ -87,12 +107,25 @@ fn zero_len_buffer_read() {
 
 struct Cryptostream<R: Read> {
     reader: R,
-    read_buffer: [u8; BUFFER_SIZE],
+    read_buffer: Secret<Box<[u8]>>,
     write_buffer: Buffer,
     never_used: bool,
     cipher: Cipher,
     crypter: Crypter,
+    iv: Vec<u8>,
     finalized: bool,
+    /// Only set for the authenticated (AEAD) variants.
+    tag: Option<Tag>,
+    /// The number of bytes returned by `read()` so far, or the position seeked to.
+    position: u64,
+    /// Output preceding the ciphertext (e.g. the header of a password-encrypted stream).
+    prefix: Vec<u8>,
+    /// The offset of the ciphertext in the underlying stream, following any header.
+    offset: u64,
+    /// Output transformed ahead of being consumed, only allocated once `fill_buf()` is called.
+    output: Secret<Vec<u8>>,
+    /// The number of bytes of `output` consumed so far.
+    output_index: usize,
 }
 
 impl<R: Read> Cryptostream<R> {
@@ -102,28 +135,273 @@ impl<R: Read> Cryptostream<R> {
         cipher: Cipher,
         key: &[u8],
         iv: &[u8],
-    ) -> Result<Self, ErrorStack> {
-        let mut crypter = Crypter::new(cipher, mode, key, Some(iv))?;
-        crypter.pad(true);
+    ) -> Result<Self, crate::Error> {
+        Self::new_padded(mode, reader, cipher, key, iv, Padding::default())
+    }
+
+    pub fn new_padded(
+        mode: Mode,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, crate::Error> {
+        Self::new_buffered(mode, reader, cipher, key, iv, padding, DEFAULT_CAPACITY)
+    }
+
+    /// Creates a cryptostream reading up to `capacity` bytes from `reader` at a time.
+    pub fn new_buffered(
+        mode: Mode,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+        capacity: usize,
+    ) -> Result<Self, crate::Error> {
+        let crypter = Crypter::new(cipher, mode, key, iv, padding)?;
 
         Ok(Self {
-            reader: reader,
-            read_buffer: [0; BUFFER_SIZE],
+            reader,
+            read_buffer: new_read_buffer(capacity),
             write_buffer: Default::default(),
             never_used: true,
-            cipher: cipher.clone(),
-            crypter: crypter,
+            cipher,
+            crypter,
+            iv: iv.to_vec(),
             finalized: false,
+            tag: None,
+            position: 0,
+            prefix: Vec::new(),
+            offset: 0,
+            output: Secret::default(),
+            output_index: 0,
         })
     }
 
+    pub fn new_aead(
+        mode: Mode,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Self::new_aead_buffered(mode, reader, cipher, key, iv, aad, DEFAULT_CAPACITY)
+    }
+
+    /// Creates an AEAD cryptostream transforming up to `capacity` bytes at a time.
+    pub fn new_aead_buffered(
+        mode: Mode,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        capacity: usize,
+    ) -> Result<Self, crate::Error> {
+        let crypter = aead::new_crypter(mode, cipher, key, iv, aad)?;
+        Ok(Self::authenticated(
+            mode, reader, cipher, crypter, iv, TAG_LEN, capacity,
+        ))
+    }
+
+    pub fn new_etm(
+        mode: Mode,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let crypter = etm::new_crypter(mode, cipher, key, mac_key, iv)?;
+        Ok(Self::authenticated(
+            mode,
+            reader,
+            cipher,
+            crypter,
+            iv,
+            MAC_LEN,
+            DEFAULT_CAPACITY,
+        ))
+    }
+
+    /// Shared by the authenticated variants, which append a tag of `tag_len` bytes to the
+    /// ciphertext, transforming up to `capacity` bytes at a time.
+    fn authenticated(
+        mode: Mode,
+        reader: R,
+        cipher: Cipher,
+        crypter: Box<dyn StreamCipher>,
+        iv: &[u8],
+        tag_len: usize,
+        capacity: usize,
+    ) -> Self {
+        let tag = match mode {
+            Mode::Encrypt => Tag::Append(tag_len),
+            Mode::Decrypt => Tag::Verify(Trailer::new(tag_len)),
+        };
+
+        Self {
+            reader,
+            read_buffer: new_read_buffer(capacity),
+            write_buffer: Default::default(),
+            never_used: true,
+            cipher,
+            crypter: Crypter::Buffered(crypter),
+            iv: iv.to_vec(),
+            finalized: false,
+            tag: Some(tag),
+            position: 0,
+            prefix: Vec::new(),
+            offset: 0,
+            output: Secret::default(),
+            output_index: 0,
+        }
+    }
+
     pub fn finish(self) -> R {
         self.reader
     }
+
+    /// Finalizes an AEAD cipher, appending the authentication tag to the output when encrypting
+    /// or verifying the withheld tag when decrypting.
+    ///
+    /// Unlike the non-authenticated case, the cipher must be finalized even if no data was ever
+    /// written to it, as the tag is still required to authenticate an empty stream.
+    fn finalize_aead(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let write_buffer = &mut self.write_buffer;
+        let crypter = &mut self.crypter;
+        write_buffer.reset();
+
+        match &self.tag {
+            Some(Tag::Append(len)) => {
+                write_buffer.fill(|b| crypter.finalize(b))?;
+                write_buffer.fill(|b| crypter.get_tag(&mut b[..*len]).map(|_| *len))?;
+            }
+            Some(Tag::Verify(trailer)) => {
+                let tag = trailer.tag().ok_or_else(aead::truncated)?;
+                crypter.set_tag(tag)?;
+                write_buffer
+                    .fill(|b| crypter.finalize(b))
+                    .map_err(|_| aead::authentication_failed())?;
+            }
+            None => unreachable!("finalize_aead() called on a non-AEAD cryptostream!"),
+        }
+
+        write_buffer.read(buf)
+    }
+}
+
+impl<R: Read + Seek> Cryptostream<R> {
+    /// Repositions a CTR mode cryptostream by recomputing the counter for the new position and
+    /// recreating the cipher from it.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        if !self.crypter.is_seekable() {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Only cryptostreams using a CTR mode cipher are seekable!",
+            ));
+        }
+
+        let position = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(n) => self.position.checked_add_signed(n),
+            // The ciphertext and plaintext are always the same length in CTR mode.
+            SeekFrom::End(n) => self.reader.seek(SeekFrom::End(n))?.checked_sub(self.offset),
+        }
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid seek to a negative position!",
+            )
+        })?;
+
+        self.crypter.seek(position)?;
+        self.reader.seek(SeekFrom::Start(self.offset + position))?;
+        self.write_buffer.reset();
+        self.output.clear();
+        self.output_index = 0;
+        self.finalized = false;
+        self.position = position;
+
+        Ok(position)
+    }
 }
 
 impl<R: Read> Read for Cryptostream<R> {
-    fn read(&mut self, mut buf: &mut [u8]) -> Result<usize, Error> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        // Drain anything left over from `fill_buf()` first.
+        if self.output_index < self.output.len() {
+            let len = std::cmp::min(buf.len(), self.output.len() - self.output_index);
+            buf[..len].copy_from_slice(&self.output[self.output_index..][..len]);
+            self.consume(len);
+            return Ok(len);
+        }
+
+        let read = self.read_unbuffered(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+
+    /// Transforms up to a buffer's worth of the source in a single pass, then scatters it across
+    /// `bufs`. Anything that doesn't fit is kept for the next read.
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Error> {
+        let mut output = self.fill_buf()?;
+        let mut read = 0;
+        for buf in bufs {
+            let len = std::cmp::min(buf.len(), output.len());
+            buf[..len].copy_from_slice(&output[..len]);
+            output = &output[len..];
+            read += len;
+        }
+
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl<R: Read> BufRead for Cryptostream<R> {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        if self.output_index == self.output.len() {
+            // Leave room for a full read of the source along with a block retained by the cipher,
+            // so that each refill transforms as much as a single `read()` would.
+            let mut output = std::mem::take(&mut self.output);
+            output.resize(self.read_buffer.len() + EVP_MAX_BLOCK_LENGTH, 0);
+            let result = self.read_unbuffered(&mut output);
+            output.truncate(*result.as_ref().unwrap_or(&0));
+            self.output = output;
+            self.output_index = 0;
+            result?;
+        }
+
+        Ok(&self.output[self.output_index..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = std::cmp::min(amt, self.output.len() - self.output_index);
+        self.output_index += amt;
+        self.position += amt as u64;
+    }
+}
+
+impl<R: Read> Cryptostream<R> {
+    /// Reads the prefix, then the transformed source, into `buf`, bypassing `output`.
+    fn read_unbuffered(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if !self.prefix.is_empty() {
+            let len = std::cmp::min(buf.len(), self.prefix.len());
+            buf[..len].copy_from_slice(&self.prefix[..len]);
+            self.prefix.drain(..len);
+            return Ok(len);
+        }
+
+        self.transform(buf)
+    }
+}
+
+impl<R: Read> Cryptostream<R> {
+    fn transform(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
         let block_size = self.cipher.block_size();
         debug_assert!(
             block_size.count_ones() == 1,
@@ -132,50 +410,70 @@ impl<R: Read> Read for Cryptostream<R> {
 
         if !self.write_buffer.is_empty() {
             // Resume from previously transformed content
-            let drained = self.write_buffer.read(&mut buf)?;
+            let drained = self.write_buffer.read(buf)?;
             return Ok(drained);
         }
         if self.finalized {
             return Ok(0);
         }
+        if let Crypter::InPlace(transformer) = &mut self.crypter {
+            // Keystream ciphers neither retain input nor pad the output, so the data read from the
+            // source is transformed directly in `buf` and there is nothing to finalize.
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            let n = self.reader.read(buf)?;
+            self.finalized = n == 0;
+            transformer.update_in_place(&mut buf[..n])?;
+            return Ok(n);
+        }
+
+        // Read as much as the output of can be written straight to `buf` with a single update,
+        // leaving room for the block the cipher may retain from a previous read. Should `buf` be
+        // smaller than that, only read a block at a time, the output of which is buffered locally.
+        let max_read = buf
+            .len()
+            .saturating_sub(block_size)
+            .clamp(block_size, self.read_buffer.len());
 
         // Read::read() is required to return zero bytes only if the EOF is reached, so we must
         // loop over the input source until at least one block has been read and transformed.
-        let mut bytes_read = 0;
         loop {
-            let max_read = self.read_buffer.len() - bytes_read - block_size;
-            let mut read_buffer = &mut self.read_buffer[bytes_read..][..max_read];
-            match self.reader.read(&mut read_buffer) {
+            let read_buffer = &mut self.read_buffer[..max_read];
+            let result = match &mut self.tag {
+                Some(Tag::Verify(trailer)) => trailer.read(&mut self.reader, read_buffer),
+                _ => self.reader.read(read_buffer),
+            };
+            match result {
                 Ok(0) => {
                     // We have reached the end of the wrapped/underlying stream
                     self.finalized = true;
 
+                    if self.tag.is_some() {
+                        return self.finalize_aead(buf);
+                    }
+
                     // [openssl::symm::Crypter::finalize(..)] will panic if zero bytes have been
                     // written to the instance before `finalize()` is called. We have to call
                     // Crypter::finalize(..) if we ever wrote to the instance.
                     return if self.never_used {
                         Ok(0)
-                    } else if !self.write_buffer.is_empty() || buf.len() < bytes_read + block_size {
+                    } else if buf.len() < block_size {
                         // The destination buffer is not sufficient for a zero-copy operation
                         // without scatter-gather.
                         let write_buffer = &mut self.write_buffer;
                         let crypter = &mut self.crypter;
-                        write_buffer.fill(|b| crypter.finalize(b))
-                            .map_err(|e| Error::new(ErrorKind::Other, e))?;
+                        write_buffer.reset();
+                        write_buffer.fill(|b| crypter.finalize(b))?;
 
                         self.write_buffer.read(buf)
                     } else {
                         // We can skip the copy and use the provided buffer directly.
-                        self.crypter
-                            .finalize(&mut buf)
-                            .map_err(|e| Error::new(ErrorKind::Other, e))
-                    }
+                        self.crypter.finalize(buf).map_err(Error::from)
+                    };
                 }
                 Ok(n) => {
                     self.never_used = false;
-                    let old_bytes_read = bytes_read;
-                    bytes_read += n;
-
                     debug_assert!(self.write_buffer.is_empty());
 
                     // OpenSSL will panic if we try to read into too small a buffer, so we may need
@@ -187,16 +485,16 @@ impl<R: Read> Read for Cryptostream<R> {
                         // necessarily been read by this point) but rather only to move the write
                         // cursor to the start of the buffer.
                         write_buffer.reset();
-                        let bytes_written = write_buffer.fill(|b| crypter.update(&read_buffer[old_bytes_read..bytes_read], b))
-                            .map_err(|e| Error::new(ErrorKind::Other, e))?;
+                        let bytes_written =
+                            write_buffer.fill(|b| crypter.update(&read_buffer[..n], b))?;
 
                         match bytes_written {
                             0 => continue,
-                            _ => return self.write_buffer.read(&mut buf)
+                            _ => return self.write_buffer.read(buf),
                         };
                     } else {
                         // Skip the double-buffering and write directly to the source.
-                        match self.crypter.update(&self.read_buffer[old_bytes_read..bytes_read], &mut buf)? {
+                        match self.crypter.update(&read_buffer[..n], buf)? {
                             0 => continue,
                             written => return Ok(written),
                         };
@@ -209,6 +507,12 @@ impl<R: Read> Read for Cryptostream<R> {
     }
 }
 
+/// Allocates the buffer a cryptostream reads its source into, of at least `EVP_MAX_BLOCK_LENGTH`
+/// bytes.
+fn new_read_buffer(capacity: usize) -> Secret<Box<[u8]>> {
+    Secret::new(vec![0u8; std::cmp::max(capacity, EVP_MAX_BLOCK_LENGTH)].into_boxed_slice())
+}
+
 /// An encrypting stream adapter that encrypts what it reads
 ///
 /// `bufread::Encryptor` is a stream adapter that sits atop a plaintext (non-encrypted) [`BufRead`]
@@ -219,12 +523,121 @@ pub struct Encryptor<R: BufRead> {
 }
 
 impl<R: BufRead> Encryptor<R> {
-    pub fn new(reader: R, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, ErrorStack> {
+    pub fn new(reader: R, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
         Ok(Self {
             inner: Cryptostream::new(Mode::Encrypt, reader, cipher, key, iv)?,
         })
     }
 
+    /// Creates a new `Encryptor` which reads up to `capacity` bytes (rather than 8 KiB) from `reader`
+    /// at a time, so that a single `read()` into a large enough buffer transforms up to that much
+    /// in one go.
+    pub fn with_capacity(
+        capacity: usize,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_buffered(
+                Mode::Encrypt,
+                reader,
+                cipher,
+                key,
+                iv,
+                Padding::default(),
+                capacity,
+            )?,
+        })
+    }
+
+    /// Creates a new `Encryptor` which pads the plaintext with `padding` rather than PKCS#7. See
+    /// the [`padding`](crate::padding) module for details.
+    pub fn with_padding(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_padded(Mode::Encrypt, reader, cipher, key, iv, padding)?,
+        })
+    }
+
+    /// Creates a new `Encryptor` with a freshly generated random IV, which must be retrieved with
+    /// [`iv()`](Self::iv) and passed on to the decryptor.
+    pub fn with_random_iv(reader: R, cipher: Cipher, key: &[u8]) -> Result<Self, crate::Error> {
+        Self::new(reader, cipher, key, &backend::random_iv(cipher)?)
+    }
+
+    /// Creates a new `Encryptor` with a freshly generated random IV, which is prepended to the
+    /// output to be read back by [`Decryptor::with_prepended_iv()`].
+    pub fn with_prepended_iv(reader: R, cipher: Cipher, key: &[u8]) -> Result<Self, crate::Error> {
+        let mut encryptor = Self::with_random_iv(reader, cipher, key)?;
+        encryptor.inner.prefix = encryptor.inner.iv.clone();
+        Ok(encryptor)
+    }
+
+    /// Creates a new `Encryptor` whose output begins with `header`, from which
+    /// [`Decryptor::auto()`] reads back the cipher and IV. See the [`header`](crate::header)
+    /// module for details.
+    pub fn with_header(reader: R, header: &Header, key: &[u8]) -> Result<Self, crate::Error> {
+        let mut inner =
+            Cryptostream::new(Mode::Encrypt, reader, header.cipher(), key, header.iv())?;
+        inner.prefix = header.to_bytes();
+
+        Ok(Self { inner })
+    }
+
+    /// Creates a new `Encryptor` keyed with `password`, from which the key and IV are derived
+    /// with `kdf` and a random salt. The output begins with a header recording the KDF and salt,
+    /// which is read back by [`Decryptor::with_password()`]. See the [`password`](crate::password)
+    /// module for details.
+    pub fn with_password(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        kdf: Kdf,
+    ) -> Result<Self, crate::Error> {
+        Self::with_kdf_header(reader, cipher, password, KdfHeader::generate(kdf)?)
+    }
+
+    /// Creates a new `Encryptor` producing the same output as `openssl enc` with a random salt,
+    /// keyed with `password` via `kdf`. See the [`password`](crate::password) module for details.
+    pub fn with_openssl_enc(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        kdf: EncKdf,
+    ) -> Result<Self, crate::Error> {
+        Self::with_kdf_header(
+            reader,
+            cipher,
+            password,
+            KdfHeader::generate_openssl_enc(kdf)?,
+        )
+    }
+
+    pub(crate) fn with_kdf_header(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        header: KdfHeader,
+    ) -> Result<Self, crate::Error> {
+        let (key, iv) = header.derive(cipher, password)?;
+        let mut inner = Cryptostream::new(Mode::Encrypt, reader, cipher, &key, &iv)?;
+        inner.prefix = header.to_bytes();
+
+        Ok(Self { inner })
+    }
+
+    /// The IV the stream is encrypted with.
+    pub fn iv(&self) -> &[u8] {
+        &self.inner.iv
+    }
+
     pub fn finish(self) -> R {
         self.inner.reader
     }
@@ -232,8 +645,25 @@ impl<R: BufRead> Encryptor<R> {
 
 impl<R: BufRead> Read for Encryptor<R> {
     /// Reads encrypted data out of the underlying plaintext
-    fn read(&mut self, mut buf: &mut [u8]) -> Result<usize, Error> {
-        self.inner.read(&mut buf)
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+
+    /// Reads encrypted data into `bufs`, encrypting it in a single pass.
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Error> {
+        self.inner.read_vectored(bufs)
+    }
+}
+
+impl<R: BufRead> BufRead for Encryptor<R> {
+    /// Returns the next run of encrypted data, encrypting more of the underlying plaintext into an
+    /// internal buffer once everything previously returned has been consumed.
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
     }
 }
 
@@ -242,17 +672,142 @@ impl<R: BufRead> Read for Encryptor<R> {
 /// `bufread::Decryptor` is a stream adapter that sits atop a ciphertext (encrypted) `BufRead` source,
 /// exposing a second `BufRead` interface. Bytes read out of `bufread::Decrytor` are the decrypted
 /// contents of the underlying `Read` stream.
+///
+/// Ciphertext ending partway through a block (or a header) is reported as
+/// [`Error::Truncated`](crate::Error::Truncated), wrapped in an error with kind
+/// [`ErrorKind::UnexpectedEof`]. Ciphertext truncated at a block boundary can't be told apart from
+/// a complete stream other than by its (most likely invalid) padding, and stream cipher modes such
+/// as CTR can't detect truncation at all; use one of the authenticated variants where this
+/// matters.
 pub struct Decryptor<R: BufRead> {
     inner: Cryptostream<R>,
 }
 
 impl<R: BufRead> Decryptor<R> {
-    pub fn new(reader: R, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, ErrorStack> {
+    pub fn new(reader: R, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
         Ok(Self {
             inner: Cryptostream::new(Mode::Decrypt, reader, cipher, key, iv)?,
         })
     }
 
+    /// Creates a new `Decryptor` which reads up to `capacity` bytes (rather than 8 KiB) from `reader`
+    /// at a time, so that a single `read()` into a large enough buffer transforms up to that much
+    /// in one go.
+    pub fn with_capacity(
+        capacity: usize,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_buffered(
+                Mode::Decrypt,
+                reader,
+                cipher,
+                key,
+                iv,
+                Padding::default(),
+                capacity,
+            )?,
+        })
+    }
+
+    /// Creates a new `Decryptor` which expects the plaintext to have been padded with `padding`
+    /// rather than PKCS#7. See the [`padding`](crate::padding) module for details.
+    pub fn with_padding(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_padded(Mode::Decrypt, reader, cipher, key, iv, padding)?,
+        })
+    }
+
+    /// Creates a new `Decryptor` for ciphertext produced by an encryptor created with
+    /// `with_prepended_iv()`, reading the IV from the start of `reader`.
+    pub fn with_prepended_iv(
+        mut reader: R,
+        cipher: Cipher,
+        key: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let mut iv = vec![0u8; cipher.iv_len().unwrap_or(0)];
+        reader.read_exact(&mut iv).map_err(error::reading_header)?;
+        let mut inner = Cryptostream::new(Mode::Decrypt, reader, cipher, key, &iv)?;
+        inner.offset = iv.len() as u64;
+
+        Ok(Self { inner })
+    }
+
+    /// Creates a new `Decryptor` for ciphertext produced by an encryptor created with
+    /// `with_header()`, reading the header from the start of `reader` and configuring the cipher
+    /// and IV as recorded in it.
+    pub fn auto(mut reader: R, key: &[u8]) -> Result<Self, crate::Error> {
+        let header = Header::read(&mut reader)?;
+        Self::from_header(reader, &header, key)
+    }
+
+    /// Creates a new `Decryptor` configured with `header`, which has already been read from the
+    /// start of `reader` with [`Header::read()`]. This allows the key to be chosen based on the
+    /// header's key id.
+    pub fn from_header(reader: R, header: &Header, key: &[u8]) -> Result<Self, crate::Error> {
+        let mut inner =
+            Cryptostream::new(Mode::Decrypt, reader, header.cipher(), key, header.iv())?;
+        inner.offset = header.encoded_len() as u64;
+
+        Ok(Self { inner })
+    }
+
+    /// Creates a new `Decryptor` for ciphertext produced by an encryptor created with
+    /// `with_password()`, reading the header from the start of `reader` and deriving the key and
+    /// IV from `password` as recorded in it. Fails with [`Error::InvalidKdfParameters`] if the
+    /// recorded parameters exceed the default [`KdfLimits`].
+    ///
+    /// [`Error::InvalidKdfParameters`]: crate::Error::InvalidKdfParameters
+    pub fn with_password(reader: R, cipher: Cipher, password: &[u8]) -> Result<Self, crate::Error> {
+        Self::with_password_and_limits(reader, cipher, password, KdfLimits::default())
+    }
+
+    /// Like [`with_password()`](Self::with_password), but accepting KDF parameters up to `limits`
+    /// rather than the defaults.
+    pub fn with_password_and_limits(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        limits: KdfLimits,
+    ) -> Result<Self, crate::Error> {
+        Self::with_format(reader, cipher, password, Format::Native(limits))
+    }
+
+    /// Creates a new `Decryptor` for the output of `openssl enc`, reading the salt from the start
+    /// of `reader` and deriving the key and IV from `password` via `kdf`, which must match the
+    /// options it was encrypted with. See the [`password`](crate::password) module for details.
+    pub fn with_openssl_enc(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        kdf: EncKdf,
+    ) -> Result<Self, crate::Error> {
+        Self::with_format(reader, cipher, password, Format::OpenSslEnc(kdf))
+    }
+
+    fn with_format(
+        mut reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        format: Format,
+    ) -> Result<Self, crate::Error> {
+        let header = KdfHeader::read(&mut reader, format)?;
+        let (key, iv) = header.derive(cipher, password)?;
+        let mut inner = Cryptostream::new(Mode::Decrypt, reader, cipher, &key, &iv)?;
+        inner.offset = header.encoded_len() as u64;
+
+        Ok(Self { inner })
+    }
+
     pub fn finish(self) -> R {
         self.inner.finish()
     }
@@ -265,7 +820,771 @@ impl<R: BufRead> Read for Decryptor<R> {
     /// routine will read in multiples of block size to avoid needless buffering of data, and so it
     /// is normal for it to read less than the buffer size if the buffer is not a multiple of the
     /// block size.
-    fn read(&mut self, mut buf: &mut [u8]) -> Result<usize, Error> {
-        self.inner.read(&mut buf)
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+
+    /// Reads decrypted data into `bufs`, decrypting it in a single pass.
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Error> {
+        self.inner.read_vectored(bufs)
+    }
+}
+
+impl<R: BufRead> BufRead for Decryptor<R> {
+    /// Returns the next run of decrypted data, decrypting more of the underlying ciphertext into
+    /// an internal buffer once everything previously returned has been consumed. This allows e.g.
+    /// `read_line()` or `lines()` to be used on the plaintext without wrapping the `Decryptor` in
+    /// a `BufReader`.
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+impl<R: BufRead + Seek> Seek for Decryptor<R> {
+    /// Seeks to an offset in the decrypted plaintext, without decrypting everything preceding it.
+    ///
+    /// Seeking is only supported with CTR mode ciphers (e.g. `Cipher::aes_256_ctr()`), for which
+    /// the position in the plaintext and ciphertext are one and the same. An error with kind
+    /// [`ErrorKind::Unsupported`] is returned for all other ciphers.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.inner.seek(pos)
+    }
+
+    fn stream_position(&mut self) -> Result<u64, Error> {
+        Ok(self.inner.position)
+    }
+}
+
+/// An authenticated encrypting stream adapter that encrypts what it reads
+///
+/// `bufread::AeadEncryptor` is the AEAD counterpart to [`bufread::Encryptor`](Encryptor), for use
+/// with authenticated ciphers such as `Cipher::aes_256_gcm()` or `Cipher::chacha20_poly1305()`.
+/// Bytes read out of `bufread::AeadEncryptor` are the encrypted contents of the underlying stream,
+/// followed by the 16-byte authentication tag once the underlying stream has been exhausted.
+pub struct AeadEncryptor<R: BufRead> {
+    inner: Cryptostream<R>,
+}
+
+impl<R: BufRead> AeadEncryptor<R> {
+    /// Creates a new `AeadEncryptor`, authenticating the associated data `aad` (which may be
+    /// empty) along with the ciphertext.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_aead(Mode::Encrypt, reader, cipher, key, iv, aad)?,
+        })
+    }
+
+    /// Creates a new `AeadEncryptor` which reads up to `capacity` bytes (rather than 8 KiB) from
+    /// `reader` at a time.
+    pub fn with_capacity(
+        capacity: usize,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_aead_buffered(
+                Mode::Encrypt,
+                reader,
+                cipher,
+                key,
+                iv,
+                aad,
+                capacity,
+            )?,
+        })
+    }
+
+    /// Creates a new `AeadEncryptor` which commits to `key`, its output beginning with the
+    /// commitment read back by [`AeadDecryptor::with_key_commitment()`], which fails before
+    /// decrypting anything if given any other key.
+    pub fn with_key_commitment(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let (key, commitment) = commit::derive(cipher, key, iv)?;
+        let mut inner = Cryptostream::new_aead(Mode::Encrypt, reader, cipher, &key, iv, aad)?;
+        inner.prefix = commitment.to_vec();
+
+        Ok(Self { inner })
+    }
+
+    pub fn finish(self) -> R {
+        self.inner.finish()
+    }
+}
+
+impl<R: BufRead> Read for AeadEncryptor<R> {
+    /// Reads encrypted data out of the underlying plaintext, followed by the authentication tag
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+}
+
+/// An authenticated decrypting stream adapter that decrypts what it reads
+///
+/// `bufread::AeadDecryptor` is the AEAD counterpart to [`bufread::Decryptor`](Decryptor), for use
+/// with authenticated ciphers such as `Cipher::aes_256_gcm()` or `Cipher::chacha20_poly1305()`.
+/// The underlying stream must contain the ciphertext followed by its 16-byte authentication tag,
+/// which is verified once the end of the stream is reached.
+///
+/// Note that decrypted bytes are returned as they are read and before the tag has been verified;
+/// the plaintext must not be trusted until `read()` has returned zero, signalling the successful
+/// verification of the tag. A failed verification is reported as an error with kind
+/// [`ErrorKind::InvalidData`](std::io::ErrorKind::InvalidData).
+pub struct AeadDecryptor<R: BufRead> {
+    inner: Cryptostream<R>,
+}
+
+impl<R: BufRead> AeadDecryptor<R> {
+    /// Creates a new `AeadDecryptor`, authenticating the associated data `aad` (which must match
+    /// that provided when encrypting) along with the ciphertext.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_aead(Mode::Decrypt, reader, cipher, key, iv, aad)?,
+        })
+    }
+
+    /// Creates a new `AeadDecryptor` which reads up to `capacity` bytes (rather than 8 KiB) from
+    /// `reader` at a time.
+    pub fn with_capacity(
+        capacity: usize,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_aead_buffered(
+                Mode::Decrypt,
+                reader,
+                cipher,
+                key,
+                iv,
+                aad,
+                capacity,
+            )?,
+        })
+    }
+
+    /// Creates a new `AeadDecryptor` for ciphertext produced by an encryptor created with
+    /// `with_key_commitment()`, reading the commitment from the start of `reader` and checking it
+    /// against `key` before anything is decrypted. Fails with
+    /// [`Error::KeyCommitmentMismatch`](crate::Error::KeyCommitmentMismatch) if they don't match.
+    pub fn with_key_commitment(
+        mut reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let (key, expected) = commit::derive(cipher, key, iv)?;
+        let mut commitment = [0u8; COMMITMENT_LEN];
+        reader
+            .read_exact(&mut commitment)
+            .map_err(error::reading_header)?;
+        commit::verify(&expected, &commitment)?;
+
+        Self::new(reader, cipher, &key, iv, aad)
+    }
+
+    pub fn finish(self) -> R {
+        self.inner.finish()
+    }
+}
+
+impl<R: BufRead> Read for AeadDecryptor<R> {
+    /// Reads decrypted data out of the underlying ciphertext, verifying the authentication tag
+    /// once the end of the stream has been reached.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+}
+
+/// An encrypting stream adapter that encrypts what it reads, then authenticates it with a MAC
+///
+/// `bufread::EtmEncryptor` is the encrypt-then-MAC counterpart to [`bufread::Encryptor`](Encryptor),
+/// for authenticating the output of unauthenticated ciphers such as `Cipher::aes_256_cbc()`. Bytes
+/// read out of `bufread::EtmEncryptor` are the encrypted contents of the underlying stream,
+/// followed by a 32-byte HMAC-SHA256 tag over the IV and the ciphertext.
+pub struct EtmEncryptor<R: BufRead> {
+    inner: Cryptostream<R>,
+}
+
+impl<R: BufRead> EtmEncryptor<R> {
+    /// Creates a new `EtmEncryptor`, computing the MAC with `mac_key`, which must be independent
+    /// of `key`.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_etm(Mode::Encrypt, reader, cipher, key, mac_key, iv)?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.inner.finish()
+    }
+}
+
+impl<R: BufRead> Read for EtmEncryptor<R> {
+    /// Reads encrypted data out of the underlying plaintext, followed by the MAC
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+}
+
+/// A decrypting stream adapter that verifies the MAC of what it reads, then decrypts it
+///
+/// `bufread::EtmDecryptor` is the encrypt-then-MAC counterpart to [`bufread::Decryptor`](Decryptor).
+/// The underlying stream must contain the ciphertext followed by its 32-byte HMAC-SHA256 tag,
+/// which is verified once the end of the stream is reached and before the padding of the final
+/// block is checked.
+///
+/// Decrypted bytes are returned as they are read, with only the final block (for block ciphers)
+/// withheld until the MAC has been verified, so the plaintext must not be trusted until `read()`
+/// has returned zero. Use [`buffered()`](EtmDecryptor::buffered) to withhold all plaintext until
+/// then instead. A failed verification is reported as an error with kind
+/// [`ErrorKind::InvalidData`](std::io::ErrorKind::InvalidData).
+pub struct EtmDecryptor<R: BufRead> {
+    inner: Cryptostream<R>,
+    /// Only set for buffered decryptors.
+    buffered: Option<Buffered>,
+}
+
+/// The plaintext of a buffered `EtmDecryptor`.
+enum Buffered {
+    Unverified,
+    Verified(Cursor<Secret<Vec<u8>>>),
+    /// Reading the ciphertext failed with the given kind of error, leaving the decryptor unusable.
+    Failed(ErrorKind),
+}
+
+impl<R: BufRead> EtmDecryptor<R> {
+    /// Creates a new `EtmDecryptor`, verifying the MAC with `mac_key`.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_etm(Mode::Decrypt, reader, cipher, key, mac_key, iv)?,
+            buffered: None,
+        })
+    }
+
+    /// Creates a new `EtmDecryptor` which reads and verifies the entire stream, holding its
+    /// plaintext in memory, before returning any of it.
+    pub fn buffered(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            buffered: Some(Buffered::Unverified),
+            ..Self::new(reader, cipher, key, mac_key, iv)?
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.inner.finish()
+    }
+}
+
+impl<R: BufRead> Read for EtmDecryptor<R> {
+    /// Reads decrypted data out of the underlying ciphertext, verifying the MAC once the end of
+    /// the stream has been reached.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        match &mut self.buffered {
+            None => self.inner.read(buf),
+            Some(Buffered::Verified(plaintext)) => plaintext.read(buf),
+            Some(Buffered::Failed(kind)) => Err(Error::new(
+                *kind,
+                "The ciphertext could not be read and verified!",
+            )),
+            Some(Buffered::Unverified) => {
+                let mut plaintext: Secret<Vec<u8>> = Secret::default();
+                match plaintext.read_to_end(&mut self.inner) {
+                    Ok(_) => self.buffered = Some(Buffered::Verified(Cursor::new(plaintext))),
+                    Err(e) => {
+                        self.buffered = Some(Buffered::Failed(e.kind()));
+                        return Err(e);
+                    }
+                }
+                self.read(buf)
+            }
+        }
+    }
+}
+
+struct SegmentedCryptostream<R: BufRead, S: Segments = Segmenter> {
+    reader: R,
+    segmenter: S,
+    /// The input accumulated towards the next segment.
+    input: Secret<Vec<u8>>,
+    /// The result of processing the last segment, which is drained by `read()`.
+    output: Secret<Vec<u8>>,
+    output_index: usize,
+    /// The number of bytes returned by `read()` so far, or the position seeked to.
+    position: u64,
+    /// The number of bytes at the start of the next segment to skip over after seeking.
+    skip: usize,
+    /// The offset of the first segment in the underlying stream, following any commitment.
+    offset: u64,
+}
+
+impl<R: BufRead> SegmentedCryptostream<R> {
+    pub fn new(
+        mode: Mode,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        let segmenter = Segmenter::new(mode, cipher, key, nonce_prefix, segment_size)?;
+        Ok(SegmentedCryptostream::with_segmenter(reader, segmenter))
+    }
+}
+
+impl<R: BufRead, S: Segments> SegmentedCryptostream<R, S> {
+    fn with_segmenter(reader: R, segmenter: S) -> Self {
+        Self {
+            reader,
+            input: Secret::new(Vec::with_capacity(segmenter.input_len())),
+            output: Secret::default(),
+            output_index: 0,
+            position: 0,
+            skip: 0,
+            offset: 0,
+            segmenter,
+        }
+    }
+
+    pub fn finish(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead + Seek> SegmentedCryptostream<R> {
+    /// Repositions a segmented decrypting cryptostream at the start of the segment containing the
+    /// new position. The segment is authenticated in its entirety on the next read, after which
+    /// the bytes preceding the new position are skipped.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        let segment_size = self.segmenter.segment_size() as u64;
+        let segment_len = self.segmenter.input_len() as u64;
+
+        let position = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(n) => self.position.checked_add_signed(n),
+            SeekFrom::End(n) => {
+                // Every segment but the last is full, and the last contains at least its tag.
+                let len = (self.reader.seek(SeekFrom::End(0))?)
+                    .checked_sub(self.offset)
+                    .ok_or_else(aead::truncated)?;
+                let segments = std::cmp::max(1, len.div_ceil(segment_len));
+                let tags = segments * TAG_LEN as u64;
+                if len < tags {
+                    return Err(aead::truncated());
+                }
+                (len - tags).checked_add_signed(n)
+            }
+        }
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid seek to a negative position!",
+            )
+        })?;
+
+        // When seeking to a segment boundary, start from the end of the preceding segment instead
+        // so that seeking to the end of the stream does not attempt to read a segment past it.
+        let (segment, skip) = match (position / segment_size, position % segment_size) {
+            (segment, 0) if segment > 0 => (segment - 1, segment_size),
+            (segment, offset) => (segment, offset),
+        };
+        let segment = u32::try_from(segment).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid seek past the end of the stream!",
+            )
+        })?;
+
+        self.reader
+            .seek(SeekFrom::Start(self.offset + segment as u64 * segment_len))?;
+        self.segmenter.seek(segment);
+        self.input.clear();
+        self.output.clear();
+        self.output_index = 0;
+        self.skip = skip as usize;
+        self.position = position;
+
+        Ok(position)
+    }
+}
+
+impl<R: BufRead, S: Segments> Read for SegmentedCryptostream<R, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        loop {
+            if self.output_index < self.output.len() {
+                let len = std::cmp::min(buf.len(), self.output.len() - self.output_index);
+                buf[..len].copy_from_slice(&self.output[self.output_index..][..len]);
+                self.output_index += len;
+                self.position += len as u64;
+                return Ok(len);
+            }
+            if self.segmenter.is_finished() {
+                return Ok(0);
+            }
+
+            // Accumulate a complete segment, or whatever remains of the stream. It is safe to
+            // bubble up ErrorKind::Interrupted as our progress is persisted in `self.input`.
+            let input_len = self.segmenter.input_len();
+            while self.input.len() < input_len {
+                let available = self.reader.fill_buf()?;
+                if available.is_empty() {
+                    break;
+                }
+                let len = std::cmp::min(available.len(), input_len - self.input.len());
+                self.input.extend_from_slice(&available[..len]);
+                self.reader.consume(len);
+            }
+
+            // A complete segment is only the last if nothing follows it.
+            let last = self.input.len() < input_len || self.reader.fill_buf()?.is_empty();
+
+            self.output.clear();
+            self.segmenter
+                .process(&self.input, last, &mut self.output)?;
+            self.input.clear();
+            self.output_index = std::cmp::min(self.skip, self.output.len());
+            self.skip = 0;
+        }
+    }
+}
+
+/// An encrypting stream adapter that encrypts what it reads in authenticated segments
+///
+/// `bufread::SegmentedEncryptor` is a stream adapter that sits atop a plaintext [`BufRead`]
+/// source. Bytes read out of `bufread::SegmentedEncryptor` are the contents of the underlying
+/// stream split into fixed-size segments, each sealed with an AEAD cipher and followed by its
+/// authentication tag. See [`write::SegmentedEncryptor`](crate::write::SegmentedEncryptor) for
+/// details.
+pub struct SegmentedEncryptor<R: BufRead> {
+    inner: SegmentedCryptostream<R>,
+}
+
+impl<R: BufRead> SegmentedEncryptor<R> {
+    /// Creates a new `SegmentedEncryptor` with the default segment size of 64 KiB.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_segment_size(reader, cipher, key, nonce_prefix, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Creates a new `SegmentedEncryptor` with the default segment size which commits to `key`,
+    /// its output beginning with the commitment read back by
+    /// [`SegmentedDecryptor::with_key_commitment()`], which fails before decrypting anything if
+    /// given any other key.
+    pub fn with_key_commitment(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_key_commitment_and_segment_size(
+            reader,
+            cipher,
+            key,
+            nonce_prefix,
+            DEFAULT_SEGMENT_SIZE,
+        )
+    }
+
+    /// Creates a new `SegmentedEncryptor` which commits to `key` as
+    /// [`with_key_commitment()`](Self::with_key_commitment) does, sealing `segment_size` bytes of
+    /// plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_key_commitment_and_segment_size(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        let (key, commitment) = commit::derive(cipher, key, nonce_prefix)?;
+        let mut encryptor =
+            Self::with_segment_size(reader, cipher, &key, nonce_prefix, segment_size)?;
+        encryptor.inner.output.extend_from_slice(&commitment);
+
+        Ok(encryptor)
+    }
+
+    /// Creates a new `SegmentedEncryptor` sealing `segment_size` bytes of plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_segment_size(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: SegmentedCryptostream::new(
+                Mode::Encrypt,
+                reader,
+                cipher,
+                key,
+                nonce_prefix,
+                segment_size,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.inner.finish()
+    }
+}
+
+impl<R: BufRead> Read for SegmentedEncryptor<R> {
+    /// Reads sealed segments out of the underlying plaintext
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+}
+
+/// A decrypting stream adapter that decrypts segmented ciphertext as it is read
+///
+/// `bufread::SegmentedDecryptor` is a stream adapter that sits atop a [`BufRead`] source of
+/// ciphertext produced by a `SegmentedEncryptor`. Each segment is authenticated in its entirety
+/// before any of its plaintext is returned from `read()`, so unauthenticated plaintext is never
+/// released. Reordered or tampered segments are reported as an error with kind
+/// [`ErrorKind::InvalidData`], while a stream cut off at a segment boundary (or within the tag of
+/// its last segment) is reported as [`Error::Truncated`](crate::Error::Truncated) with kind
+/// [`ErrorKind::UnexpectedEof`].
+pub struct SegmentedDecryptor<R: BufRead> {
+    inner: SegmentedCryptostream<R>,
+}
+
+impl<R: BufRead> SegmentedDecryptor<R> {
+    /// Creates a new `SegmentedDecryptor` for ciphertext sealed with the default segment size of
+    /// 64 KiB.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_segment_size(reader, cipher, key, nonce_prefix, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Creates a new `SegmentedDecryptor` for ciphertext produced by an encryptor created with
+    /// `with_key_commitment()`, reading the commitment from the start of `reader` and checking it
+    /// against `key` before any segment is decrypted. Fails with
+    /// [`Error::KeyCommitmentMismatch`](crate::Error::KeyCommitmentMismatch) if they don't match.
+    pub fn with_key_commitment(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_key_commitment_and_segment_size(
+            reader,
+            cipher,
+            key,
+            nonce_prefix,
+            DEFAULT_SEGMENT_SIZE,
+        )
+    }
+
+    /// Creates a new `SegmentedDecryptor` which checks the key commitment as
+    /// [`with_key_commitment()`](Self::with_key_commitment) does, for ciphertext sealed with
+    /// `segment_size` bytes of plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_key_commitment_and_segment_size(
+        mut reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        let (key, expected) = commit::derive(cipher, key, nonce_prefix)?;
+        let mut commitment = [0u8; COMMITMENT_LEN];
+        reader
+            .read_exact(&mut commitment)
+            .map_err(error::reading_header)?;
+        commit::verify(&expected, &commitment)?;
+
+        let mut decryptor =
+            Self::with_segment_size(reader, cipher, &key, nonce_prefix, segment_size)?;
+        decryptor.inner.offset = COMMITMENT_LEN as u64;
+        Ok(decryptor)
+    }
+
+    /// Creates a new `SegmentedDecryptor` for ciphertext sealed with `segment_size` bytes of
+    /// plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_segment_size(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: SegmentedCryptostream::new(
+                Mode::Decrypt,
+                reader,
+                cipher,
+                key,
+                nonce_prefix,
+                segment_size,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.inner.finish()
+    }
+}
+
+impl<R: BufRead> Read for SegmentedDecryptor<R> {
+    /// Reads authenticated plaintext out of the underlying segmented ciphertext
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+}
+
+impl<R: BufRead + Seek> Seek for SegmentedDecryptor<R> {
+    /// Seeks to an offset in the decrypted plaintext, only decrypting the segment containing it.
+    ///
+    /// The segment containing the new position is still authenticated in its entirety before any
+    /// of it is returned. Seeking past the end of the plaintext is permitted, but subsequent reads
+    /// will fail as there is no segment there to authenticate.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.inner.seek(pos)
+    }
+
+    fn stream_position(&mut self) -> Result<u64, Error> {
+        Ok(self.inner.position)
+    }
+}
+
+/// An encrypting stream adapter that encrypts what it reads as a libsodium secretstream
+///
+/// `bufread::SecretStreamEncryptor` is a stream adapter that sits atop a plaintext [`BufRead`]
+/// source. Bytes read out of `bufread::SecretStreamEncryptor` are the secretstream header followed
+/// by the contents of the underlying stream encrypted a chunk at a time. See
+/// [`write::SecretStreamEncryptor`](crate::write::SecretStreamEncryptor) for details.
+pub struct SecretStreamEncryptor<R: BufRead> {
+    inner: SegmentedCryptostream<R, Chunker>,
+}
+
+impl<R: BufRead> SecretStreamEncryptor<R> {
+    /// Creates a new `SecretStreamEncryptor` with the default chunk size of 4 KiB.
+    pub fn new(reader: R, key: &[u8]) -> Result<Self, crate::Error> {
+        Self::with_chunk_size(reader, key, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new `SecretStreamEncryptor` encrypting `chunk_size` bytes of plaintext per
+    /// message.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(reader: R, key: &[u8], chunk_size: usize) -> Result<Self, crate::Error> {
+        let chunker = Chunker::new(Mode::Encrypt, key, chunk_size)?;
+        Ok(Self {
+            inner: SegmentedCryptostream::with_segmenter(reader, chunker),
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.inner.finish()
+    }
+}
+
+impl<R: BufRead> Read for SecretStreamEncryptor<R> {
+    /// Reads encrypted chunks out of the underlying plaintext
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
+    }
+}
+
+/// A decrypting stream adapter that decrypts a libsodium secretstream as it is read
+///
+/// `bufread::SecretStreamDecryptor` is a stream adapter that sits atop a [`BufRead`] source of a
+/// secretstream encrypted in fixed-size chunks. Each chunk is authenticated in its entirety before
+/// any of its plaintext is returned from `read()`, so unauthenticated plaintext is never released.
+/// Tampered or reordered chunks are reported as an error with kind [`ErrorKind::InvalidData`],
+/// while a stream which ends without a chunk tagged
+/// [`Tag::Final`](crate::secretstream::Tag::Final) is reported as
+/// [`Error::Truncated`](crate::Error::Truncated) with kind [`ErrorKind::UnexpectedEof`].
+pub struct SecretStreamDecryptor<R: BufRead> {
+    inner: SegmentedCryptostream<R, Chunker>,
+}
+
+impl<R: BufRead> SecretStreamDecryptor<R> {
+    /// Creates a new `SecretStreamDecryptor` for a secretstream encrypted with the default chunk
+    /// size of 4 KiB.
+    pub fn new(reader: R, key: &[u8]) -> Result<Self, crate::Error> {
+        Self::with_chunk_size(reader, key, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new `SecretStreamDecryptor` for a secretstream encrypted with `chunk_size` bytes
+    /// of plaintext per message.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(reader: R, key: &[u8], chunk_size: usize) -> Result<Self, crate::Error> {
+        let chunker = Chunker::new(Mode::Decrypt, key, chunk_size)?;
+        Ok(Self {
+            inner: SegmentedCryptostream::with_segmenter(reader, chunker),
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.inner.finish()
+    }
+}
+
+impl<R: BufRead> Read for SecretStreamDecryptor<R> {
+    /// Reads authenticated plaintext out of the underlying secretstream
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read(buf)
     }
 }
@@ -0,0 +1,266 @@
+//! The identifiers of the ciphers the cryptostreams encrypt and decrypt with.
+
+/// A symmetric cipher, along with its key length and mode of operation.
+///
+/// Ciphers are identified by a `Cipher` regardless of the [`backend`](crate::backend) in use, so
+/// that switching backends requires no changes to any code using the cryptostreams. Its
+/// constructors are named after (and behave like) those of `openssl::symm::Cipher`. With the
+/// `openssl` feature enabled, any `openssl::symm::Cipher` can also be converted into a `Cipher`
+/// with `Cipher::from()`, leaving the cipher to the OpenSSL backend to implement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cipher(pub(crate) Id);
+
+/// The ciphers identified by a [`Cipher`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Id {
+    Aes128Ecb,
+    Aes192Ecb,
+    Aes256Ecb,
+    Aes128Cbc,
+    Aes192Cbc,
+    Aes256Cbc,
+    Aes128Ctr,
+    Aes192Ctr,
+    Aes256Ctr,
+    Aes128Cfb8,
+    Aes192Cfb8,
+    Aes256Cfb8,
+    Aes128Cfb128,
+    Aes192Cfb128,
+    Aes256Cfb128,
+    Aes128Ofb,
+    Aes192Ofb,
+    Aes256Ofb,
+    Aes128Gcm,
+    Aes192Gcm,
+    Aes256Gcm,
+    Aes128Ccm,
+    Aes192Ccm,
+    Aes256Ccm,
+    Aes128Ocb,
+    Aes192Ocb,
+    Aes256Ocb,
+    Aes128GcmSiv,
+    Aes256GcmSiv,
+    ChaCha20,
+    ChaCha20Poly1305,
+    /// Any other cipher OpenSSL provides, identified by its NID.
+    #[cfg(feature = "openssl")]
+    Other(openssl::nid::Nid),
+}
+
+/// Whether a cipher encrypts or decrypts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Encrypt,
+    Decrypt,
+}
+
+macro_rules! constructors {
+    ($($(#[$attr:meta])* $name:ident => $id:ident,)*) => {
+        $(
+            $(#[$attr])*
+            pub const fn $name() -> Self {
+                Cipher(Id::$id)
+            }
+        )*
+
+        /// Every cipher with a public constructor.
+        #[cfg(feature = "openssl")]
+        const NAMED: &'static [Id] = &[$(Id::$id,)*];
+    };
+}
+
+impl Cipher {
+    constructors! {
+        aes_128_ecb => Aes128Ecb,
+        aes_192_ecb => Aes192Ecb,
+        aes_256_ecb => Aes256Ecb,
+        aes_128_cbc => Aes128Cbc,
+        aes_192_cbc => Aes192Cbc,
+        aes_256_cbc => Aes256Cbc,
+        aes_128_ctr => Aes128Ctr,
+        aes_192_ctr => Aes192Ctr,
+        aes_256_ctr => Aes256Ctr,
+        aes_128_cfb8 => Aes128Cfb8,
+        aes_192_cfb8 => Aes192Cfb8,
+        aes_256_cfb8 => Aes256Cfb8,
+        aes_128_cfb128 => Aes128Cfb128,
+        aes_192_cfb128 => Aes192Cfb128,
+        aes_256_cfb128 => Aes256Cfb128,
+        aes_128_ofb => Aes128Ofb,
+        aes_192_ofb => Aes192Ofb,
+        aes_256_ofb => Aes256Ofb,
+        aes_128_gcm => Aes128Gcm,
+        aes_192_gcm => Aes192Gcm,
+        aes_256_gcm => Aes256Gcm,
+        aes_128_ccm => Aes128Ccm,
+        aes_192_ccm => Aes192Ccm,
+        aes_256_ccm => Aes256Ccm,
+        aes_128_ocb => Aes128Ocb,
+        aes_192_ocb => Aes192Ocb,
+        aes_256_ocb => Aes256Ocb,
+        chacha20 => ChaCha20,
+        chacha20_poly1305 => ChaCha20Poly1305,
+    }
+
+    /// AES-GCM-SIV with a 128-bit key, which is public as [`crate::siv::aes_128_gcm_siv()`] so
+    /// that its availability can be checked.
+    pub(crate) const fn aes_128_gcm_siv() -> Self {
+        Cipher(Id::Aes128GcmSiv)
+    }
+
+    /// AES-GCM-SIV with a 256-bit key, which is public as [`crate::siv::aes_256_gcm_siv()`].
+    pub(crate) const fn aes_256_gcm_siv() -> Self {
+        Cipher(Id::Aes256GcmSiv)
+    }
+
+    /// The length of the key, in bytes.
+    pub fn key_len(&self) -> usize {
+        match self.0 {
+            Id::Aes128Ecb
+            | Id::Aes128Cbc
+            | Id::Aes128Ctr
+            | Id::Aes128Cfb8
+            | Id::Aes128Cfb128
+            | Id::Aes128Ofb
+            | Id::Aes128Gcm
+            | Id::Aes128Ccm
+            | Id::Aes128Ocb
+            | Id::Aes128GcmSiv => 16,
+            Id::Aes192Ecb
+            | Id::Aes192Cbc
+            | Id::Aes192Ctr
+            | Id::Aes192Cfb8
+            | Id::Aes192Cfb128
+            | Id::Aes192Ofb
+            | Id::Aes192Gcm
+            | Id::Aes192Ccm
+            | Id::Aes192Ocb => 24,
+            Id::Aes256Ecb
+            | Id::Aes256Cbc
+            | Id::Aes256Ctr
+            | Id::Aes256Cfb8
+            | Id::Aes256Cfb128
+            | Id::Aes256Ofb
+            | Id::Aes256Gcm
+            | Id::Aes256Ccm
+            | Id::Aes256Ocb
+            | Id::Aes256GcmSiv
+            | Id::ChaCha20
+            | Id::ChaCha20Poly1305 => 32,
+            #[cfg(feature = "openssl")]
+            Id::Other(_) => self.to_openssl().map_or(0, |c| c.key_len()),
+        }
+    }
+
+    /// The length of the IV, in bytes, or `None` if the cipher takes no IV (i.e. ECB mode). For
+    /// the AEAD ciphers, which accept nonces of other lengths too, this is the default length.
+    pub fn iv_len(&self) -> Option<usize> {
+        match self.0 {
+            Id::Aes128Ecb | Id::Aes192Ecb | Id::Aes256Ecb => None,
+            Id::Aes128Gcm
+            | Id::Aes192Gcm
+            | Id::Aes256Gcm
+            | Id::Aes128Ccm
+            | Id::Aes192Ccm
+            | Id::Aes256Ccm
+            | Id::Aes128Ocb
+            | Id::Aes192Ocb
+            | Id::Aes256Ocb
+            | Id::Aes128GcmSiv
+            | Id::Aes256GcmSiv
+            | Id::ChaCha20Poly1305 => Some(12),
+            #[cfg(feature = "openssl")]
+            Id::Other(_) => self.to_openssl().and_then(|c| c.iv_len()),
+            _ => Some(16),
+        }
+    }
+
+    /// The block size of the cipher, or 1 if it operates on a byte at a time (e.g. CTR mode).
+    pub fn block_size(&self) -> usize {
+        match self.0 {
+            Id::Aes128Ecb
+            | Id::Aes192Ecb
+            | Id::Aes256Ecb
+            | Id::Aes128Cbc
+            | Id::Aes192Cbc
+            | Id::Aes256Cbc
+            | Id::Aes128Ocb
+            | Id::Aes192Ocb
+            | Id::Aes256Ocb => 16,
+            #[cfg(feature = "openssl")]
+            Id::Other(_) => self.to_openssl().map_or(1, |c| c.block_size()),
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl Cipher {
+    /// The equivalent `openssl::symm::Cipher`, or `None` if the linked OpenSSL doesn't provide
+    /// it.
+    pub(crate) fn to_openssl(self) -> Option<openssl::symm::Cipher> {
+        use openssl::nid::Nid;
+        use openssl::symm::Cipher as C;
+
+        let cipher = match self.0 {
+            Id::Aes128Ecb => C::aes_128_ecb(),
+            Id::Aes192Ecb => C::aes_192_ecb(),
+            Id::Aes256Ecb => C::aes_256_ecb(),
+            Id::Aes128Cbc => C::aes_128_cbc(),
+            Id::Aes192Cbc => C::aes_192_cbc(),
+            Id::Aes256Cbc => C::aes_256_cbc(),
+            Id::Aes128Ctr => C::aes_128_ctr(),
+            Id::Aes192Ctr => C::aes_192_ctr(),
+            Id::Aes256Ctr => C::aes_256_ctr(),
+            Id::Aes128Cfb8 => C::aes_128_cfb8(),
+            Id::Aes192Cfb8 => C::aes_192_cfb8(),
+            Id::Aes256Cfb8 => C::aes_256_cfb8(),
+            Id::Aes128Cfb128 => C::aes_128_cfb128(),
+            Id::Aes192Cfb128 => C::aes_192_cfb128(),
+            Id::Aes256Cfb128 => C::aes_256_cfb128(),
+            Id::Aes128Ofb => C::aes_128_ofb(),
+            Id::Aes192Ofb => C::aes_192_ofb(),
+            Id::Aes256Ofb => C::aes_256_ofb(),
+            Id::Aes128Gcm => C::aes_128_gcm(),
+            Id::Aes192Gcm => C::aes_192_gcm(),
+            Id::Aes256Gcm => C::aes_256_gcm(),
+            Id::Aes128Ccm => C::aes_128_ccm(),
+            Id::Aes192Ccm => C::aes_192_ccm(),
+            Id::Aes256Ccm => C::aes_256_ccm(),
+            Id::Aes128Ocb => return C::from_nid(Nid::AES_128_OCB),
+            Id::Aes192Ocb => return C::from_nid(Nid::AES_192_OCB),
+            Id::Aes256Ocb => return C::from_nid(Nid::AES_256_OCB),
+            Id::Aes128GcmSiv | Id::Aes256GcmSiv => return crate::backend::fetch_gcm_siv(self),
+            Id::ChaCha20 => C::chacha20(),
+            Id::ChaCha20Poly1305 => C::chacha20_poly1305(),
+            Id::Other(nid) => return C::from_nid(nid),
+        };
+
+        Some(cipher)
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl From<openssl::symm::Cipher> for Cipher {
+    fn from(cipher: openssl::symm::Cipher) -> Self {
+        let nid = cipher.nid();
+        let named = Self::NAMED
+            .iter()
+            .map(|&id| Cipher(id))
+            .find(|c| c.to_openssl().map(|c| c.nid()) == Some(nid));
+
+        named.unwrap_or(Cipher(Id::Other(nid)))
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl From<openssl::symm::Mode> for Mode {
+    fn from(mode: openssl::symm::Mode) -> Self {
+        match mode {
+            openssl::symm::Mode::Encrypt => Mode::Encrypt,
+            openssl::symm::Mode::Decrypt => Mode::Decrypt,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! An I/O-agnostic core shared by the asynchronous cryptostreams.
+//!
+//! The asynchronous cryptostreams can't block on the underlying stream the way the `Read` and
+//! `Write` variants do, so the transformation is split out into a `Codec` that is fed input as it
+//! becomes available and buffers the transformed output until the underlying stream is ready to
+//! accept it (or the caller is ready to read it). Partial blocks are retained by the cipher
+//! itself between calls, so no input is ever lost when the underlying stream returns
+//! `Poll::Pending` mid-block.
+
+use crate::backend::StreamCipher;
+use crate::cipher::{Cipher, Mode};
+use crate::padding::{self, Padding};
+use crate::secret::Secret;
+use std::io::Error;
+
+/// The maximum number of input bytes transformed in one go.
+const CHUNK_SIZE: usize = 4096;
+
+pub(crate) struct Codec {
+    crypter: Box<dyn StreamCipher>,
+    cipher: Cipher,
+    output: Secret<Vec<u8>>,
+    output_index: usize,
+    never_used: bool,
+    finalized: bool,
+}
+
+impl Codec {
+    pub fn new(mode: Mode, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
+        let crypter = padding::new_cipher(cipher, mode, key, iv, Padding::default())?;
+
+        Ok(Self {
+            crypter,
+            cipher,
+            output: Secret::new(Vec::with_capacity(CHUNK_SIZE + cipher.block_size())),
+            output_index: 0,
+            never_used: true,
+            finalized: false,
+        })
+    }
+
+    /// The transformed output that has yet to be consumed.
+    pub fn output(&self) -> &[u8] {
+        &self.output[self.output_index..]
+    }
+
+    /// Marks `amt` bytes of the pending output as consumed.
+    pub fn consume(&mut self, amt: usize) {
+        self.output_index = std::cmp::min(self.output_index + amt, self.output.len());
+    }
+
+    pub fn is_finalized(&self) -> bool {
+        self.finalized
+    }
+
+    /// Transforms as much of `input` as possible, returning the number of bytes consumed. Must
+    /// only be called once all previously transformed output has been consumed.
+    pub fn update(&mut self, input: &[u8]) -> Result<usize, Error> {
+        debug_assert!(self.output().is_empty());
+        if self.finalized {
+            return Ok(0);
+        }
+
+        // Crypter::update() requires `output.len() >= input.len() + block_size`
+        let len = std::cmp::min(input.len(), CHUNK_SIZE);
+        self.output.resize(len + self.cipher.block_size(), 0);
+        let written = self.crypter.update(&input[..len], &mut self.output)?;
+        self.output.truncate(written);
+        self.output_index = 0;
+        self.never_used = self.never_used && len == 0;
+
+        Ok(len)
+    }
+
+    /// Finalizes the cipher, padding (or unpadding) the final block. Must only be called once
+    /// all previously transformed output has been consumed.
+    pub fn finalize(&mut self) -> Result<(), Error> {
+        debug_assert!(self.output().is_empty());
+        if self.finalized {
+            return Ok(());
+        }
+        self.finalized = true;
+
+        // Match the behavior of the `Read` and `Write` cryptostreams, which never finalize a
+        // cipher that was never used.
+        self.output.resize(self.cipher.block_size() * 2, 0);
+        let written = match self.never_used {
+            true => 0,
+            false => self.crypter.finalize(&mut self.output)?,
+        };
+        self.output.truncate(written);
+        self.output_index = 0;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+//! Internal support for the key-committing variants of the authenticated cryptostreams.
+//!
+//! AEAD ciphers such as AES-GCM and ChaCha20-Poly1305 are not key-committing: a ciphertext can be
+//! crafted to authenticate under more than one key, which lets an attacker able to submit
+//! ciphertexts for decryption test many candidate keys (or passwords) at once. The committing
+//! variants derive both the key the stream is encrypted with and a 32-byte commitment to it from
+//! the caller's key with HKDF-SHA256, salted with the IV (or nonce prefix), and write the
+//! commitment ahead of the ciphertext. The decryptor derives the commitment in turn and checks it
+//! before decrypting anything, so decrypting with any other key fails straight away with
+//! [`Error::KeyCommitmentMismatch`](crate::Error::KeyCommitmentMismatch).
+
+use crate::backend;
+use crate::cipher::Cipher;
+use crate::error;
+use crate::secret::Secret;
+
+/// The length of the commitment written ahead of the ciphertext.
+pub(crate) const COMMITMENT_LEN: usize = 32;
+
+const KEY_INFO: &[u8] = b"cryptostream key commitment: encryption key";
+const COMMITMENT_INFO: &[u8] = b"cryptostream key commitment: commitment";
+
+/// Derives the key to encrypt (or decrypt) with from `key`, along with the commitment to it,
+/// salted with `salt`.
+pub(crate) fn derive(
+    cipher: Cipher,
+    key: &[u8],
+    salt: &[u8],
+) -> Result<(Secret<Vec<u8>>, [u8; COMMITMENT_LEN]), crate::Error> {
+    // The derived key is always the right length, so check the one we were given instead.
+    error::check_lengths(cipher, key, salt, true)?;
+
+    let mut derived = Secret::new(vec![0u8; key.len()]);
+    backend::hkdf_sha256(key, salt, KEY_INFO, &mut derived)?;
+    let mut commitment = [0u8; COMMITMENT_LEN];
+    backend::hkdf_sha256(key, salt, COMMITMENT_INFO, &mut commitment)?;
+
+    Ok((derived, commitment))
+}
+
+/// Checks the commitment read from the start of the ciphertext against the one `expected`.
+pub(crate) fn verify(expected: &[u8; COMMITMENT_LEN], actual: &[u8]) -> Result<(), crate::Error> {
+    match backend::constant_time_eq(expected, actual) {
+        true => Ok(()),
+        false => Err(crate::Error::KeyCommitmentMismatch),
+    }
+}
+
+/// The commitment awaited at the start of a ciphertext written to a decryptor, which must be
+/// checked before anything following it is decrypted.
+pub(crate) struct Pending {
+    expected: [u8; COMMITMENT_LEN],
+    received: [u8; COMMITMENT_LEN],
+    len: usize,
+}
+
+impl Pending {
+    pub fn new(expected: [u8; COMMITMENT_LEN]) -> Self {
+        Self {
+            expected,
+            received: [0u8; COMMITMENT_LEN],
+            len: 0,
+        }
+    }
+
+    /// Accumulates the commitment from `buf`, returning the number of bytes consumed. Once the
+    /// commitment is complete it is checked, failing if it doesn't match.
+    pub fn write(&mut self, buf: &[u8]) -> Result<usize, crate::Error> {
+        let len = std::cmp::min(buf.len(), COMMITMENT_LEN - self.len);
+        self.received[self.len..][..len].copy_from_slice(&buf[..len]);
+        self.len += len;
+
+        if self.is_complete() {
+            verify(&self.expected, &self.received)?;
+        }
+        Ok(len)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.len == COMMITMENT_LEN
+    }
+}
@@ -0,0 +1,63 @@
+//! Internal support for seeking within counter (CTR) mode cryptostreams.
+//!
+//! In CTR mode, the keystream for each block is derived by encrypting the IV incremented by the
+//! index of the block, so the cipher can be positioned at any offset into the stream by
+//! recomputing the counter and creating a new cipher from it, then discarding the keystream
+//! preceding the offset within the block.
+
+use crate::backend::{self, StreamCipher};
+use crate::cipher::{Cipher, Id, Mode};
+use crate::secret::Secret;
+
+/// The block size of AES, which is the only block cipher OpenSSL offers in CTR mode.
+const BLOCK_SIZE: u64 = 16;
+
+/// Whether or not `cipher` is a CTR mode cipher supported by [`Counter`].
+pub(crate) fn is_ctr(cipher: Cipher) -> bool {
+    matches!(cipher.0, Id::Aes128Ctr | Id::Aes192Ctr | Id::Aes256Ctr)
+}
+
+/// The state required to recreate a CTR mode cipher at an arbitrary position in the stream.
+pub(crate) struct Counter {
+    cipher: Cipher,
+    mode: Mode,
+    key: Secret<Vec<u8>>,
+    iv: [u8; BLOCK_SIZE as usize],
+}
+
+impl Counter {
+    /// Returns `None` if `cipher` is not a CTR mode cipher.
+    pub fn new(cipher: Cipher, mode: Mode, key: &[u8], iv: &[u8]) -> Option<Self> {
+        if !is_ctr(cipher) || iv.len() != BLOCK_SIZE as usize {
+            return None;
+        }
+
+        let mut counter = [0u8; BLOCK_SIZE as usize];
+        counter.copy_from_slice(iv);
+        Some(Self {
+            cipher,
+            mode,
+            key: Secret::new(key.to_vec()),
+            iv: counter,
+        })
+    }
+
+    /// Creates a cipher that picks up at `position` bytes into the stream.
+    pub fn crypter_at(&self, position: u64) -> Result<Box<dyn StreamCipher>, crate::Error> {
+        // OpenSSL treats the entire IV as a 128-bit big-endian counter.
+        let block = position / BLOCK_SIZE;
+        let iv = u128::from_be_bytes(self.iv).wrapping_add(block as u128);
+        let mut crypter =
+            backend::new_cipher(self.cipher, self.mode, &self.key, &iv.to_be_bytes())?;
+
+        // Advance through the keystream up to the offset within the block.
+        let offset = (position % BLOCK_SIZE) as usize;
+        if offset > 0 {
+            // The discarded output is the keystream itself.
+            let mut discard = Secret::new([0u8; BLOCK_SIZE as usize * 2]);
+            crypter.update(&[0u8; BLOCK_SIZE as usize][..offset], &mut discard[..])?;
+        }
+
+        Ok(crypter)
+    }
+}
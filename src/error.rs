@@ -0,0 +1,208 @@
+//! The error type shared by all cryptostreams.
+
+use crate::backend;
+use crate::cipher::Cipher;
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+/// The errors that may be returned by a cryptostream.
+///
+/// The cryptostream constructors return an [`Error`] directly, while the `Read` and `Write`
+/// implementations (being bound by those traits to `std::io::Error`) wrap it in an I/O error of the
+/// corresponding [`ErrorKind`]. The original `Error` can be recovered from the latter with
+/// [`io::Error::get_ref()`] and `downcast_ref()`, or by converting it back with `Error::from()`:
+///
+/// ```
+/// use cryptostream::{read, Cipher, Error};
+/// use std::io::Read;
+///
+/// let key = [0u8; 16];
+/// let iv = [0u8; 16];
+/// // A ciphertext that decrypts to invalid padding under this key and IV.
+/// let ciphertext = [0u8; 16];
+///
+/// let mut decryptor = read::Decryptor::new(&ciphertext[..], Cipher::aes_128_cbc(), &key, &iv)?;
+/// let err = decryptor.read_to_end(&mut Vec::new()).unwrap_err();
+/// assert!(matches!(Error::from(err), Error::BadPadding));
+/// # Ok::<(), Error>(())
+/// ```
+///
+/// New variants may be added without a major release, so matches on an `Error` must include a
+/// wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The key is not the length required by the cipher
+    InvalidKeyLength,
+    /// The IV is not the length required by the cipher
+    InvalidIvLength,
+    /// The padding of the final block was invalid
+    BadPadding,
+    /// The plaintext is not a whole number of blocks long, as it must be to encrypt it with
+    /// [`Padding::None`](crate::padding::Padding::None)
+    IncompletePlaintext,
+    /// The authentication tag did not match the decrypted ciphertext
+    AuthenticationFailed,
+    /// The ciphertext is committed to a different key than the one it was decrypted with
+    KeyCommitmentMismatch,
+    /// The ciphertext ended before it was complete (e.g. partway through a block)
+    Truncated,
+    /// The cipher does not support the requested operation
+    UnsupportedCipher,
+    /// The header at the start of the ciphertext is malformed
+    InvalidHeader,
+    /// The header at the start of the ciphertext is of a version this release doesn't know
+    UnsupportedVersion(u8),
+    /// The key id recorded in a header is empty or longer than 255 bytes
+    InvalidKeyId,
+    /// The key derivation function recorded in a header is unknown (or not enabled)
+    UnsupportedKdf,
+    /// The key derivation parameters are invalid or exceed the limits accepted by the decryptors
+    InvalidKdfParameters,
+    /// An error reported by OpenSSL
+    #[cfg(feature = "openssl")]
+    Backend(openssl::error::ErrorStack),
+    /// An error reading from or writing to the underlying stream
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidKeyLength => f.write_str("The key length is invalid for this cipher!"),
+            Error::InvalidIvLength => f.write_str("The IV length is invalid for this cipher!"),
+            Error::BadPadding => f.write_str("The padding of the final block is invalid!"),
+            Error::IncompletePlaintext => {
+                f.write_str("The plaintext is not a multiple of the block size!")
+            }
+            Error::AuthenticationFailed => f.write_str("Authentication tag verification failed!"),
+            Error::KeyCommitmentMismatch => {
+                f.write_str("The ciphertext is committed to a different key!")
+            }
+            Error::Truncated => f.write_str("The ciphertext is truncated!"),
+            Error::UnsupportedCipher => {
+                f.write_str("The operation is not supported by this cipher!")
+            }
+            Error::InvalidHeader => f.write_str("The ciphertext header is invalid!"),
+            Error::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "Version {} of the ciphertext header is not supported!",
+                    version
+                )
+            }
+            Error::InvalidKeyId => f.write_str("The key id must be between 1 and 255 bytes long!"),
+            Error::UnsupportedKdf => f.write_str("The key derivation function is not supported!"),
+            Error::InvalidKdfParameters => {
+                f.write_str("The key derivation parameters are invalid or exceed the limits!")
+            }
+            #[cfg(feature = "openssl")]
+            Error::Backend(e) => e.fmt(f),
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "openssl")]
+            Error::Backend(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// The [`ErrorKind`] of the I/O error this error is wrapped in by the `Read` and `Write`
+    /// implementations.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::InvalidKeyLength
+            | Error::InvalidIvLength
+            | Error::InvalidKeyId
+            | Error::InvalidKdfParameters
+            | Error::IncompletePlaintext => ErrorKind::InvalidInput,
+            Error::BadPadding
+            | Error::AuthenticationFailed
+            | Error::KeyCommitmentMismatch
+            | Error::InvalidHeader => ErrorKind::InvalidData,
+            Error::Truncated => ErrorKind::UnexpectedEof,
+            Error::UnsupportedCipher | Error::UnsupportedVersion(_) | Error::UnsupportedKdf => {
+                ErrorKind::Unsupported
+            }
+            #[cfg(feature = "openssl")]
+            Error::Backend(_) => ErrorKind::Other,
+            Error::Io(e) => e.kind(),
+        }
+    }
+}
+
+#[cfg(feature = "openssl")]
+impl From<openssl::error::ErrorStack> for Error {
+    fn from(e: openssl::error::ErrorStack) -> Self {
+        Error::Backend(e)
+    }
+}
+
+impl From<backend::Error> for Error {
+    fn from(e: backend::Error) -> Self {
+        match e {
+            #[cfg(feature = "openssl")]
+            backend::Error::OpenSsl(e) => Error::Backend(e),
+            backend::Error::IncompleteBlock => Error::Truncated,
+            backend::Error::IncompletePlaintext => Error::IncompletePlaintext,
+            backend::Error::BadPadding => Error::BadPadding,
+            backend::Error::AuthenticationFailed => Error::AuthenticationFailed,
+            backend::Error::Unsupported => Error::UnsupportedCipher,
+            backend::Error::Random(e) => Error::Io(e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    /// Unwraps an `Error` previously wrapped in an I/O error, or wraps any other I/O error as
+    /// [`Error::Io`].
+    fn from(e: io::Error) -> Self {
+        match e.get_ref().map(|inner| inner.is::<Error>()) {
+            Some(true) => *e.into_inner().unwrap().downcast::<Error>().unwrap(),
+            _ => Error::Io(e),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            e => io::Error::new(e.kind(), e),
+        }
+    }
+}
+
+/// Converts an error reading the header at the start of a ciphertext, for which hitting the end
+/// of the stream means the ciphertext is truncated.
+pub(crate) fn reading_header(e: io::Error) -> Error {
+    match e.kind() {
+        ErrorKind::UnexpectedEof => Error::Truncated,
+        _ => Error::from(e),
+    }
+}
+
+/// Checks that `key`, and unless the cipher is an AEAD cipher (which accept IVs of varying
+/// lengths) also `iv`, are the lengths required by `cipher`.
+pub(crate) fn check_lengths(
+    cipher: Cipher,
+    key: &[u8],
+    iv: &[u8],
+    aead: bool,
+) -> Result<(), Error> {
+    if key.len() != cipher.key_len() {
+        return Err(Error::InvalidKeyLength);
+    }
+    match cipher.iv_len() {
+        Some(len) if !aead && iv.len() != len => Err(Error::InvalidIvLength),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,109 @@
+//! Internal support for the encrypt-then-MAC cryptostream variants.
+//!
+//! These authenticate the output of an unauthenticated cipher (such as AES-CBC or AES-CTR) with
+//! HMAC-SHA256, keyed separately from the cipher, over the IV followed by the ciphertext. The
+//! resulting MAC is appended to the ciphertext in place of an AEAD tag, so the AEAD machinery in
+//! the `aead` module takes care of appending and withholding it; all that differs is the
+//! `StreamCipher` producing and verifying it, which wraps the underlying cipher.
+
+use crate::backend::{self, HmacSha256, StreamCipher};
+use crate::cipher::{Cipher, Mode};
+use crate::error;
+
+/// The length of the HMAC-SHA256 tag appended to the ciphertext.
+pub(crate) const MAC_LEN: usize = 32;
+
+/// Creates a `StreamCipher` authenticating the ciphertext of `cipher` with HMAC-SHA256 keyed with
+/// `mac_key`, having already fed it the IV.
+pub(crate) fn new_crypter(
+    mode: Mode,
+    cipher: Cipher,
+    key: &[u8],
+    mac_key: &[u8],
+    iv: &[u8],
+) -> Result<Box<dyn StreamCipher>, crate::Error> {
+    error::check_lengths(cipher, key, iv, false)?;
+    let inner = backend::new_cipher(cipher, mode, key, iv)?;
+
+    let mut mac = HmacSha256::new(mac_key)?;
+    mac.update(iv)?;
+
+    Ok(Box::new(EtmCipher {
+        inner,
+        mode,
+        mac,
+        tag: None,
+    }))
+}
+
+struct EtmCipher {
+    inner: Box<dyn StreamCipher>,
+    mode: Mode,
+    mac: HmacSha256,
+    /// When encrypting, the MAC computed by `finalize()`. When decrypting, the expected MAC
+    /// provided by `set_tag()`.
+    tag: Option<[u8; MAC_LEN]>,
+}
+
+impl StreamCipher for EtmCipher {
+    fn block_size(&self) -> usize {
+        self.inner.block_size()
+    }
+
+    fn pad(&mut self, padding: bool) {
+        self.inner.pad(padding)
+    }
+
+    fn aad_update(&mut self, _input: &[u8]) -> Result<(), backend::Error> {
+        Err(backend::Error::Unsupported)
+    }
+
+    fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, backend::Error> {
+        if let Mode::Decrypt = self.mode {
+            self.mac.update(input)?;
+            return self.inner.update(input, output);
+        }
+
+        let written = self.inner.update(input, output)?;
+        self.mac.update(&output[..written])?;
+        Ok(written)
+    }
+
+    fn finalize(&mut self, output: &mut [u8]) -> Result<usize, backend::Error> {
+        if let Mode::Encrypt = self.mode {
+            let written = self.inner.finalize(output)?;
+            self.mac.update(&output[..written])?;
+            self.tag = Some(self.mac.finalize()?);
+            return Ok(written);
+        }
+
+        // Verify the MAC before touching the padding, so that a tampered ciphertext can never be
+        // distinguished by its padding being valid or not.
+        let expected = self.tag.ok_or(backend::Error::AuthenticationFailed)?;
+        if !backend::constant_time_eq(&self.mac.finalize()?, &expected) {
+            return Err(backend::Error::AuthenticationFailed);
+        }
+        self.inner.finalize(output)
+    }
+
+    fn set_tag(&mut self, tag: &[u8]) -> Result<(), backend::Error> {
+        if tag.len() != MAC_LEN {
+            return Err(backend::Error::AuthenticationFailed);
+        }
+
+        let mut expected = [0u8; MAC_LEN];
+        expected.copy_from_slice(tag);
+        self.tag = Some(expected);
+        Ok(())
+    }
+
+    fn get_tag(&self, tag: &mut [u8]) -> Result<(), backend::Error> {
+        match &self.tag {
+            Some(mac) if tag.len() == MAC_LEN => {
+                tag.copy_from_slice(mac);
+                Ok(())
+            }
+            _ => Err(backend::Error::Unsupported),
+        }
+    }
+}
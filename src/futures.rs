@@ -0,0 +1,286 @@
+//! Asynchronous cryptostream types implementing the executor-agnostic
+//! [`futures-io`](https://docs.rs/futures-io) traits (as used by async-std, smol, and others),
+//! providing both encryption and decryption facilities. Requires the `futures` feature.
+//!
+//! Unlike the blocking variants, the direction of the cryptostream is determined by what it wraps
+//! rather than by the module it is found in: wrap an [`AsyncBufRead`] source in a
+//! [`futures::Encryptor`](Encryptor) or [`futures::Decryptor`](Decryptor) to read the encrypted or
+//! decrypted contents out of it via [`AsyncRead`] or [`AsyncBufRead`], or wrap an [`AsyncWrite`]
+//! destination to have the encrypted or decrypted equivalent of whatever is written to the
+//! cryptostream written to it in turn. (An [`AsyncRead`] source can be wrapped in a
+//! `futures::io::BufReader` first.)
+//!
+//! As there is no asynchronous equivalent to `Drop`, the final block is only padded and written
+//! out when the cryptostream is closed via [`AsyncWrite::poll_close()`] (e.g. with
+//! `AsyncWriteExt::close()`). Dropping a writing cryptostream without closing it will result in
+//! truncated output.
+
+use crate::cipher::{Cipher, Mode};
+use crate::codec::Codec;
+use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use pin_project_lite::pin_project;
+use std::io::{Error, ErrorKind};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+pin_project! {
+    struct Cryptostream<T> {
+        #[pin]
+        inner: T,
+        codec: Codec,
+    }
+}
+
+impl<T> Cryptostream<T> {
+    pub fn new(
+        mode: Mode,
+        inner: T,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner,
+            codec: Codec::new(mode, cipher, key, iv)?,
+        })
+    }
+}
+
+impl<T: AsyncBufRead> AsyncBufRead for Cryptostream<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<&[u8], Error>> {
+        let mut this = self.project();
+
+        // Partial blocks are retained by the cipher, so it's safe to bail on `Poll::Pending`.
+        while this.codec.output().is_empty() && !this.codec.is_finalized() {
+            let input = ready!(this.inner.as_mut().poll_fill_buf(cx))?;
+            if input.is_empty() {
+                this.codec.finalize()?;
+            } else {
+                let consumed = this.codec.update(input)?;
+                this.inner.as_mut().consume(consumed);
+            }
+        }
+
+        Poll::Ready(Ok(this.codec.output()))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.project().codec.consume(amt)
+    }
+}
+
+impl<T: AsyncBufRead> AsyncRead for Cryptostream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        let output = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = std::cmp::min(output.len(), buf.len());
+        buf[..len].copy_from_slice(&output[..len]);
+        self.consume(len);
+
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl<T: AsyncWrite> Cryptostream<T> {
+    /// Writes all previously transformed output to the underlying stream.
+    fn poll_drain(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        let mut this = self.project();
+
+        while !this.codec.output().is_empty() {
+            match ready!(this.inner.as_mut().poll_write(cx, this.codec.output()))? {
+                0 => return Poll::Ready(Err(ErrorKind::WriteZero.into())),
+                written => this.codec.consume(written),
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for Cryptostream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Error>> {
+        // The output of the previous write must make it out before we can transform more input,
+        // but the output of this write can wait until the next write, flush, or close.
+        ready!(self.as_mut().poll_drain(cx))?;
+        Poll::Ready(self.project().codec.update(buf))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        ready!(self.as_mut().poll_drain(cx))?;
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        ready!(self.as_mut().poll_drain(cx))?;
+        if !self.codec.is_finalized() {
+            self.as_mut().project().codec.finalize()?;
+            ready!(self.as_mut().poll_drain(cx))?;
+        }
+        self.project().inner.poll_close(cx)
+    }
+}
+
+pin_project! {
+    /// An asynchronous encrypting stream adapter
+    ///
+    /// When wrapping an [`AsyncBufRead`] source, bytes read out of the `Encryptor` are the
+    /// encrypted contents of the underlying stream. When wrapping an [`AsyncWrite`] destination,
+    /// plaintext written to the `Encryptor` is encrypted and written to the underlying stream, and
+    /// the final block is padded and written out when the `Encryptor` is closed.
+    pub struct Encryptor<T> {
+        #[pin]
+        inner: Cryptostream<T>,
+    }
+}
+
+impl<T> Encryptor<T> {
+    pub fn new(inner: T, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new(Mode::Encrypt, inner, cipher, key, iv)?,
+        })
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner.inner
+    }
+
+    /// Returns the wrapped stream. When writing, the `Encryptor` must be closed first or the
+    /// final block will never be written.
+    pub fn into_inner(self) -> T {
+        self.inner.inner
+    }
+}
+
+impl<T: AsyncBufRead> AsyncRead for Encryptor<T> {
+    /// Reads encrypted data out of the underlying plaintext
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncBufRead> AsyncBufRead for Encryptor<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<&[u8], Error>> {
+        self.project().inner.poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.project().inner.consume(amt)
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for Encryptor<T> {
+    /// Writes plaintext to the cryptostream, causing its encrypted contents to be written to the
+    /// underlying stream.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Error>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    /// Flushes the underlying stream but does not pad the final block, as that would prevent
+    /// anything further from being written.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    /// Pads the final block, writes all remaining output, and closes the underlying stream.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+pin_project! {
+    /// An asynchronous decrypting stream adapter
+    ///
+    /// When wrapping an [`AsyncBufRead`] source, bytes read out of the `Decryptor` are the
+    /// decrypted contents of the underlying stream. When wrapping an [`AsyncWrite`] destination,
+    /// ciphertext written to the `Decryptor` is decrypted and written to the underlying stream,
+    /// and the final block is unpadded and written out when the `Decryptor` is closed.
+    pub struct Decryptor<T> {
+        #[pin]
+        inner: Cryptostream<T>,
+    }
+}
+
+impl<T> Decryptor<T> {
+    pub fn new(inner: T, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new(Mode::Decrypt, inner, cipher, key, iv)?,
+        })
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner.inner
+    }
+
+    /// Returns the wrapped stream. When writing, the `Decryptor` must be closed first or the
+    /// final block will never be written.
+    pub fn into_inner(self) -> T {
+        self.inner.inner
+    }
+}
+
+impl<T: AsyncBufRead> AsyncRead for Decryptor<T> {
+    /// Reads decrypted data out of the underlying ciphertext
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncBufRead> AsyncBufRead for Decryptor<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<&[u8], Error>> {
+        self.project().inner.poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.project().inner.consume(amt)
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for Decryptor<T> {
+    /// Writes ciphertext to the cryptostream, causing its decrypted contents to be written to the
+    /// underlying stream.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Error>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    /// Flushes the underlying stream but does not decrypt the final block, as it cannot be
+    /// unpadded until the end of the stream is known.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    /// Unpads the final block, writes all remaining output, and closes the underlying stream.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.project().inner.poll_close(cx)
+    }
+}
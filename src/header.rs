@@ -0,0 +1,168 @@
+//! A self-describing header recording how a stream was encrypted.
+//!
+//! Rather than having to tell a decryptor the cipher and IV out-of-band, an encryptor created with
+//! `with_header` writes a [`Header`] recording both (and, optionally, an identifier for the key)
+//! to the start of the ciphertext, and the decryptors' `auto` constructors read it back and
+//! configure themselves accordingly. Only the key itself need be known to decrypt.
+//!
+//! The header is laid out as follows:
+//!
+//! ```text
+//! magic ("CSTR") || version (1 byte) || cipher id (1 byte)
+//!     || iv length (1 byte) || iv || key id length (1 byte) || key id
+//! ```
+//!
+//! Only version 1 exists so far. The cipher ids are listed in [`Header::new()`]; a key id length
+//! of zero means no key id is present. Note that the header itself is not authenticated.
+
+use crate::cipher::Cipher;
+use crate::error::{self, Error};
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"CSTR";
+const VERSION: u8 = 1;
+
+/// The ciphers which may be recorded in a header, indexed by their id minus one. Ids must never be
+/// reused or reordered once released.
+fn ciphers() -> [Cipher; 13] {
+    [
+        Cipher::aes_128_cbc(),
+        Cipher::aes_192_cbc(),
+        Cipher::aes_256_cbc(),
+        Cipher::aes_128_ctr(),
+        Cipher::aes_192_ctr(),
+        Cipher::aes_256_ctr(),
+        Cipher::aes_128_ofb(),
+        Cipher::aes_192_ofb(),
+        Cipher::aes_256_ofb(),
+        Cipher::aes_128_cfb128(),
+        Cipher::aes_192_cfb128(),
+        Cipher::aes_256_cfb128(),
+        Cipher::chacha20(),
+    ]
+}
+
+/// A header recording the cipher, IV, and optionally the key id of an encrypted stream.
+#[derive(Clone)]
+pub struct Header {
+    cipher: Cipher,
+    cipher_id: u8,
+    iv: Vec<u8>,
+    key_id: Option<Vec<u8>>,
+}
+
+impl Header {
+    /// Creates a header for a stream encrypted with `cipher` and `iv`.
+    ///
+    /// The supported ciphers are AES-128, AES-192, and AES-256 in CBC (ids 1 to 3), CTR (4 to 6),
+    /// OFB (7 to 9), and CFB128 (10 to 12) modes, and ChaCha20 (13). Authenticated ciphers aren't
+    /// supported, as the basic cryptostreams don't handle their tags.
+    pub fn new(cipher: Cipher, iv: &[u8]) -> Result<Self, Error> {
+        let cipher_id = match ciphers().iter().position(|&c| c == cipher) {
+            Some(index) => index as u8 + 1,
+            None => return Err(Error::UnsupportedCipher),
+        };
+        if Some(iv.len()) != cipher.iv_len() {
+            return Err(Error::InvalidIvLength);
+        }
+
+        Ok(Self {
+            cipher,
+            cipher_id,
+            iv: iv.to_vec(),
+            key_id: None,
+        })
+    }
+
+    /// Records `key_id`, identifying the key used to encrypt the stream, in the header. It is
+    /// stored in plain text and may be at most 255 bytes long.
+    pub fn with_key_id(mut self, key_id: &[u8]) -> Result<Self, Error> {
+        if key_id.is_empty() || key_id.len() > u8::MAX as usize {
+            return Err(Error::InvalidKeyId);
+        }
+
+        self.key_id = Some(key_id.to_vec());
+        Ok(self)
+    }
+
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    pub fn iv(&self) -> &[u8] {
+        &self.iv
+    }
+
+    pub fn key_id(&self) -> Option<&[u8]> {
+        self.key_id.as_deref()
+    }
+
+    /// The length of the encoded header.
+    pub fn encoded_len(&self) -> usize {
+        MAGIC.len() + 4 + self.iv.len() + self.key_id().map_or(0, <[u8]>::len)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let key_id = self.key_id().unwrap_or_default();
+
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.cipher_id);
+        bytes.push(self.iv.len() as u8);
+        bytes.extend_from_slice(&self.iv);
+        bytes.push(key_id.len() as u8);
+        bytes.extend_from_slice(key_id);
+        bytes
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    /// Reads a header from the start of `reader`, consuming exactly the bytes making it up.
+    ///
+    /// Fails with [`Error::InvalidHeader`] if `reader` doesn't begin with a header,
+    /// [`Error::UnsupportedVersion`] if the header is of a version other than 1,
+    /// [`Error::UnsupportedCipher`] if it records an unknown cipher, and [`Error::Truncated`] if it
+    /// ends partway through the header.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, Error> {
+        fn bytes<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>, Error> {
+            let mut bytes = vec![0u8; len];
+            reader
+                .read_exact(&mut bytes)
+                .map_err(error::reading_header)?;
+            Ok(bytes)
+        }
+
+        let fixed = bytes(&mut reader, 7)?;
+        if &fixed[..4] != MAGIC {
+            return Err(Error::InvalidHeader);
+        }
+
+        let (version, cipher_id, iv_len) = (fixed[4], fixed[5], fixed[6]);
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let cipher = cipher_id
+            .checked_sub(1)
+            .and_then(|i| ciphers().get(i as usize).copied())
+            .ok_or(Error::UnsupportedCipher)?;
+        if Some(iv_len as usize) != cipher.iv_len() {
+            return Err(Error::InvalidHeader);
+        }
+
+        let iv = bytes(&mut reader, iv_len as usize)?;
+        let key_id = match bytes(&mut reader, 1)?[0] {
+            0 => None,
+            len => Some(bytes(&mut reader, len as usize)?),
+        };
+
+        Ok(Self {
+            cipher,
+            cipher_id,
+            iv,
+            key_id,
+        })
+    }
+}
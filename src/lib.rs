@@ -9,29 +9,131 @@
 //! The most common reasons for using this library:
 //!
 //! * [`read::Decryptor`]: You have an encrypted
-//! `Read` source and you want to transparently decrypt its contents while reading from it (e.g.
-//! you have encrypted data at rest and want to decrypt it into memory).
+//!   `Read` source and you want to transparently decrypt its contents while reading from it (e.g.
+//!   you have encrypted data at rest and want to decrypt it into memory).
 //! * [`write::Encryptor`]:
-//! You have a `Write` instance you want to write the encrypted ciphertext equivalent of some
-//! plaintext you have in memory (e.g. you have plaintext data in memory you want to store it
-//! encrypted).
+//!   You have a `Write` instance you want to write the encrypted ciphertext equivalent of some
+//!   plaintext you have in memory (e.g. you have plaintext data in memory you want to store it
+//!   encrypted).
 //!
 //! Considerably less common use cases:
 //!
 //! * [`read::Encryptor`]: You have a `Read` source containing
-//! plaintext but you want to pull encrypted contents out of it (e.g. you want to encrypt data
-//! stored as plaintext).
+//!   plaintext but you want to pull encrypted contents out of it (e.g. you want to encrypt data
+//!   stored as plaintext).
 //! * [`write::Decryptor`]: You want to write cyphertext to a `Write`
-//! instance and have it pass through the decrypted plaintext to the underlying stream (e.g. you
-//! have cryptotext in memory and want to store it decrypted).
+//!   instance and have it pass through the decrypted plaintext to the underlying stream (e.g. you
+//!   have cryptotext in memory and want to store it decrypted).
 //!
 //! Additionally, the [`bufread`] module provides the [`bufread::Encryptor`] and
 //! [`bufread::Decryptor`] types for encrypting/decrypting plaintext/ciphertext on-the-fly from a
 //! [`BufRead`](std::io::BufRead) source. (There is no need for a `bufwrite` variant.)
+//!
+//! When using an authenticated (AEAD) cipher such as AES-GCM or ChaCha20-Poly1305, use the
+//! `AeadEncryptor` and `AeadDecryptor` variants found in each of the above modules instead (e.g.
+//! [`write::AeadEncryptor`] and [`read::AeadDecryptor`]), which append the authentication tag to
+//! the end of the ciphertext when encrypting and verify it at the end of the stream when
+//! decrypting.
+//!
+//! As the authentication tag of an AEAD cipher can only be verified once the entire ciphertext has
+//! been decrypted, the `AeadDecryptor` types necessarily hand out plaintext before it has been
+//! authenticated. If that is not acceptable, use the `SegmentedEncryptor` and `SegmentedDecryptor`
+//! variants instead, which split the stream into individually authenticated segments (following
+//! the STREAM construction) so that no plaintext is released before it has been verified and
+//! truncation, reordering, or tampering are all detected. Where an unauthenticated cipher such as
+//! AES-CBC must be kept, the `EtmEncryptor` and `EtmDecryptor` variants authenticate its
+//! ciphertext with HMAC-SHA256 (encrypt-then-MAC) under a separate MAC key.
+//!
+//! AES-GCM and ChaCha20-Poly1305 do not commit to their key: a ciphertext can be crafted to
+//! authenticate under many keys at once, which an attacker able to submit ciphertexts for
+//! decryption can use to guess a key (or password) far faster than one attempt at a time. The
+//! `with_key_commitment` constructors of the `Aead` and `Segmented` variants write a commitment
+//! to the key (derived with HKDF-SHA256) ahead of the ciphertext, which the decryptor checks
+//! before decrypting anything, failing with [`Error::KeyCommitmentMismatch`] if given any other
+//! key.
+//!
+//! Reusing the nonce prefix of a segmented stream with AES-GCM or ChaCha20-Poly1305 reveals the
+//! XOR of the plaintexts and allows segments to be forged. Where a nonce prefix may be reused by
+//! accident (e.g. by a VM restored from a snapshot), seal the segments with AES-GCM-SIV from the
+//! [`siv`] module instead, which then only reveals which segments are identical.
+//!
+//! To interoperate with libsodium, the `SecretStreamEncryptor` and `SecretStreamDecryptor` variants
+//! read and write the format of its `crypto_secretstream_xchacha20poly1305` API, encrypted in
+//! fixed-size chunks as in the libsodium documentation. Streams of messages framed some other way
+//! (or carrying `PUSH` and `REKEY` tags) can be handled a message at a time with the
+//! [`secretstream`] module.
+//!
+//! Asynchronous cryptostreams implementing tokio's `AsyncRead`, `AsyncBufRead`, and `AsyncWrite`
+//! traits are available in the `tokio` module when the `async-tokio` feature is enabled, while
+//! their counterparts implementing the executor-agnostic `futures-io` traits are available in the
+//! `futures` module when the `futures` feature is enabled.
+//!
+//! All cryptostreams use OpenSSL by default. Enabling the `rustcrypto` feature switches AES-CBC,
+//! AES-CTR, AES-GCM, AES-GCM-SIV, ChaCha20, and ChaCha20-Poly1305 over to a pure-Rust
+//! implementation instead, without any changes to the cryptostream APIs, as ciphers are identified
+//! by the crate's own [`Cipher`] throughout. OpenSSL handles every other cipher unless the default
+//! `openssl` feature is disabled, in which case the crate builds without OpenSSL at all and those
+//! ciphers are rejected with [`Error::UnsupportedCipher`]. See the [`backend`] module for details.
+//!
+//! Rather than a raw key and IV, the basic encryptors and decryptors can also be keyed with a
+//! password via their `with_password` constructors, which derive the key and IV with PBKDF2,
+//! scrypt, or Argon2id and store the salt in a header at the start of the ciphertext. Their
+//! `with_openssl_enc` constructors read and write the salted format of `openssl enc` instead. See
+//! the [`password`] module for details.
+//!
+//! Encryptors created with `with_header` record the cipher and IV in a header at the start of the
+//! ciphertext, so that decryptors created with `auto` need only be given the key. See the
+//! [`header`] module for details.
+//!
+//! Block cipher modes such as CBC are padded with PKCS#7 by default, while the `with_padding`
+//! constructors of the basic encryptors and decryptors select another scheme from the [`padding`]
+//! module (or none at all).
+//!
+//! Where the copies the cryptostreams make through their internal buffers are unwelcome, the
+//! [`transform::Transformer`] type encrypts or decrypts caller-owned buffers (or, with the `bytes`
+//! feature, `BytesMut` buffers) in place with a keystream cipher such as AES-CTR or ChaCha20.
+//!
+//! With the `zeroize` feature enabled, the cryptostreams wipe the plaintext and ciphertext held in
+//! their internal buffers, along with their copies of keys (including those derived from a
+//! password), once dropped or finished. Keys kept in `Zeroizing` buffers (re-exported from the
+//! `zeroize` crate) can be passed to any constructor as `&key[..]` without a copy being left
+//! behind.
+//!
+//! The cryptostream constructors return a typed [`Error`], which is also wrapped in the I/O
+//! errors returned by their `Read` and `Write` implementations so that e.g. invalid padding or a
+//! failed authentication can be told apart from a failure of the underlying stream. See the
+//! [`Error`] documentation for details.
 
+mod aead;
+pub mod backend;
 pub mod bufread;
+mod cipher;
+#[cfg(any(feature = "async-tokio", feature = "futures"))]
+mod codec;
+mod commit;
+mod ctr;
+mod error;
+mod etm;
+#[cfg(feature = "futures")]
+pub mod futures;
+pub mod header;
+pub mod padding;
+pub mod password;
 pub mod read;
+mod secret;
+pub mod secretstream;
+mod segment;
+pub mod siv;
+#[cfg(feature = "async-tokio")]
+pub mod tokio;
+pub mod transform;
 pub mod write;
 
-#[cfg(test)]
+// The tests check the cryptostreams against OpenSSL.
+#[cfg(all(test, feature = "openssl"))]
 mod tests;
+
+pub use cipher::{Cipher, Mode};
+pub use error::Error;
+#[cfg(feature = "zeroize")]
+pub use zeroize::Zeroizing;
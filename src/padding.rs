@@ -0,0 +1,228 @@
+//! Padding schemes for block cipher modes such as CBC.
+//!
+//! The basic cryptostreams pad the plaintext to a whole number of blocks with PKCS#7 by default,
+//! but their `with_padding` constructors accept any of the [`Padding`] schemes instead. Padding is
+//! always applied and removed by this crate rather than by the cipher backend, and a final block
+//! whose padding is invalid is reported as [`Error::BadPadding`](crate::Error::BadPadding),
+//! wrapped in an I/O error of kind [`InvalidData`](std::io::ErrorKind::InvalidData). Encrypting
+//! plaintext that ends partway through a block with [`Padding::None`] is reported as
+//! [`Error::IncompletePlaintext`](crate::Error::IncompletePlaintext) instead.
+//!
+//! Padding only applies to block cipher modes; it is ignored for stream cipher modes such as CTR,
+//! whose ciphertext is always the same length as the plaintext.
+
+use crate::backend::{self, Error, StreamCipher};
+use crate::cipher::{Cipher, Mode};
+use crate::error;
+use crate::secret::Secret;
+
+/// A scheme for padding the plaintext to a whole number of blocks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Padding {
+    /// No padding: the plaintext must already be a whole number of blocks long.
+    None,
+    /// PKCS#7: `n` bytes of value `n`. This is the default.
+    #[default]
+    Pkcs7,
+    /// Zero bytes, up to the end of the block, and none at all if the plaintext is already a whole
+    /// number of blocks long. As the zeroes are indistinguishable from the plaintext, all trailing
+    /// zeroes are removed when decrypting, so this is only suitable for plaintext that can't end in
+    /// a zero byte.
+    Zero,
+    /// ISO 10126: `n - 1` random bytes followed by a byte of value `n`.
+    Iso10126,
+    /// ANSI X9.23: `n - 1` zero bytes followed by a byte of value `n`.
+    AnsiX923,
+    /// ISO/IEC 7816-4: a single `0x80` byte followed by zero bytes, as used by smart cards.
+    Iso7816_4,
+}
+
+impl Padding {
+    /// Fills `block` with the padding following `len` bytes of plaintext in a block of
+    /// `block.len()` bytes, returning the length of the padding.
+    fn pad(self, len: usize, block: &mut [u8]) -> Result<usize, Error> {
+        let block_size = block.len();
+        let n = match self {
+            Padding::None if !len.is_multiple_of(block_size) => {
+                return Err(Error::IncompletePlaintext)
+            }
+            Padding::None => return Ok(0),
+            Padding::Zero if len.is_multiple_of(block_size) => return Ok(0),
+            _ => block_size - len % block_size,
+        };
+
+        let padding = &mut block[..n];
+        match self {
+            Padding::Pkcs7 => padding.fill(n as u8),
+            Padding::Zero => padding.fill(0),
+            Padding::Iso10126 => {
+                backend::random_bytes(padding)?;
+                padding[n - 1] = n as u8;
+            }
+            Padding::AnsiX923 => {
+                padding.fill(0);
+                padding[n - 1] = n as u8;
+            }
+            Padding::Iso7816_4 => {
+                padding.fill(0);
+                padding[0] = 0x80;
+            }
+            Padding::None => unreachable!(),
+        }
+
+        Ok(n)
+    }
+
+    /// Returns the length of the plaintext in the final `block`, once its padding is removed.
+    fn unpad(self, block: &[u8]) -> Result<usize, Error> {
+        // Every scheme but zero padding adds at least one byte, so only it can leave no final
+        // block.
+        let block_size = block.len();
+        let last = match block.last() {
+            Some(&last) => last as usize,
+            None if self == Padding::Zero => return Ok(0),
+            None => return Err(Error::BadPadding),
+        };
+
+        match self {
+            Padding::None => Ok(block_size),
+            Padding::Zero => Ok(block.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1)),
+            Padding::Iso7816_4 => match block.iter().rposition(|&b| b != 0) {
+                Some(i) if block[i] == 0x80 => Ok(i),
+                _ => Err(Error::BadPadding),
+            },
+            Padding::Pkcs7 | Padding::Iso10126 | Padding::AnsiX923 => {
+                if last == 0 || last > block_size {
+                    return Err(Error::BadPadding);
+                }
+
+                let filler = &block[block_size - last..block_size - 1];
+                let valid = match self {
+                    Padding::Pkcs7 => filler.iter().all(|&b| b as usize == last),
+                    Padding::AnsiX923 => filler.iter().all(|&b| b == 0),
+                    _ => true,
+                };
+                match valid {
+                    true => Ok(block_size - last),
+                    false => Err(Error::BadPadding),
+                }
+            }
+        }
+    }
+}
+
+/// Creates a `StreamCipher` which pads (or unpads) its input with `padding`.
+pub(crate) fn new_cipher(
+    cipher: Cipher,
+    mode: Mode,
+    key: &[u8],
+    iv: &[u8],
+    padding: Padding,
+) -> Result<Box<dyn StreamCipher>, crate::Error> {
+    error::check_lengths(cipher, key, iv, false)?;
+    let mut inner = backend::new_cipher(cipher, mode, key, iv)?;
+    if cipher.block_size() == 1 {
+        return Ok(inner);
+    }
+
+    // Even without padding, the cipher is wrapped to detect input ending partway through a block,
+    // which the backend may not otherwise distinguish from other errors.
+    inner.pad(false);
+
+    Ok(Box::new(PaddedCipher {
+        inner,
+        mode,
+        padding,
+        len: 0,
+        held: Secret::default(),
+        scratch: Secret::default(),
+    }))
+}
+
+/// Wraps a block cipher with its own padding disabled.
+struct PaddedCipher {
+    inner: Box<dyn StreamCipher>,
+    mode: Mode,
+    padding: Padding,
+    /// The number of bytes passed to `update()` so far, modulo the block size.
+    len: usize,
+    /// When decrypting, the last block of plaintext, which is withheld until `finalize()` as it
+    /// may turn out to contain the padding.
+    held: Secret<Vec<u8>>,
+    scratch: Secret<Vec<u8>>,
+}
+
+impl StreamCipher for PaddedCipher {
+    fn block_size(&self) -> usize {
+        self.inner.block_size()
+    }
+
+    fn pad(&mut self, padding: bool) {
+        if !padding {
+            self.padding = Padding::None;
+        }
+    }
+
+    fn aad_update(&mut self, aad: &[u8]) -> Result<(), Error> {
+        self.inner.aad_update(aad)
+    }
+
+    fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+        let block_size = self.inner.block_size();
+        self.len = (self.len + input.len()) % block_size;
+        if let Mode::Encrypt = self.mode {
+            return self.inner.update(input, output);
+        }
+
+        // The inner cipher only ever outputs whole blocks, of which the last is withheld.
+        self.scratch.resize(input.len() + block_size, 0);
+        let written = self.inner.update(input, &mut self.scratch)?;
+        if written == 0 {
+            return Ok(0);
+        }
+
+        let held = self.held.len();
+        output[..held].copy_from_slice(&self.held);
+        output[held..][..written - block_size]
+            .copy_from_slice(&self.scratch[..written - block_size]);
+        self.held.clear();
+        self.held
+            .extend_from_slice(&self.scratch[written - block_size..written]);
+
+        Ok(held + written - block_size)
+    }
+
+    fn finalize(&mut self, output: &mut [u8]) -> Result<usize, Error> {
+        let block_size = self.inner.block_size();
+
+        if let Mode::Encrypt = self.mode {
+            // Any bytes left over from `update()` and the padding make up exactly one block (if
+            // any), but the inner cipher requires room for another in its output.
+            let mut padding = Secret::new([0u8; 32]);
+            let mut block = Secret::new([0u8; 64]);
+            let n = self.padding.pad(self.len, &mut padding[..block_size])?;
+            let mut written = self.inner.update(&padding[..n], &mut block[..])?;
+            written += self.inner.finalize(&mut block[written..])?;
+            output[..written].copy_from_slice(&block[..written]);
+            return Ok(written);
+        }
+
+        if self.len != 0 {
+            return Err(Error::IncompleteBlock);
+        }
+        self.inner.finalize(&mut [0u8; 32][..])?;
+
+        let len = self.padding.unpad(&self.held)?;
+        output[..len].copy_from_slice(&self.held[..len]);
+        self.held.clear();
+        Ok(len)
+    }
+
+    fn set_tag(&mut self, tag: &[u8]) -> Result<(), Error> {
+        self.inner.set_tag(tag)
+    }
+
+    fn get_tag(&self, tag: &mut [u8]) -> Result<(), Error> {
+        self.inner.get_tag(tag)
+    }
+}
@@ -0,0 +1,457 @@
+//! Support for cryptostreams keyed with a password rather than a raw key and IV.
+//!
+//! The `with_password` constructors of the encryptors derive both the key and the IV from the
+//! password and a freshly generated random salt with the chosen [`Kdf`], and write a small header
+//! to the start of the ciphertext recording the KDF, its parameters, and the salt. The
+//! `with_password` constructors of the decryptors read the header back and repeat the derivation,
+//! so only the password (and the cipher) need be known to decrypt.
+//!
+//! The header is laid out as follows, with all integers big-endian:
+//!
+//! ```text
+//! kdf id (1 byte) || kdf parameters || salt length (1 byte) || salt
+//! ```
+//!
+//! | KDF          | id | parameters                                                      |
+//! |--------------|----|-----------------------------------------------------------------|
+//! | PBKDF2       | 1  | iterations (4 bytes)                                            |
+//! | scrypt       | 2  | log2(N) (1 byte), r (4 bytes), p (4 bytes)                      |
+//! | Argon2id     | 3  | memory in KiB (4 bytes), iterations (4 bytes), lanes (4 bytes)  |
+//!
+//! As the parameters are read from the (untrusted) ciphertext, a tampered header can make
+//! decryption arbitrarily expensive. The `with_password` constructors of the decryptors reject
+//! parameters exceeding the [`KdfLimits`] defaults of [`Kdf::MAX_PBKDF2_ITERATIONS`] and friends
+//! (including scrypt parameters needing more memory than [`Kdf::MAX_SCRYPT_MEMORY`]), as well as
+//! salts shorter than 16 bytes. Ciphertext derived with more expensive parameters can be decrypted
+//! by raising the limits with the `with_password_and_limits` constructors instead, but callers
+//! decrypting input from untrusted sources should still be mindful of the cost of a single key
+//! derivation.
+//!
+//! # `openssl enc` compatibility
+//!
+//! The `with_openssl_enc` constructors instead read and write the format of the `openssl enc`
+//! command line tool when used with a salt (its default), so that files can be exchanged with it in
+//! either direction:
+//!
+//! ```text
+//! "Salted__" || salt (8 bytes) || ciphertext
+//! ```
+//!
+//! As this header records neither the KDF nor its parameters, both sides must agree on them
+//! out-of-band, as they must with the `-md`, `-pbkdf2`, and `-iter` options of `openssl enc`.
+//! See [`EncKdf`] for the options corresponding to each variant.
+
+use crate::backend;
+use crate::cipher::Cipher;
+use crate::error::{self, Error};
+use crate::secret::Secret;
+use std::io::Read;
+
+/// The length of the salt generated by the encryptors, and the shortest accepted by the
+/// decryptors.
+const SALT_LEN: usize = 16;
+
+/// The length of the longest possible header: the KDF id, the largest set of parameters, and the
+/// longest salt.
+pub(crate) const MAX_HEADER_LEN: usize = 1 + 12 + 1 + u8::MAX as usize;
+
+/// The magic bytes at the start of the output of `openssl enc`.
+const ENC_MAGIC: &[u8] = b"Salted__";
+/// The length of the salt following [`ENC_MAGIC`].
+const ENC_SALT_LEN: usize = 8;
+
+const PBKDF2_ID: u8 = 1;
+const SCRYPT_ID: u8 = 2;
+#[cfg(feature = "argon2")]
+const ARGON2ID_ID: u8 = 3;
+
+/// The key derivation function used to derive a key and IV from a password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kdf {
+    /// PBKDF2 with HMAC-SHA256, per RFC 8018.
+    Pbkdf2 { iterations: u32 },
+    /// scrypt with a cost parameter of `N = 2^log_n`, per RFC 7914.
+    Scrypt { log_n: u8, r: u32, p: u32 },
+    /// Argon2id (version 0x13), per RFC 9106. Requires the `argon2` feature.
+    #[cfg(feature = "argon2")]
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        lanes: u32,
+    },
+}
+
+impl Kdf {
+    /// The largest PBKDF2 iteration count accepted by the decryptors by default.
+    pub const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+    /// The largest scrypt `log2(N)` accepted by the decryptors by default.
+    pub const MAX_SCRYPT_LOG_N: u8 = 24;
+    /// The most memory (roughly `128·r·(N + p)` bytes) a scrypt header may require of the
+    /// decryptors by default (1 GiB).
+    pub const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+    /// The most memory an Argon2id header may require of the decryptors by default, in KiB
+    /// (256 MiB).
+    pub const MAX_ARGON2_MEMORY_KIB: u32 = 256 * 1024;
+
+    /// PBKDF2-HMAC-SHA256 with 600,000 iterations, as recommended by OWASP.
+    pub const fn pbkdf2() -> Self {
+        Kdf::Pbkdf2 {
+            iterations: 600_000,
+        }
+    }
+
+    /// scrypt with `N = 2^17`, `r = 8`, and `p = 1`, as recommended by OWASP.
+    pub const fn scrypt() -> Self {
+        Kdf::Scrypt {
+            log_n: 17,
+            r: 8,
+            p: 1,
+        }
+    }
+
+    /// Argon2id with 19 MiB of memory, 2 iterations, and a single lane, as recommended by OWASP.
+    #[cfg(feature = "argon2")]
+    pub const fn argon2id() -> Self {
+        Kdf::Argon2id {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            lanes: 1,
+        }
+    }
+
+    /// Whether or not the parameters are within `limits`.
+    fn is_within_limits(&self, limits: &KdfLimits) -> bool {
+        match *self {
+            Kdf::Pbkdf2 { iterations } => iterations <= limits.pbkdf2_iterations,
+            Kdf::Scrypt { log_n, r, p } => {
+                // Beyond 63, `N` itself would overflow.
+                if log_n > limits.scrypt_log_n || log_n > 63 {
+                    return false;
+                }
+                let (n, r, p) = (1u64 << log_n, r as u64, p as u64);
+                n.saturating_mul(r * p) <= 1 << 32
+                    && (128 * r).saturating_mul(n.saturating_add(p)) <= limits.scrypt_memory
+            }
+            #[cfg(feature = "argon2")]
+            Kdf::Argon2id {
+                memory_kib,
+                iterations,
+                ..
+            } => {
+                memory_kib <= limits.argon2_memory_kib
+                    && memory_kib as u64 * iterations as u64 <= 1 << 32
+            }
+        }
+    }
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Kdf::pbkdf2()
+    }
+}
+
+/// The most expensive [`Kdf`] parameters the decryptors accept from the header of a
+/// password-encrypted stream.
+///
+/// The `with_password` constructors use the defaults, given by the `MAX_*` constants of [`Kdf`].
+/// To decrypt ciphertext derived with more expensive parameters, raise the relevant limit and pass
+/// it to a `with_password_and_limits` constructor:
+///
+/// ```
+/// use cryptostream::password::{Kdf, KdfLimits};
+///
+/// let limits = KdfLimits {
+///     argon2_memory_kib: 1024 * 1024,
+///     ..KdfLimits::default()
+/// };
+/// assert!(limits.argon2_memory_kib > Kdf::MAX_ARGON2_MEMORY_KIB);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfLimits {
+    /// The largest PBKDF2 iteration count.
+    pub pbkdf2_iterations: u32,
+    /// The largest scrypt `log2(N)`.
+    pub scrypt_log_n: u8,
+    /// The most memory (roughly `128·r·(N + p)` bytes) a scrypt header may require.
+    pub scrypt_memory: u64,
+    /// The most memory an Argon2id header may require, in KiB.
+    pub argon2_memory_kib: u32,
+}
+
+impl Default for KdfLimits {
+    fn default() -> Self {
+        KdfLimits {
+            pbkdf2_iterations: Kdf::MAX_PBKDF2_ITERATIONS,
+            scrypt_log_n: Kdf::MAX_SCRYPT_LOG_N,
+            scrypt_memory: Kdf::MAX_SCRYPT_MEMORY,
+            argon2_memory_kib: Kdf::MAX_ARGON2_MEMORY_KIB,
+        }
+    }
+}
+
+/// A message digest selectable with the `-md` option of `openssl enc`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Digest {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Digest {
+    /// The length of the longest digest, SHA-512.
+    pub(crate) const MAX_LEN: usize = 64;
+}
+
+/// The key derivation used by `openssl enc`, which must be known in advance to decrypt its output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncKdf {
+    /// A single iteration of the legacy `EVP_BytesToKey` derivation with `digest`, which is what
+    /// `openssl enc` uses without `-pbkdf2`. The digest is selected with `-md`, defaulting to MD5
+    /// before OpenSSL 1.1.0 and to SHA-256 since.
+    BytesToKey(Digest),
+    /// PBKDF2 with HMAC-`digest`, as with `openssl enc -pbkdf2 -iter <iterations> -md <digest>`.
+    Pbkdf2 { digest: Digest, iterations: u32 },
+}
+
+impl EncKdf {
+    /// PBKDF2-HMAC-SHA256 with 10,000 iterations, the defaults of `openssl enc -pbkdf2`.
+    pub fn pbkdf2() -> Self {
+        EncKdf::Pbkdf2 {
+            digest: Digest::Sha256,
+            iterations: 10_000,
+        }
+    }
+}
+
+impl Default for EncKdf {
+    fn default() -> Self {
+        EncKdf::pbkdf2()
+    }
+}
+
+/// The format of the header expected by a decryptor.
+#[derive(Clone, Copy)]
+pub(crate) enum Format {
+    /// This crate's own header, recording the KDF and its parameters, which must be within the
+    /// limits.
+    Native(KdfLimits),
+    /// The `Salted__` header of `openssl enc`, along with the KDF it was used with.
+    OpenSslEnc(EncKdf),
+}
+
+/// The header written to the start of a password-encrypted stream.
+pub(crate) enum KdfHeader {
+    Native {
+        kdf: Kdf,
+        salt: Vec<u8>,
+    },
+    OpenSslEnc {
+        kdf: EncKdf,
+        salt: [u8; ENC_SALT_LEN],
+    },
+}
+
+impl KdfHeader {
+    /// Creates a header with a freshly generated random salt.
+    pub fn generate(kdf: Kdf) -> Result<Self, Error> {
+        // Never produce a stream the decryptors will refuse to decrypt by default.
+        if !kdf.is_within_limits(&KdfLimits::default()) {
+            return Err(Error::InvalidKdfParameters);
+        }
+
+        let mut salt = vec![0u8; SALT_LEN];
+        backend::random_bytes(&mut salt)?;
+        Ok(KdfHeader::Native { kdf, salt })
+    }
+
+    /// Creates an `openssl enc` header with a freshly generated random salt.
+    pub fn generate_openssl_enc(kdf: EncKdf) -> Result<Self, Error> {
+        let mut salt = [0u8; ENC_SALT_LEN];
+        backend::random_bytes(&mut salt)?;
+        Ok(KdfHeader::OpenSslEnc { kdf, salt })
+    }
+
+    /// The length of the encoded header.
+    pub fn encoded_len(&self) -> usize {
+        self.to_bytes().len()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (kdf, salt) = match self {
+            KdfHeader::Native { kdf, salt } => (kdf, salt),
+            KdfHeader::OpenSslEnc { salt, .. } => return [ENC_MAGIC, &salt[..]].concat(),
+        };
+
+        let mut bytes = Vec::with_capacity(14 + salt.len());
+        match *kdf {
+            Kdf::Pbkdf2 { iterations } => {
+                bytes.push(PBKDF2_ID);
+                bytes.extend_from_slice(&iterations.to_be_bytes());
+            }
+            Kdf::Scrypt { log_n, r, p } => {
+                bytes.push(SCRYPT_ID);
+                bytes.push(log_n);
+                bytes.extend_from_slice(&r.to_be_bytes());
+                bytes.extend_from_slice(&p.to_be_bytes());
+            }
+            #[cfg(feature = "argon2")]
+            Kdf::Argon2id {
+                memory_kib,
+                iterations,
+                lanes,
+            } => {
+                bytes.push(ARGON2ID_ID);
+                bytes.extend_from_slice(&memory_kib.to_be_bytes());
+                bytes.extend_from_slice(&iterations.to_be_bytes());
+                bytes.extend_from_slice(&lanes.to_be_bytes());
+            }
+        }
+        bytes.push(salt.len() as u8);
+        bytes.extend_from_slice(salt);
+        bytes
+    }
+
+    /// Reads a header of the given format from the start of `reader`, consuming exactly the bytes
+    /// making it up. Fails with [`Error::Truncated`] if `reader` ends partway through the header,
+    /// or with [`Error::InvalidKdfParameters`] if its parameters exceed the limits of `format` or
+    /// its salt is too short.
+    pub fn read<R: Read>(mut reader: R, format: Format) -> Result<Self, Error> {
+        fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), Error> {
+            reader.read_exact(buf).map_err(error::reading_header)
+        }
+        fn u32<R: Read>(reader: &mut R) -> Result<u32, Error> {
+            let mut bytes = [0u8; 4];
+            read_exact(reader, &mut bytes)?;
+            Ok(u32::from_be_bytes(bytes))
+        }
+        fn u8<R: Read>(reader: &mut R) -> Result<u8, Error> {
+            let mut byte = [0u8; 1];
+            read_exact(reader, &mut byte)?;
+            Ok(byte[0])
+        }
+
+        let limits = match format {
+            Format::Native(limits) => limits,
+            Format::OpenSslEnc(kdf) => {
+                let mut magic = [0u8; ENC_MAGIC.len()];
+                read_exact(&mut reader, &mut magic)?;
+                if magic != ENC_MAGIC {
+                    return Err(Error::InvalidHeader);
+                }
+
+                let mut salt = [0u8; ENC_SALT_LEN];
+                read_exact(&mut reader, &mut salt)?;
+                return Ok(KdfHeader::OpenSslEnc { kdf, salt });
+            }
+        };
+
+        let kdf = match u8(&mut reader)? {
+            PBKDF2_ID => Kdf::Pbkdf2 {
+                iterations: u32(&mut reader)?,
+            },
+            SCRYPT_ID => Kdf::Scrypt {
+                log_n: u8(&mut reader)?,
+                r: u32(&mut reader)?,
+                p: u32(&mut reader)?,
+            },
+            #[cfg(feature = "argon2")]
+            ARGON2ID_ID => Kdf::Argon2id {
+                memory_kib: u32(&mut reader)?,
+                iterations: u32(&mut reader)?,
+                lanes: u32(&mut reader)?,
+            },
+            _ => return Err(Error::UnsupportedKdf),
+        };
+        if !kdf.is_within_limits(&limits) {
+            return Err(Error::InvalidKdfParameters);
+        }
+
+        let salt_len = u8(&mut reader)? as usize;
+        if salt_len < SALT_LEN {
+            return Err(Error::InvalidKdfParameters);
+        }
+        let mut salt = vec![0u8; salt_len];
+        read_exact(&mut reader, &mut salt)?;
+
+        Ok(KdfHeader::Native { kdf, salt })
+    }
+
+    /// Derives the key and IV for `cipher` from `password`.
+    pub fn derive(
+        &self,
+        cipher: Cipher,
+        password: &[u8],
+    ) -> Result<(Secret<Vec<u8>>, Vec<u8>), crate::Error> {
+        let key_len = cipher.key_len();
+        let mut output = Secret::new(vec![0u8; key_len + cipher.iv_len().unwrap_or(0)]);
+
+        let (kdf, salt) = match self {
+            KdfHeader::Native { kdf, salt } => (*kdf, &salt[..]),
+            KdfHeader::OpenSslEnc { kdf, salt } => {
+                match *kdf {
+                    EncKdf::BytesToKey(digest) => {
+                        bytes_to_key(digest, password, salt, &mut output)?
+                    }
+                    EncKdf::Pbkdf2 { digest, iterations } => {
+                        backend::pbkdf2_hmac(password, salt, iterations, digest, &mut output)?
+                    }
+                }
+                let iv = output.split_off(key_len);
+                return Ok((output, iv));
+            }
+        };
+
+        match kdf {
+            Kdf::Pbkdf2 { iterations } => {
+                backend::pbkdf2_hmac(password, salt, iterations, Digest::Sha256, &mut output)?
+            }
+            Kdf::Scrypt { log_n, r, p } => {
+                backend::scrypt(password, salt, log_n, r, p, &mut output)?
+            }
+            #[cfg(feature = "argon2")]
+            Kdf::Argon2id {
+                memory_kib,
+                iterations,
+                lanes,
+            } => {
+                use argon2::{Algorithm, Argon2, Params, Version};
+
+                let invalid = |_| Error::InvalidKdfParameters;
+                let params = Params::new(memory_kib, iterations, lanes, Some(output.len()))
+                    .map_err(invalid)?;
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password, salt, &mut output)
+                    .map_err(invalid)?
+            }
+        }
+
+        let iv = output.split_off(key_len);
+        Ok((output, iv))
+    }
+}
+
+/// Fills `output` with a single iteration of OpenSSL's legacy `EVP_BytesToKey` derivation: the
+/// concatenation of `D_1 || D_2 || ...`, where `D_i = digest(D_{i-1} || password || salt)`.
+fn bytes_to_key(
+    digest: Digest,
+    password: &[u8],
+    salt: &[u8],
+    output: &mut [u8],
+) -> Result<(), Error> {
+    let mut block = Secret::new([0u8; Digest::MAX_LEN]);
+    let mut len = 0;
+    let mut filled = 0;
+    while filled < output.len() {
+        let previous = Secret::new(*block);
+        len = backend::hash(digest, &[&previous[..len], password, salt], &mut block[..])?;
+
+        let n = std::cmp::min(len, output.len() - filled);
+        output[filled..][..n].copy_from_slice(&block[..n]);
+        filled += n;
+    }
+
+    Ok(())
+}
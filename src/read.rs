@@ -7,9 +7,19 @@
 //! time) via `.read(..)` calls.
 
 use crate::bufread;
-use openssl::error::ErrorStack;
-use openssl::symm::Cipher;
-use std::io::{BufReader, Error, Read};
+use crate::cipher::Cipher;
+use crate::header::Header;
+use crate::padding::Padding;
+use crate::password::{EncKdf, Kdf, KdfLimits};
+use crate::secretstream::DEFAULT_CHUNK_SIZE;
+use crate::segment::{DEFAULT_SEGMENT_SIZE, NONCE_PREFIX_LEN};
+use std::io::{Error, IoSliceMut, Read, Seek, SeekFrom};
+
+// The buffer of `std::io::BufReader` can't be wiped once the cryptostream is done with it.
+#[cfg(feature = "zeroize")]
+use crate::secret::BufReader;
+#[cfg(not(feature = "zeroize"))]
+use std::io::BufReader;
 
 /// An encrypting stream adapter that encrypts what it reads
 ///
@@ -21,12 +31,120 @@ pub struct Encryptor<R: Read> {
 }
 
 impl<R: Read> Encryptor<R> {
-    pub fn new(reader: R, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, ErrorStack> {
+    pub fn new(reader: R, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
         Ok(Self {
             reader: bufread::Encryptor::new(BufReader::new(reader), cipher, key, iv)?,
         })
     }
 
+    /// Creates a new `Encryptor` which reads up to `capacity` bytes (rather than 8 KiB) from `reader`
+    /// at a time. See [`bufread::Encryptor::with_capacity()`].
+    pub fn with_capacity(
+        capacity: usize,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Encryptor::with_capacity(
+                capacity,
+                BufReader::with_capacity(capacity, reader),
+                cipher,
+                key,
+                iv,
+            )?,
+        })
+    }
+
+    /// Creates a new `Encryptor` which pads the plaintext with `padding` rather than PKCS#7. See
+    /// the [`padding`](crate::padding) module for details.
+    pub fn with_padding(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Encryptor::with_padding(
+                BufReader::new(reader),
+                cipher,
+                key,
+                iv,
+                padding,
+            )?,
+        })
+    }
+
+    /// Creates a new `Encryptor` with a freshly generated random IV, which must be retrieved with
+    /// [`iv()`](Self::iv) and passed on to the decryptor.
+    pub fn with_random_iv(reader: R, cipher: Cipher, key: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Encryptor::with_random_iv(BufReader::new(reader), cipher, key)?,
+        })
+    }
+
+    /// Creates a new `Encryptor` with a freshly generated random IV, which is prepended to the
+    /// output to be read back by [`Decryptor::with_prepended_iv()`].
+    pub fn with_prepended_iv(reader: R, cipher: Cipher, key: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Encryptor::with_prepended_iv(BufReader::new(reader), cipher, key)?,
+        })
+    }
+
+    /// Creates a new `Encryptor` whose output begins with `header`, from which
+    /// [`Decryptor::auto()`] reads back the cipher and IV. See the [`header`](crate::header)
+    /// module for details.
+    pub fn with_header(reader: R, header: &Header, key: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Encryptor::with_header(BufReader::new(reader), header, key)?,
+        })
+    }
+
+    /// Creates a new `Encryptor` keyed with `password`, from which the key and IV are derived
+    /// with `kdf` and a random salt. The output begins with a header recording the KDF and salt,
+    /// which is read back by [`Decryptor::with_password()`]. See the [`password`](crate::password)
+    /// module for details.
+    pub fn with_password(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        kdf: Kdf,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Encryptor::with_password(
+                BufReader::new(reader),
+                cipher,
+                password,
+                kdf,
+            )?,
+        })
+    }
+
+    /// Creates a new `Encryptor` producing the same output as `openssl enc` with a random salt,
+    /// keyed with `password` via `kdf`. See the [`password`](crate::password) module for details.
+    pub fn with_openssl_enc(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        kdf: EncKdf,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Encryptor::with_openssl_enc(
+                BufReader::new(reader),
+                cipher,
+                password,
+                kdf,
+            )?,
+        })
+    }
+
+    /// The IV the stream is encrypted with.
+    pub fn iv(&self) -> &[u8] {
+        self.reader.iv()
+    }
+
     pub fn finish(self) -> R {
         self.reader.finish().into_inner()
     }
@@ -35,8 +153,13 @@ impl<R: Read> Encryptor<R> {
 impl<R: Read> Read for Encryptor<R> {
     /// Reading from the cryptostream returns an encrypted view of bytes pulled from the underlying
     /// `Read` stream.
-    fn read(&mut self, mut buf: &mut [u8]) -> Result<usize, Error> {
-        self.reader.read(&mut buf)
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+
+    /// Reads encrypted data into `bufs`, encrypting it in a single pass.
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Error> {
+        self.reader.read_vectored(bufs)
     }
 }
 
@@ -44,18 +167,134 @@ impl<R: Read> Read for Encryptor<R> {
 ///
 /// `read::Decryptor` is a stream adapter that sits atop a ciphertext (encrypted) `Read` source,
 /// exposing a second `Read` interface. Bytes read out of `read::Decrytor` are the decrypted
-/// contents of the underlying `Read` stream.
+/// contents of the underlying `Read` stream. See [`bufread::Decryptor`] for how truncated
+/// ciphertext is reported.
 pub struct Decryptor<R: Read> {
     reader: bufread::Decryptor<BufReader<R>>,
 }
 
 impl<R: Read> Decryptor<R> {
-    pub fn new(reader: R, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, ErrorStack> {
+    pub fn new(reader: R, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
         Ok(Self {
             reader: bufread::Decryptor::new(BufReader::new(reader), cipher, key, iv)?,
         })
     }
 
+    /// Creates a new `Decryptor` which reads up to `capacity` bytes (rather than 8 KiB) from `reader`
+    /// at a time. See [`bufread::Decryptor::with_capacity()`].
+    pub fn with_capacity(
+        capacity: usize,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Decryptor::with_capacity(
+                capacity,
+                BufReader::with_capacity(capacity, reader),
+                cipher,
+                key,
+                iv,
+            )?,
+        })
+    }
+
+    /// Creates a new `Decryptor` which expects the plaintext to have been padded with `padding`
+    /// rather than PKCS#7. See the [`padding`](crate::padding) module for details.
+    pub fn with_padding(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Decryptor::with_padding(
+                BufReader::new(reader),
+                cipher,
+                key,
+                iv,
+                padding,
+            )?,
+        })
+    }
+
+    /// Creates a new `Decryptor` for ciphertext produced by an encryptor created with
+    /// `with_prepended_iv()`, reading the IV from the start of `reader`.
+    pub fn with_prepended_iv(reader: R, cipher: Cipher, key: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Decryptor::with_prepended_iv(BufReader::new(reader), cipher, key)?,
+        })
+    }
+
+    /// Creates a new `Decryptor` for ciphertext produced by an encryptor created with
+    /// `with_header()`, reading the header from the start of `reader` and configuring the cipher
+    /// and IV as recorded in it.
+    pub fn auto(reader: R, key: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Decryptor::auto(BufReader::new(reader), key)?,
+        })
+    }
+
+    /// Creates a new `Decryptor` configured with `header`, which has already been read from the
+    /// start of `reader` with [`Header::read()`]. This allows the key to be chosen based on the
+    /// header's key id.
+    pub fn from_header(reader: R, header: &Header, key: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Decryptor::from_header(BufReader::new(reader), header, key)?,
+        })
+    }
+
+    /// Creates a new `Decryptor` for ciphertext produced by an encryptor created with
+    /// `with_password()`, reading the header from the start of `reader` and deriving the key and
+    /// IV from `password` as recorded in it. Fails with [`Error::InvalidKdfParameters`] if the
+    /// recorded parameters exceed the default [`KdfLimits`].
+    ///
+    /// [`Error::InvalidKdfParameters`]: crate::Error::InvalidKdfParameters
+    pub fn with_password(reader: R, cipher: Cipher, password: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Decryptor::with_password(BufReader::new(reader), cipher, password)?,
+        })
+    }
+
+    /// Like [`with_password()`](Self::with_password), but accepting KDF parameters up to `limits`
+    /// rather than the defaults.
+    pub fn with_password_and_limits(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        limits: KdfLimits,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Decryptor::with_password_and_limits(
+                BufReader::new(reader),
+                cipher,
+                password,
+                limits,
+            )?,
+        })
+    }
+
+    /// Creates a new `Decryptor` for the output of `openssl enc`, reading the salt from the start
+    /// of `reader` and deriving the key and IV from `password` via `kdf`, which must match the
+    /// options it was encrypted with. See the [`password`](crate::password) module for details.
+    pub fn with_openssl_enc(
+        reader: R,
+        cipher: Cipher,
+        password: &[u8],
+        kdf: EncKdf,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::Decryptor::with_openssl_enc(
+                BufReader::new(reader),
+                cipher,
+                password,
+                kdf,
+            )?,
+        })
+    }
+
     pub fn finish(self) -> R {
         self.reader.finish().into_inner()
     }
@@ -64,7 +303,584 @@ impl<R: Read> Decryptor<R> {
 impl<R: Read> Read for Decryptor<R> {
     /// Reading from the cryptostream returns returns a decrypted view of bytes pulled from the
     /// underlying `Read` stream.
-    fn read(&mut self, mut buf: &mut [u8]) -> Result<usize, Error> {
-        self.reader.read(&mut buf)
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+
+    /// Reads decrypted data into `bufs`, decrypting it in a single pass.
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize, Error> {
+        self.reader.read_vectored(bufs)
+    }
+}
+
+impl<R: Read + Seek> Seek for Decryptor<R> {
+    /// Seeks to an offset in the decrypted plaintext, without decrypting everything preceding it.
+    ///
+    /// Seeking is only supported with CTR mode ciphers (e.g. `Cipher::aes_256_ctr()`); an error
+    /// with kind [`ErrorKind::Unsupported`](std::io::ErrorKind::Unsupported) is returned for all
+    /// other ciphers.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.reader.seek(pos)
+    }
+
+    fn stream_position(&mut self) -> Result<u64, Error> {
+        self.reader.stream_position()
+    }
+}
+
+/// An authenticated encrypting stream adapter that encrypts what it reads
+///
+/// `read::AeadEncryptor` is the AEAD counterpart to [`read::Encryptor`](Encryptor), for use with
+/// authenticated ciphers such as `Cipher::aes_256_gcm()` or `Cipher::chacha20_poly1305()`. Bytes
+/// read out of `read::AeadEncryptor` are the encrypted contents of the underlying `Read` stream,
+/// followed by the 16-byte authentication tag.
+pub struct AeadEncryptor<R: Read> {
+    reader: bufread::AeadEncryptor<BufReader<R>>,
+}
+
+impl<R: Read> AeadEncryptor<R> {
+    /// Creates a new `AeadEncryptor`, authenticating the associated data `aad` (which may be
+    /// empty) along with the ciphertext.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::AeadEncryptor::new(BufReader::new(reader), cipher, key, iv, aad)?,
+        })
+    }
+
+    /// Creates a new `AeadEncryptor` which reads up to `capacity` bytes (rather than 8 KiB) from
+    /// `reader` at a time. See [`bufread::AeadEncryptor::with_capacity()`].
+    pub fn with_capacity(
+        capacity: usize,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::AeadEncryptor::with_capacity(
+                capacity,
+                BufReader::with_capacity(capacity, reader),
+                cipher,
+                key,
+                iv,
+                aad,
+            )?,
+        })
+    }
+
+    /// Creates a new `AeadEncryptor` which commits to `key`, its output beginning with the
+    /// commitment checked by [`AeadDecryptor::with_key_commitment()`]. See
+    /// [`bufread::AeadEncryptor::with_key_commitment()`] for details.
+    pub fn with_key_commitment(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::AeadEncryptor::with_key_commitment(
+                BufReader::new(reader),
+                cipher,
+                key,
+                iv,
+                aad,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.reader.finish().into_inner()
+    }
+}
+
+impl<R: Read> Read for AeadEncryptor<R> {
+    /// Reading from the cryptostream returns an encrypted view of bytes pulled from the underlying
+    /// `Read` stream, followed by the authentication tag.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+}
+
+/// An authenticated decrypting stream adapter that decrypts what it reads
+///
+/// `read::AeadDecryptor` is the AEAD counterpart to [`read::Decryptor`](Decryptor), for use with
+/// authenticated ciphers such as `Cipher::aes_256_gcm()` or `Cipher::chacha20_poly1305()`. The
+/// underlying `Read` stream must contain the ciphertext followed by its 16-byte authentication
+/// tag, which is verified once the end of the stream is reached.
+///
+/// Decrypted bytes are returned before the tag has been verified and must not be trusted until
+/// `read()` has returned zero; a failed verification is reported as an error with kind
+/// [`ErrorKind::InvalidData`](std::io::ErrorKind::InvalidData).
+pub struct AeadDecryptor<R: Read> {
+    reader: bufread::AeadDecryptor<BufReader<R>>,
+}
+
+impl<R: Read> AeadDecryptor<R> {
+    /// Creates a new `AeadDecryptor`, authenticating the associated data `aad` (which must match
+    /// that provided when encrypting) along with the ciphertext.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::AeadDecryptor::new(BufReader::new(reader), cipher, key, iv, aad)?,
+        })
+    }
+
+    /// Creates a new `AeadDecryptor` which reads up to `capacity` bytes (rather than 8 KiB) from
+    /// `reader` at a time. See [`bufread::AeadDecryptor::with_capacity()`].
+    pub fn with_capacity(
+        capacity: usize,
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::AeadDecryptor::with_capacity(
+                capacity,
+                BufReader::with_capacity(capacity, reader),
+                cipher,
+                key,
+                iv,
+                aad,
+            )?,
+        })
+    }
+
+    /// Creates a new `AeadDecryptor` for ciphertext produced by an encryptor created with
+    /// `with_key_commitment()`, checking the commitment at its start against `key` before anything
+    /// is decrypted. See [`bufread::AeadDecryptor::with_key_commitment()`] for details.
+    pub fn with_key_commitment(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::AeadDecryptor::with_key_commitment(
+                BufReader::new(reader),
+                cipher,
+                key,
+                iv,
+                aad,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.reader.finish().into_inner()
+    }
+}
+
+impl<R: Read> Read for AeadDecryptor<R> {
+    /// Reading from the cryptostream returns a decrypted view of bytes pulled from the underlying
+    /// `Read` stream, verifying the authentication tag once the end of the stream is reached.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+}
+
+/// An encrypting stream adapter that encrypts what it reads, then authenticates it with a MAC
+///
+/// `read::EtmEncryptor` is the encrypt-then-MAC counterpart to [`read::Encryptor`](Encryptor). See
+/// [`bufread::EtmEncryptor`] for details.
+pub struct EtmEncryptor<R: Read> {
+    reader: bufread::EtmEncryptor<BufReader<R>>,
+}
+
+impl<R: Read> EtmEncryptor<R> {
+    /// Creates a new `EtmEncryptor`, computing the MAC with `mac_key`, which must be independent
+    /// of `key`.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::EtmEncryptor::new(BufReader::new(reader), cipher, key, mac_key, iv)?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.reader.finish().into_inner()
+    }
+}
+
+impl<R: Read> Read for EtmEncryptor<R> {
+    /// Reading from the cryptostream returns an encrypted view of bytes pulled from the underlying
+    /// `Read` stream, followed by the MAC.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+}
+
+/// A decrypting stream adapter that verifies the MAC of what it reads, then decrypts it
+///
+/// `read::EtmDecryptor` is the encrypt-then-MAC counterpart to [`read::Decryptor`](Decryptor). See
+/// [`bufread::EtmDecryptor`] for details.
+pub struct EtmDecryptor<R: Read> {
+    reader: bufread::EtmDecryptor<BufReader<R>>,
+}
+
+impl<R: Read> EtmDecryptor<R> {
+    /// Creates a new `EtmDecryptor`, verifying the MAC with `mac_key`.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::EtmDecryptor::new(BufReader::new(reader), cipher, key, mac_key, iv)?,
+        })
+    }
+
+    /// Creates a new `EtmDecryptor` which reads and verifies the entire stream, holding its
+    /// plaintext in memory, before returning any of it.
+    pub fn buffered(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::EtmDecryptor::buffered(
+                BufReader::new(reader),
+                cipher,
+                key,
+                mac_key,
+                iv,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.reader.finish().into_inner()
+    }
+}
+
+impl<R: Read> Read for EtmDecryptor<R> {
+    /// Reading from the cryptostream returns a decrypted view of bytes pulled from the underlying
+    /// `Read` stream, verifying the MAC once the end of the stream is reached.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+}
+
+/// An encrypting stream adapter that encrypts what it reads in authenticated segments
+///
+/// `read::SegmentedEncryptor` is a stream adapter that sits atop a plaintext `Read` source. Bytes
+/// read out of `read::SegmentedEncryptor` are the contents of the underlying stream split into
+/// fixed-size segments, each sealed with an AEAD cipher and followed by its authentication tag.
+/// See [`write::SegmentedEncryptor`](crate::write::SegmentedEncryptor) for details.
+pub struct SegmentedEncryptor<R: Read> {
+    reader: bufread::SegmentedEncryptor<BufReader<R>>,
+}
+
+impl<R: Read> SegmentedEncryptor<R> {
+    /// Creates a new `SegmentedEncryptor` with the default segment size of 64 KiB.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_segment_size(reader, cipher, key, nonce_prefix, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Creates a new `SegmentedEncryptor` with the default segment size which commits to `key`,
+    /// its output beginning with the commitment checked by
+    /// [`SegmentedDecryptor::with_key_commitment()`]. See
+    /// [`bufread::SegmentedEncryptor::with_key_commitment()`] for details.
+    pub fn with_key_commitment(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_key_commitment_and_segment_size(
+            reader,
+            cipher,
+            key,
+            nonce_prefix,
+            DEFAULT_SEGMENT_SIZE,
+        )
+    }
+
+    /// Creates a new `SegmentedEncryptor` which commits to `key` as
+    /// [`with_key_commitment()`](Self::with_key_commitment) does, sealing `segment_size` bytes of
+    /// plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_key_commitment_and_segment_size(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::SegmentedEncryptor::with_key_commitment_and_segment_size(
+                BufReader::new(reader),
+                cipher,
+                key,
+                nonce_prefix,
+                segment_size,
+            )?,
+        })
+    }
+
+    /// Creates a new `SegmentedEncryptor` sealing `segment_size` bytes of plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_segment_size(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::SegmentedEncryptor::with_segment_size(
+                BufReader::new(reader),
+                cipher,
+                key,
+                nonce_prefix,
+                segment_size,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.reader.finish().into_inner()
+    }
+}
+
+impl<R: Read> Read for SegmentedEncryptor<R> {
+    /// Reading from the cryptostream returns the sealed segments of bytes pulled from the
+    /// underlying `Read` stream.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+}
+
+/// A decrypting stream adapter that decrypts segmented ciphertext as it is read
+///
+/// `read::SegmentedDecryptor` is a stream adapter that sits atop a `Read` source of ciphertext
+/// produced by a `SegmentedEncryptor`. Each segment is authenticated in its entirety before any of
+/// its plaintext is returned from `read()`, so unauthenticated plaintext is never released.
+/// Reordered or tampered segments are reported as an error with kind
+/// [`ErrorKind::InvalidData`](std::io::ErrorKind::InvalidData), while a stream cut off at a segment
+/// boundary is reported as [`Error::Truncated`](crate::Error::Truncated). See
+/// [`bufread::SegmentedDecryptor`] for details.
+pub struct SegmentedDecryptor<R: Read> {
+    reader: bufread::SegmentedDecryptor<BufReader<R>>,
+}
+
+impl<R: Read> SegmentedDecryptor<R> {
+    /// Creates a new `SegmentedDecryptor` for ciphertext sealed with the default segment size of
+    /// 64 KiB.
+    pub fn new(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_segment_size(reader, cipher, key, nonce_prefix, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Creates a new `SegmentedDecryptor` for ciphertext produced by an encryptor created with
+    /// `with_key_commitment()`, checking the commitment at its start against `key` before any
+    /// segment is decrypted. See [`bufread::SegmentedDecryptor::with_key_commitment()`] for
+    /// details.
+    pub fn with_key_commitment(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_key_commitment_and_segment_size(
+            reader,
+            cipher,
+            key,
+            nonce_prefix,
+            DEFAULT_SEGMENT_SIZE,
+        )
+    }
+
+    /// Creates a new `SegmentedDecryptor` which checks the key commitment as
+    /// [`with_key_commitment()`](Self::with_key_commitment) does, for ciphertext sealed with
+    /// `segment_size` bytes of plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_key_commitment_and_segment_size(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::SegmentedDecryptor::with_key_commitment_and_segment_size(
+                BufReader::new(reader),
+                cipher,
+                key,
+                nonce_prefix,
+                segment_size,
+            )?,
+        })
+    }
+
+    /// Creates a new `SegmentedDecryptor` for ciphertext sealed with `segment_size` bytes of
+    /// plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_segment_size(
+        reader: R,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::SegmentedDecryptor::with_segment_size(
+                BufReader::new(reader),
+                cipher,
+                key,
+                nonce_prefix,
+                segment_size,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.reader.finish().into_inner()
+    }
+}
+
+impl<R: Read> Read for SegmentedDecryptor<R> {
+    /// Reading from the cryptostream returns the authenticated plaintext of the segments pulled
+    /// from the underlying `Read` stream.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R: Read + Seek> Seek for SegmentedDecryptor<R> {
+    /// Seeks to an offset in the decrypted plaintext, only decrypting the segment containing it.
+    ///
+    /// The segment containing the new position is still authenticated in its entirety before any
+    /// of it is returned.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.reader.seek(pos)
+    }
+
+    fn stream_position(&mut self) -> Result<u64, Error> {
+        self.reader.stream_position()
+    }
+}
+
+/// An encrypting stream adapter that encrypts what it reads as a libsodium secretstream
+///
+/// `read::SecretStreamEncryptor` is a stream adapter that sits atop a plaintext `Read` source.
+/// Bytes read out of `read::SecretStreamEncryptor` are the secretstream header followed by the
+/// contents of the underlying stream encrypted a chunk at a time. See
+/// [`write::SecretStreamEncryptor`](crate::write::SecretStreamEncryptor) for details.
+pub struct SecretStreamEncryptor<R: Read> {
+    reader: bufread::SecretStreamEncryptor<BufReader<R>>,
+}
+
+impl<R: Read> SecretStreamEncryptor<R> {
+    /// Creates a new `SecretStreamEncryptor` with the default chunk size of 4 KiB.
+    pub fn new(reader: R, key: &[u8]) -> Result<Self, crate::Error> {
+        Self::with_chunk_size(reader, key, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new `SecretStreamEncryptor` encrypting `chunk_size` bytes of plaintext per
+    /// message.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(reader: R, key: &[u8], chunk_size: usize) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::SecretStreamEncryptor::with_chunk_size(
+                BufReader::new(reader),
+                key,
+                chunk_size,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.reader.finish().into_inner()
+    }
+}
+
+impl<R: Read> Read for SecretStreamEncryptor<R> {
+    /// Reading from the cryptostream returns the encrypted chunks of bytes pulled from the
+    /// underlying `Read` stream.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
+    }
+}
+
+/// A decrypting stream adapter that decrypts a libsodium secretstream as it is read
+///
+/// `read::SecretStreamDecryptor` is a stream adapter that sits atop a `Read` source of a
+/// secretstream encrypted in fixed-size chunks. Each chunk is authenticated in its entirety before
+/// any of its plaintext is returned from `read()`. See [`bufread::SecretStreamDecryptor`] for
+/// details.
+pub struct SecretStreamDecryptor<R: Read> {
+    reader: bufread::SecretStreamDecryptor<BufReader<R>>,
+}
+
+impl<R: Read> SecretStreamDecryptor<R> {
+    /// Creates a new `SecretStreamDecryptor` for a secretstream encrypted with the default chunk
+    /// size of 4 KiB.
+    pub fn new(reader: R, key: &[u8]) -> Result<Self, crate::Error> {
+        Self::with_chunk_size(reader, key, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new `SecretStreamDecryptor` for a secretstream encrypted with `chunk_size` bytes
+    /// of plaintext per message.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(reader: R, key: &[u8], chunk_size: usize) -> Result<Self, crate::Error> {
+        Ok(Self {
+            reader: bufread::SecretStreamDecryptor::with_chunk_size(
+                BufReader::new(reader),
+                key,
+                chunk_size,
+            )?,
+        })
+    }
+
+    pub fn finish(self) -> R {
+        self.reader.finish().into_inner()
+    }
+}
+
+impl<R: Read> Read for SecretStreamDecryptor<R> {
+    /// Reading from the cryptostream returns the authenticated plaintext of the chunks pulled from
+    /// the underlying `Read` stream.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.reader.read(buf)
     }
 }
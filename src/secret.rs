@@ -0,0 +1,208 @@
+//! Wiping of the keys and the plaintext (or ciphertext) held by the cryptostreams.
+//!
+//! Copies of keys and the buffers the cryptostreams transform their input into are held in a
+//! [`Secret`], which overwrites its contents with zeroes when dropped if the crate is built with
+//! the `zeroize` feature. Without it, a `Secret` is a plain wrapper and wiping is a no-op.
+
+use std::io::{ErrorKind, Read, Result};
+use std::ops::{Deref, DerefMut};
+
+/// Something that can be overwritten with zeroes.
+pub(crate) trait Wipe {
+    fn wipe(&mut self);
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: zeroize::Zeroize + ?Sized> Wipe for T {
+    fn wipe(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(not(feature = "zeroize"))]
+impl<T: ?Sized> Wipe for T {
+    fn wipe(&mut self) {}
+}
+
+/// Wipes the contents of `value` when dropped. Vectors are wiped in their entirety, including any
+/// spare capacity left behind by `clear()` or `truncate()`.
+#[derive(Default)]
+pub(crate) struct Secret<T: Wipe>(T);
+
+impl<T: Wipe> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+/// The number of bytes `Secret::read_to_end()` reads at a time.
+const READ_CHUNK: usize = 8 * 1024;
+
+impl Secret<Vec<u8>> {
+    /// Reserves capacity for at least `additional` more bytes. Unlike `Vec::reserve()`, which
+    /// frees the old allocation with its contents intact, the contents are moved into a new
+    /// allocation by hand, wiping the old one.
+    pub fn reserve(&mut self, additional: usize) {
+        if self.0.capacity() - self.0.len() >= additional {
+            return;
+        }
+
+        let capacity = std::cmp::max(self.0.len() + additional, self.0.capacity() * 2);
+        let mut grown = Vec::with_capacity(capacity);
+        grown.extend_from_slice(&self.0);
+        // The old allocation is wiped as it is dropped here.
+        drop(Secret(std::mem::replace(&mut self.0, grown)));
+    }
+
+    /// Appends `data`, growing the vector with [`reserve()`](Self::reserve).
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.reserve(data.len());
+        self.0.extend_from_slice(data);
+    }
+
+    /// Reads `reader` to the end, as `Read::read_to_end()` does but growing the vector with
+    /// [`reserve()`](Self::reserve). Returns the number of bytes read.
+    pub fn read_to_end<R: Read + ?Sized>(&mut self, reader: &mut R) -> Result<usize> {
+        let start = self.0.len();
+        loop {
+            self.reserve(READ_CHUNK);
+            let len = self.0.len();
+            self.0.resize(len + READ_CHUNK, 0);
+            match reader.read(&mut self.0[len..]) {
+                Ok(0) => {
+                    self.0.truncate(len);
+                    return Ok(len - start);
+                }
+                Ok(n) => self.0.truncate(len + n),
+                Err(e) => {
+                    self.0.truncate(len);
+                    if e.kind() != ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Wipe> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Wipe> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Wipe + AsRef<[u8]>> AsRef<[u8]> for Secret<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl<T: Wipe> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+pub(crate) use self::buf_reader::BufReader;
+
+/// A stand-in for [`std::io::BufReader`], whose buffer can't be wiped, used by the `read`
+/// cryptostreams when built with the `zeroize` feature.
+#[cfg(feature = "zeroize")]
+mod buf_reader {
+    use super::Secret;
+    use std::io::{BufRead, IoSliceMut, Read, Result, Seek, SeekFrom};
+
+    const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+    pub(crate) struct BufReader<R> {
+        inner: R,
+        buffer: Secret<Box<[u8]>>,
+        /// The range of `buffer` yet to be consumed.
+        pos: usize,
+        filled: usize,
+    }
+
+    impl<R: Read> BufReader<R> {
+        pub fn new(inner: R) -> Self {
+            Self::with_capacity(DEFAULT_CAPACITY, inner)
+        }
+
+        pub fn with_capacity(capacity: usize, inner: R) -> Self {
+            Self {
+                inner,
+                buffer: Secret::new(vec![0u8; capacity].into_boxed_slice()),
+                pos: 0,
+                filled: 0,
+            }
+        }
+
+        pub fn into_inner(self) -> R {
+            // `buffer` is wiped as it is dropped here.
+            self.inner
+        }
+
+        fn discard_buffer(&mut self) {
+            self.pos = 0;
+            self.filled = 0;
+        }
+    }
+
+    impl<R: Read> Read for BufReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            // Bypass the buffer entirely for reads at least as large as it, as `std` does.
+            if self.pos == self.filled && buf.len() >= self.buffer.len() {
+                self.discard_buffer();
+                return self.inner.read(buf);
+            }
+
+            let n = self.fill_buf()?.read(buf)?;
+            self.consume(n);
+            Ok(n)
+        }
+
+        fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Result<usize> {
+            let n = self.fill_buf()?.read_vectored(bufs)?;
+            self.consume(n);
+            Ok(n)
+        }
+    }
+
+    impl<R: Read> BufRead for BufReader<R> {
+        fn fill_buf(&mut self) -> Result<&[u8]> {
+            if self.pos == self.filled {
+                self.filled = self.inner.read(&mut self.buffer)?;
+                self.pos = 0;
+            }
+            Ok(&self.buffer[self.pos..self.filled])
+        }
+
+        fn consume(&mut self, amt: usize) {
+            self.pos = std::cmp::min(self.pos + amt, self.filled);
+        }
+    }
+
+    impl<R: Read + Seek> Seek for BufReader<R> {
+        /// Seeks the underlying stream, discarding the buffer. As with `std`, a relative seek is
+        /// relative to the position of the `BufReader` rather than the underlying stream.
+        fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+            let result = match pos {
+                SeekFrom::Current(offset) => {
+                    let remaining = (self.filled - self.pos) as i64;
+                    self.inner.seek(SeekFrom::Current(offset - remaining))?
+                }
+                _ => self.inner.seek(pos)?,
+            };
+            self.discard_buffer();
+            Ok(result)
+        }
+    }
+}
@@ -0,0 +1,468 @@
+//! Streams compatible with libsodium's `crypto_secretstream_xchacha20poly1305` API.
+//!
+//! A secretstream is a sequence of messages encrypted with XChaCha20-Poly1305, each tagged to
+//! mark its place in the stream. The stream begins with a random 24-byte header, from which the
+//! key and nonce of the first message are derived, and every message is followed by the tag and
+//! MAC that authenticate it, adding [`ABYTES`] bytes to its length. The nonce of each message is
+//! derived from the MAC of the one before it, so that dropped, reordered, or duplicated messages
+//! all fail to authenticate.
+//!
+//! Each message carries a [`Tag`]:
+//!
+//! * [`Tag::Message`], the most common tag, adds no information about the message.
+//! * [`Tag::Push`] marks the end of a set of messages, without ending the stream.
+//! * [`Tag::Rekey`] derives a new key for the messages that follow, forgetting the previous one.
+//! * [`Tag::Final`] marks the end of the stream, and also rekeys.
+//!
+//! [`PushStream`] and [`PullStream`] encrypt and decrypt a secretstream a message at a time, as
+//! libsodium's `crypto_secretstream_xchacha20poly1305_push()` and `_pull()` do, leaving it to the
+//! caller to frame the messages. The `SecretStreamEncryptor` and `SecretStreamDecryptor` types of
+//! the [`read`](crate::read), [`write`](crate::write), and [`bufread`](crate::bufread) modules
+//! instead frame the stream in fixed-size chunks, as in the file encryption example of the
+//! libsodium documentation:
+//!
+//! ```text
+//! header || encrypted chunk (chunk_size + 17 bytes) || ... || final chunk (at most chunk_size + 16 bytes)
+//! ```
+//!
+//! Every chunk but the last holds exactly `chunk_size` bytes of plaintext and is tagged
+//! [`Tag::Message`], while the last holds anywhere from zero to `chunk_size - 1` bytes and is
+//! tagged [`Tag::Final`]. The chunk size must match on both ends; libsodium's example uses 4096
+//! bytes, which is the default here.
+//!
+//! Poly1305 is provided by the `poly1305` crate when the `rustcrypto` feature is enabled, and
+//! otherwise by OpenSSL 1.1.1 and later.
+
+use crate::backend;
+use crate::cipher::{Cipher, Mode};
+use crate::secret::Secret;
+use crate::segment::Segments;
+use std::convert::TryInto;
+use std::io::{Error, ErrorKind};
+
+/// The length of the key of a secretstream.
+pub const KEY_LEN: usize = 32;
+
+/// The length of the header at the start of a secretstream.
+pub const HEADER_LEN: usize = 24;
+
+/// The number of bytes each message grows by when encrypted: the encrypted tag and the MAC.
+pub const ABYTES: usize = 1 + MAC_LEN;
+
+/// The number of plaintext bytes in each chunk unless otherwise specified.
+pub(crate) const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+const MAC_LEN: usize = 16;
+const COUNTER_LEN: usize = 4;
+/// The length of the IETF ChaCha20 nonce: a 32-bit counter followed by the 8-byte "inonce".
+const NONCE_LEN: usize = COUNTER_LEN + 8;
+/// The length of the ChaCha20 block holding the encrypted tag, which the MAC covers in full.
+const BLOCK_LEN: usize = 64;
+
+/// The tag attached to each message of a secretstream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tag {
+    /// An ordinary message
+    Message = 0,
+    /// The last message of a set of messages, but not of the stream
+    Push = 1,
+    /// A message after which the key is replaced
+    Rekey = 2,
+    /// The last message of the stream
+    Final = 3,
+}
+
+impl Tag {
+    fn from_u8(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Tag::Message),
+            1 => Some(Tag::Push),
+            2 => Some(Tag::Rekey),
+            3 => Some(Tag::Final),
+            _ => None,
+        }
+    }
+}
+
+/// Encrypts a secretstream a message at a time.
+pub struct PushStream {
+    state: State,
+}
+
+impl PushStream {
+    /// Creates a new secretstream keyed with `key`, returning it along with the random header
+    /// which must precede its messages.
+    pub fn new(key: &[u8]) -> Result<(Self, [u8; HEADER_LEN]), crate::Error> {
+        let mut header = [0u8; HEADER_LEN];
+        backend::random_bytes(&mut header)?;
+        Ok((Self::with_header(key, &header)?, header))
+    }
+
+    pub(crate) fn with_header(key: &[u8], header: &[u8; HEADER_LEN]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            state: State::new(key, header)?,
+        })
+    }
+
+    /// Encrypts `message`, authenticating it along with the associated data `ad` (which may be
+    /// empty) and `tag`, and appends the result to `output`.
+    pub fn push(
+        &mut self,
+        message: &[u8],
+        ad: &[u8],
+        tag: Tag,
+        output: &mut Vec<u8>,
+    ) -> Result<(), crate::Error> {
+        self.state.push(message, ad, tag, output)
+    }
+
+    /// Replaces the key without attaching a tag to any message, as
+    /// `crypto_secretstream_xchacha20poly1305_rekey()` does. The decrypting side must call
+    /// [`PullStream::rekey()`] after pulling the same message.
+    pub fn rekey(&mut self) -> Result<(), crate::Error> {
+        self.state.rekey()
+    }
+}
+
+/// Decrypts a secretstream a message at a time.
+pub struct PullStream {
+    state: State,
+}
+
+impl PullStream {
+    /// Creates a new `PullStream` for the secretstream beginning with `header`, keyed with `key`.
+    pub fn new(key: &[u8], header: &[u8; HEADER_LEN]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            state: State::new(key, header)?,
+        })
+    }
+
+    /// Authenticates and decrypts the next message of the stream (along with the associated data
+    /// `ad` it was encrypted with), appending the plaintext to `output` and returning its tag.
+    ///
+    /// Fails with [`Error::AuthenticationFailed`](crate::Error::AuthenticationFailed) without
+    /// appending anything to `output` if the message is not authentic, in which case the stream
+    /// is left as it was.
+    pub fn pull(
+        &mut self,
+        ciphertext: &[u8],
+        ad: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<Tag, crate::Error> {
+        self.state.pull(ciphertext, ad, output)
+    }
+
+    /// Replaces the key as [`PushStream::rekey()`] does.
+    pub fn rekey(&mut self) -> Result<(), crate::Error> {
+        self.state.rekey()
+    }
+}
+
+/// The key and nonce shared by [`PushStream`] and [`PullStream`], which evolve with every message.
+struct State {
+    key: Secret<[u8; KEY_LEN]>,
+    nonce: [u8; NONCE_LEN],
+}
+
+impl State {
+    fn new(key: &[u8], header: &[u8; HEADER_LEN]) -> Result<Self, crate::Error> {
+        if key.len() != KEY_LEN {
+            return Err(crate::Error::InvalidKeyLength);
+        }
+
+        let mut state = Self {
+            key: hchacha20(key, &header[..16]),
+            nonce: [0u8; NONCE_LEN],
+        };
+        state.nonce[COUNTER_LEN..].copy_from_slice(&header[16..]);
+        state.reset_counter();
+
+        Ok(state)
+    }
+
+    fn reset_counter(&mut self) {
+        self.nonce[..COUNTER_LEN].copy_from_slice(&1u32.to_le_bytes());
+    }
+
+    /// XORs `data` with the ChaCha20 keystream of the current message, starting at `block`.
+    fn xor(&self, block: u32, data: &mut [u8]) -> Result<(), crate::Error> {
+        let mut iv = [0u8; 16];
+        iv[..4].copy_from_slice(&block.to_le_bytes());
+        iv[4..].copy_from_slice(&self.nonce);
+        let mut crypter =
+            backend::new_cipher(Cipher::chacha20(), Mode::Encrypt, &self.key[..], &iv)?;
+        crypter.update_in_place(data)?;
+        Ok(())
+    }
+
+    /// Computes the MAC of a message from its associated data, the block holding its encrypted
+    /// tag, and its ciphertext.
+    fn mac(
+        &self,
+        ad: &[u8],
+        block: &[u8; BLOCK_LEN],
+        ciphertext: &[u8],
+    ) -> Result<[u8; MAC_LEN], crate::Error> {
+        let mut mac_key = Secret::new([0u8; BLOCK_LEN]);
+        self.xor(0, &mut mac_key[..])?;
+
+        // libsodium pads the ciphertext with `len % 16` zeroes, rather than up to a multiple of 16
+        // as it does the associated data.
+        let pad = [0u8; 16];
+        backend::poly1305(
+            &mac_key[..32],
+            &[
+                ad,
+                &pad[..(16 - ad.len() % 16) % 16],
+                block,
+                ciphertext,
+                &pad[..ciphertext.len() % 16],
+                &(ad.len() as u64).to_le_bytes(),
+                &((BLOCK_LEN + ciphertext.len()) as u64).to_le_bytes(),
+            ],
+        )
+    }
+
+    fn push(
+        &mut self,
+        message: &[u8],
+        ad: &[u8],
+        tag: Tag,
+        output: &mut Vec<u8>,
+    ) -> Result<(), crate::Error> {
+        let mut block = [0u8; BLOCK_LEN];
+        block[0] = tag as u8;
+        self.xor(1, &mut block)?;
+
+        let start = output.len();
+        output.push(block[0]);
+        output.extend_from_slice(message);
+        self.xor(2, &mut output[start + 1..])?;
+        let mac = self.mac(ad, &block, &output[start + 1..])?;
+        output.extend_from_slice(&mac);
+
+        self.advance(&mac, tag as u8)
+    }
+
+    fn pull(
+        &mut self,
+        ciphertext: &[u8],
+        ad: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<Tag, crate::Error> {
+        if ciphertext.len() < ABYTES {
+            return Err(crate::Error::Truncated);
+        }
+        let (encrypted, mac) = ciphertext[1..].split_at(ciphertext.len() - ABYTES);
+
+        let mut block = [0u8; BLOCK_LEN];
+        block[0] = ciphertext[0];
+        self.xor(1, &mut block)?;
+        let tag = block[0];
+        block[0] = ciphertext[0];
+        let expected = self.mac(ad, &block, encrypted)?;
+        if !backend::constant_time_eq(&expected, mac) {
+            return Err(crate::Error::AuthenticationFailed);
+        }
+        let tag = Tag::from_u8(tag).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "The message has an unknown secretstream tag!",
+            )
+        })?;
+
+        let start = output.len();
+        output.extend_from_slice(encrypted);
+        self.xor(2, &mut output[start..])?;
+
+        self.advance(&expected, tag as u8)?;
+        Ok(tag)
+    }
+
+    /// Derives the nonce of the next message from the MAC of the last, rekeying if its `tag`
+    /// calls for it or the counter has wrapped around.
+    fn advance(&mut self, mac: &[u8; MAC_LEN], tag: u8) -> Result<(), crate::Error> {
+        for (inonce, mac) in self.nonce[COUNTER_LEN..].iter_mut().zip(mac) {
+            *inonce ^= mac;
+        }
+        let counter = u32::from_le_bytes(self.nonce[..COUNTER_LEN].try_into().unwrap());
+        let counter = counter.wrapping_add(1);
+        self.nonce[..COUNTER_LEN].copy_from_slice(&counter.to_le_bytes());
+
+        if tag & Tag::Rekey as u8 != 0 || counter == 0 {
+            self.rekey()?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the key and inonce with the keystream they're XORed with, and resets the counter.
+    fn rekey(&mut self) -> Result<(), crate::Error> {
+        let mut next = Secret::new([0u8; KEY_LEN + NONCE_LEN - COUNTER_LEN]);
+        next[..KEY_LEN].copy_from_slice(&self.key[..]);
+        next[KEY_LEN..].copy_from_slice(&self.nonce[COUNTER_LEN..]);
+        self.xor(0, &mut next[..])?;
+
+        self.key.copy_from_slice(&next[..KEY_LEN]);
+        self.nonce[COUNTER_LEN..].copy_from_slice(&next[KEY_LEN..]);
+        self.reset_counter();
+        Ok(())
+    }
+}
+
+/// Derives the subkey the stream is encrypted with from `key` and the first 16 bytes of the
+/// header, as the "X" of XChaCha20 does.
+fn hchacha20(key: &[u8], input: &[u8]) -> Secret<[u8; KEY_LEN]> {
+    let mut state = Secret::new([0u32; 16]);
+    state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+    for (word, bytes) in state[4..]
+        .iter_mut()
+        .zip(key.chunks_exact(4).chain(input.chunks_exact(4)))
+    {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    let mut subkey = Secret::new([0u8; KEY_LEN]);
+    let words = state[..4].iter().chain(&state[12..]);
+    for (bytes, word) in subkey.chunks_exact_mut(4).zip(words) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    subkey
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// Encrypts or decrypts a secretstream framed in fixed-size chunks, on behalf of the
+/// `SecretStreamEncryptor` and `SecretStreamDecryptor` cryptostreams.
+pub(crate) struct Chunker {
+    chunk_size: usize,
+    state: ChunkerState,
+    finished: bool,
+}
+
+enum ChunkerState {
+    /// The header is written out ahead of the first chunk.
+    Push {
+        stream: PushStream,
+        header: Option<[u8; HEADER_LEN]>,
+    },
+    /// The header is read in as a "chunk" of its own, before the stream can be decrypted.
+    AwaitingHeader {
+        key: Secret<Vec<u8>>,
+    },
+    Pull(PullStream),
+}
+
+impl Chunker {
+    pub fn new(mode: Mode, key: &[u8], chunk_size: usize) -> Result<Self, crate::Error> {
+        assert!(chunk_size > 0, "The chunk size must be non-zero!");
+
+        let state = match mode {
+            Mode::Encrypt => {
+                let (stream, header) = PushStream::new(key)?;
+                ChunkerState::Push {
+                    stream,
+                    header: Some(header),
+                }
+            }
+            Mode::Decrypt if key.len() != KEY_LEN => return Err(crate::Error::InvalidKeyLength),
+            Mode::Decrypt => ChunkerState::AwaitingHeader {
+                key: Secret::new(key.to_vec()),
+            },
+        };
+
+        Ok(Self {
+            chunk_size,
+            state,
+            finished: false,
+        })
+    }
+}
+
+impl Segments for Chunker {
+    fn input_len(&self) -> usize {
+        match self.state {
+            ChunkerState::Push { .. } => self.chunk_size,
+            ChunkerState::AwaitingHeader { .. } => HEADER_LEN,
+            ChunkerState::Pull(_) => self.chunk_size + ABYTES,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn process(&mut self, input: &[u8], last: bool, output: &mut Vec<u8>) -> Result<(), Error> {
+        if self.finished {
+            return Err(Error::other(
+                "No further messages may follow the final message of the stream!",
+            ));
+        }
+
+        match &mut self.state {
+            ChunkerState::Push { stream, header } => {
+                if let Some(header) = header.take() {
+                    output.extend_from_slice(&header);
+                }
+                // As in libsodium's example, the final chunk is never a full one, lest it be
+                // mistaken for one followed by more.
+                if input.len() == self.chunk_size {
+                    stream.push(input, &[], Tag::Message, output)?;
+                    if last {
+                        stream.push(&[], &[], Tag::Final, output)?;
+                    }
+                } else {
+                    stream.push(input, &[], Tag::Final, output)?;
+                }
+                self.finished = last;
+            }
+            ChunkerState::AwaitingHeader { key } => {
+                let header = input.try_into().map_err(|_| crate::Error::Truncated)?;
+                let stream = PullStream::new(key, header)?;
+                self.state = ChunkerState::Pull(stream);
+                if last {
+                    return Err(crate::Error::Truncated.into());
+                }
+            }
+            ChunkerState::Pull(stream) => {
+                let start = output.len();
+                let tag = stream.pull(input, &[], output)?;
+                let result = match (tag, last) {
+                    (Tag::Final, false) => Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "The ciphertext continues past the final message of the stream!",
+                    )),
+                    (Tag::Final, true) => Ok(()),
+                    (_, true) => Err(crate::Error::Truncated.into()),
+                    (_, false) => Ok(()),
+                };
+                if result.is_err() {
+                    output.truncate(start);
+                }
+                self.finished = tag == Tag::Final;
+                result?;
+            }
+        }
+
+        Ok(())
+    }
+}
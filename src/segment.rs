@@ -0,0 +1,176 @@
+//! Internal support shared by the segmented (STREAM) cryptostream variants.
+//!
+//! The segmented format splits the plaintext into fixed-size segments, each of which is sealed
+//! independently with an AEAD cipher (AES-GCM, AES-GCM-SIV, or ChaCha20-Poly1305) and followed by
+//! its own authentication tag. The nonce for each segment is derived from a per-stream prefix, the
+//! index of the segment, and a flag marking the final segment:
+//!
+//! ```text
+//! nonce = nonce_prefix (7 bytes) || segment index (4 bytes, big-endian) || last segment (1 byte)
+//! ```
+//!
+//! This is the STREAM construction of Hoang, Reyhanitabar, Rogaway and Vizár, which ensures that
+//! reordered, duplicated, or tampered segments all fail authentication, as does a stream that has
+//! been truncated at a segment boundary (as its final segment will not be flagged as such).
+//!
+//! Every segment but the last contains exactly `segment_size` bytes of plaintext, while the last
+//! contains anywhere from zero to `segment_size` bytes. A stream therefore always contains at
+//! least one segment, even if the plaintext is empty.
+
+use crate::aead::{self, TAG_LEN};
+use crate::backend;
+use crate::cipher::{Cipher, Mode};
+use crate::error;
+use crate::secret::Secret;
+use std::io::Error;
+
+/// The length of the caller-provided nonce prefix, unique to each stream encrypted with a key.
+pub(crate) const NONCE_PREFIX_LEN: usize = 7;
+
+/// The length of the nonce derived for each segment.
+const NONCE_LEN: usize = NONCE_PREFIX_LEN + 4 + 1;
+
+/// The number of plaintext bytes sealed in each segment unless otherwise specified.
+pub(crate) const DEFAULT_SEGMENT_SIZE: usize = 64 * 1024;
+
+/// Seals or opens successive segments of a stream, on behalf of the segmented cryptostreams.
+pub(crate) trait Segments {
+    /// The length of the next (non-final) segment of the input stream.
+    fn input_len(&self) -> usize;
+
+    /// Whether or not the final segment has already been processed.
+    fn is_finished(&self) -> bool;
+
+    /// Seals (when encrypting) or opens (when decrypting) the next segment of the stream,
+    /// appending the result to `output`. `input` must be exactly [`input_len()`](Self::input_len)
+    /// bytes long unless it is the `last` segment.
+    ///
+    /// When decrypting, nothing is appended to `output` unless the segment is authentic.
+    fn process(&mut self, input: &[u8], last: bool, output: &mut Vec<u8>) -> Result<(), Error>;
+}
+
+/// Seals or opens successive segments of a stream with the STREAM construction.
+pub(crate) struct Segmenter {
+    mode: Mode,
+    cipher: Cipher,
+    key: Secret<Vec<u8>>,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    segment_size: usize,
+    /// The index of the next segment, or `None` once the final segment has been processed or the
+    /// index space has been exhausted.
+    index: Option<u32>,
+}
+
+impl Segmenter {
+    pub fn new(
+        mode: Mode,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        assert!(segment_size > 0, "The segment size must be non-zero!");
+
+        let segmenter = Self {
+            mode,
+            cipher,
+            key: Secret::new(key.to_vec()),
+            nonce_prefix: *nonce_prefix,
+            segment_size,
+            index: Some(0),
+        };
+
+        // Surface any issues with the cipher or key upfront rather than on first use.
+        let nonce = segmenter.nonce(0, false);
+        error::check_lengths(cipher, key, &nonce, true)?;
+        // Sealing an empty message works for the ciphers (e.g. GCM-SIV) that can't be streamed.
+        backend::seal(cipher, key, &nonce, &[], &[], &mut Vec::new())?;
+
+        Ok(segmenter)
+    }
+
+    /// The number of plaintext bytes in each (non-final) segment.
+    pub fn segment_size(&self) -> usize {
+        self.segment_size
+    }
+
+    /// Sets the index of the next segment to be processed.
+    pub fn seek(&mut self, index: u32) {
+        self.index = Some(index);
+    }
+
+    fn nonce(&self, index: u32, last: bool) -> [u8; NONCE_LEN] {
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.nonce_prefix);
+        nonce[NONCE_PREFIX_LEN..][..4].copy_from_slice(&index.to_be_bytes());
+        nonce[NONCE_LEN - 1] = last as u8;
+        nonce
+    }
+
+    /// Opens a segment sealed with `nonce`, returning whether it is authentic. Nothing is
+    /// appended to `output` unless it is.
+    fn open(&self, nonce: &[u8], input: &[u8], output: &mut Vec<u8>) -> Result<bool, Error> {
+        let (ciphertext, tag) = input.split_at(input.len() - TAG_LEN);
+        match backend::open(self.cipher, &self.key, nonce, &[], ciphertext, tag, output) {
+            Ok(()) => Ok(true),
+            Err(backend::Error::AuthenticationFailed) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl Segments for Segmenter {
+    fn input_len(&self) -> usize {
+        match self.mode {
+            Mode::Encrypt => self.segment_size,
+            Mode::Decrypt => self.segment_size + TAG_LEN,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.index.is_none()
+    }
+
+    fn process(&mut self, input: &[u8], last: bool, output: &mut Vec<u8>) -> Result<(), Error> {
+        let index = self.index.ok_or_else(|| {
+            Error::other("No further segments may follow the final segment of the stream!")
+        })?;
+        debug_assert!(last || input.len() == self.input_len());
+        debug_assert!(input.len() <= self.input_len());
+
+        let nonce = self.nonce(index, last);
+        match self.mode {
+            Mode::Encrypt => {
+                backend::seal(self.cipher, &self.key, &nonce, &[], input, output)?;
+            }
+            Mode::Decrypt => {
+                if input.len() < TAG_LEN {
+                    return Err(aead::truncated());
+                }
+                if !self.open(&nonce, input, output)? {
+                    // A complete segment that fails to open as the last but opens as any other
+                    // segment means the stream was cut off at a segment boundary.
+                    if last
+                        && input.len() == self.input_len()
+                        && self.open(&self.nonce(index, false), input, &mut Vec::new())?
+                    {
+                        return Err(aead::truncated());
+                    }
+                    return Err(aead::authentication_failed());
+                }
+            }
+        }
+
+        self.index = match last {
+            true => None,
+            false => index.checked_add(1),
+        };
+        if self.index.is_none() && !last {
+            return Err(Error::other(
+                "Exhausted the segment index space of the stream!",
+            ));
+        }
+
+        Ok(())
+    }
+}
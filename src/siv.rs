@@ -0,0 +1,73 @@
+//! AES-GCM-SIV, a nonce-misuse-resistant AEAD cipher for the segmented cryptostreams.
+//!
+//! With AES-GCM or ChaCha20-Poly1305, encrypting two streams with the same key and nonce prefix
+//! is catastrophic: the XOR of their plaintexts is revealed, and the authentication key can be
+//! recovered and used to forge segments. This is all too easy to do by accident, e.g. when a VM
+//! is snapshotted and restored, replaying the random nonce prefix it generated.
+//!
+//! AES-GCM-SIV ([RFC 8452](https://www.rfc-editor.org/rfc/rfc8452)) derives the nonce it encrypts
+//! with from the plaintext itself, so that reusing a nonce prefix only reveals which segments of
+//! the two streams are identical (those with the same index and plaintext). Nonce prefixes should
+//! still be unique; AES-GCM-SIV merely limits the damage when they are not.
+//!
+//! As AES-GCM-SIV must see the entirety of a message before encrypting any of it, it can only be
+//! used with the segmented cryptostreams (e.g. [`write::SegmentedEncryptor`] and
+//! [`read::SegmentedDecryptor`]), which seal each segment as a single message. The other
+//! cryptostreams reject it with [`Error::UnsupportedCipher`].
+//!
+//! ```
+//! use cryptostream::{siv, write};
+//! use std::io::Write;
+//!
+//! let cipher = siv::aes_256_gcm_siv()?;
+//! let key: [u8; 32] = [0x42; 32];
+//! let nonce_prefix: [u8; 7] = [0x17; 7];
+//!
+//! let mut encryptor = write::SegmentedEncryptor::new(Vec::new(), cipher, &key, &nonce_prefix)?;
+//! encryptor.write_all(b"It was the best of times, it was the worst of times.")?;
+//! let encrypted = encryptor.finish()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! AES-GCM-SIV is provided by the `aes-gcm-siv` crate when the `rustcrypto` feature is enabled,
+//! and otherwise by OpenSSL 3.2 and later.
+//!
+//! [`write::SegmentedEncryptor`]: crate::write::SegmentedEncryptor
+//! [`read::SegmentedDecryptor`]: crate::read::SegmentedDecryptor
+
+use crate::cipher::{Cipher, Id};
+use crate::Error;
+
+/// AES-GCM-SIV with a 128-bit key.
+///
+/// Fails with [`Error::UnsupportedCipher`] if the backend does not provide it.
+pub fn aes_128_gcm_siv() -> Result<Cipher, Error> {
+    supported(Cipher::aes_128_gcm_siv())
+}
+
+/// AES-GCM-SIV with a 256-bit key.
+///
+/// Fails with [`Error::UnsupportedCipher`] if the backend does not provide it.
+pub fn aes_256_gcm_siv() -> Result<Cipher, Error> {
+    supported(Cipher::aes_256_gcm_siv())
+}
+
+/// The RustCrypto backend always provides GCM-SIV.
+#[cfg(feature = "rustcrypto")]
+fn supported(cipher: Cipher) -> Result<Cipher, Error> {
+    Ok(cipher)
+}
+
+/// Checks that the linked OpenSSL provides GCM-SIV.
+#[cfg(not(feature = "rustcrypto"))]
+fn supported(cipher: Cipher) -> Result<Cipher, Error> {
+    match crate::backend::fetch_gcm_siv(cipher) {
+        Some(_) => Ok(cipher),
+        None => Err(Error::UnsupportedCipher),
+    }
+}
+
+/// Whether or not `cipher` is one of the GCM-SIV ciphers returned by this module.
+pub(crate) fn is_gcm_siv(cipher: Cipher) -> bool {
+    matches!(cipher.0, Id::Aes128GcmSiv | Id::Aes256GcmSiv)
+}
@@ -0,0 +1,166 @@
+//! Tests for the authenticated (AEAD) cryptostream variants.
+
+use super::symm::{decrypt_aead, encrypt_aead};
+use super::TEST;
+use crate::Cipher;
+use crate::{read, write};
+use std::io::{ErrorKind, Read, Write};
+
+const AAD: &[u8] = b"associated data";
+
+fn init_secrets() -> ([u8; 256 / 8], [u8; 96 / 8]) {
+    (rand::random(), rand::random())
+}
+
+fn encrypt(plaintext: &[u8], cipher: Cipher, key: &[u8], iv: &[u8]) -> Vec<u8> {
+    let mut encryptor = write::AeadEncryptor::new(Vec::new(), cipher, key, iv, AAD).unwrap();
+    encryptor.write_all(plaintext).unwrap();
+    encryptor.finish().unwrap()
+}
+
+fn decrypt(ciphertext: &[u8], cipher: Cipher, key: &[u8], iv: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decryptor = read::AeadDecryptor::new(ciphertext, cipher, key, iv, AAD).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted)?;
+    Ok(decrypted)
+}
+
+#[test]
+fn write_encrypt_appends_tag() {
+    for &cipher in &[Cipher::aes_256_gcm(), Cipher::chacha20_poly1305()] {
+        let (key, iv) = init_secrets();
+        let encrypted = encrypt(TEST, cipher, &key, &iv);
+
+        let mut tag = [0u8; 16];
+        let expected = encrypt_aead(cipher, &key, Some(&iv), AAD, TEST, &mut tag).unwrap();
+        assert_eq!(&encrypted[..TEST.len()], expected.as_slice());
+        assert_eq!(&encrypted[TEST.len()..], &tag);
+    }
+}
+
+#[test]
+fn read_encrypt_appends_tag() {
+    let (key, iv) = init_secrets();
+    let cipher = Cipher::aes_256_gcm();
+
+    let mut encryptor = read::AeadEncryptor::new(TEST, cipher, &key, &iv, AAD).unwrap();
+    let mut encrypted = Vec::new();
+    encryptor.read_to_end(&mut encrypted).unwrap();
+
+    assert_eq!(encrypted, encrypt(TEST, cipher, &key, &iv));
+}
+
+#[test]
+fn read_decrypt_verifies_tag() {
+    for &cipher in &[Cipher::aes_256_gcm(), Cipher::chacha20_poly1305()] {
+        let (key, iv) = init_secrets();
+        let encrypted = encrypt(TEST, cipher, &key, &iv);
+
+        let decrypted = decrypt(&encrypted, cipher, &key, &iv).unwrap();
+        assert_eq!(decrypted, TEST);
+    }
+}
+
+#[test]
+fn read_decrypt_small_reads() {
+    let (key, iv) = init_secrets();
+    let cipher = Cipher::aes_128_gcm();
+    let key = &key[..128 / 8];
+    let encrypted = encrypt(TEST, cipher, key, &iv);
+
+    let mut decryptor = read::AeadDecryptor::new(&encrypted[..], cipher, key, &iv, AAD).unwrap();
+    let mut decrypted = Vec::new();
+    let mut buffer = [0u8; 3];
+    loop {
+        match decryptor.read(&mut buffer).unwrap() {
+            0 => break,
+            n => decrypted.extend_from_slice(&buffer[..n]),
+        }
+    }
+
+    assert_eq!(decrypted, TEST);
+}
+
+#[test]
+fn write_decrypt_verifies_tag() {
+    let (key, iv) = init_secrets();
+    let cipher = Cipher::chacha20_poly1305();
+    let encrypted = encrypt(TEST, cipher, &key, &iv);
+
+    // Feed the ciphertext a few bytes at a time so the tag straddles several writes.
+    let mut decryptor = write::AeadDecryptor::new(Vec::new(), cipher, &key, &iv, AAD).unwrap();
+    for chunk in encrypted.chunks(7) {
+        decryptor.write_all(chunk).unwrap();
+    }
+    let decrypted = decryptor.finish().unwrap();
+
+    assert_eq!(decrypted, TEST);
+}
+
+#[test]
+fn empty_plaintext_is_authenticated() {
+    let (key, iv) = init_secrets();
+    let cipher = Cipher::aes_256_gcm();
+
+    let encrypted = encrypt(b"", cipher, &key, &iv);
+    assert_eq!(encrypted.len(), 16);
+    assert!(decrypt(&encrypted, cipher, &key, &iv).unwrap().is_empty());
+
+    let mut tag = [0u8; 16];
+    encrypt_aead(cipher, &key, Some(&iv), AAD, b"", &mut tag).unwrap();
+    assert_eq!(encrypted, tag);
+}
+
+#[test]
+fn tampered_ciphertext_is_rejected() {
+    let (key, iv) = init_secrets();
+    let cipher = Cipher::aes_256_gcm();
+    let encrypted = encrypt(TEST, cipher, &key, &iv);
+
+    for i in &[0, TEST.len() - 1, TEST.len(), encrypted.len() - 1] {
+        let mut tampered = encrypted.clone();
+        tampered[*i] ^= 0x01;
+
+        let err = decrypt(&tampered, cipher, &key, &iv).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let mut decryptor = write::AeadDecryptor::new(Vec::new(), cipher, &key, &iv, AAD).unwrap();
+        decryptor.write_all(&tampered).unwrap();
+        let err = decryptor.finish().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn mismatched_aad_is_rejected() {
+    let (key, iv) = init_secrets();
+    let cipher = Cipher::aes_256_gcm();
+    let mut tag = [0u8; 16];
+    let mut encrypted = encrypt_aead(cipher, &key, Some(&iv), b"other", TEST, &mut tag).unwrap();
+    encrypted.extend_from_slice(&tag);
+
+    let err = decrypt(&encrypted, cipher, &key, &iv).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // Sanity check the ciphertext itself
+    let decrypted = decrypt_aead(
+        cipher,
+        &key,
+        Some(&iv),
+        b"other",
+        &encrypted[..TEST.len()],
+        &tag,
+    );
+    assert_eq!(decrypted.unwrap(), TEST);
+}
+
+#[test]
+fn missing_tag_is_rejected() {
+    let (key, iv) = init_secrets();
+    let cipher = Cipher::aes_256_gcm();
+    let encrypted = encrypt(TEST, cipher, &key, &iv);
+
+    assert!(decrypt(&encrypted[..TEST.len()], cipher, &key, &iv).is_err());
+    assert!(decrypt(&encrypted[..10], cipher, &key, &iv).is_err());
+    assert!(decrypt(b"", cipher, &key, &iv).is_err());
+}
@@ -0,0 +1,145 @@
+//! Tests for the `futures-io` `AsyncRead`/`AsyncBufRead`/`AsyncWrite` cryptostreams.
+
+use super::symm::encrypt;
+use super::TEST;
+use crate::futures::{Decryptor, Encryptor};
+use crate::Cipher;
+use ::futures::executor::block_on;
+use ::futures::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use ::futures::io::{BufReader, Cursor};
+use ::futures::stream::TryStreamExt;
+use std::io::Error;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+fn init_secrets() -> (Cipher, [u8; 128 / 8], [u8; 128 / 8]) {
+    (Cipher::aes_128_cbc(), rand::random(), rand::random())
+}
+
+/// Reads and writes a single byte at a time, returning `Poll::Pending` before each one.
+struct Trickle<T> {
+    inner: T,
+    pending: bool,
+}
+
+impl<T> Trickle<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            pending: true,
+        }
+    }
+
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.pending = !self.pending;
+        if self.pending {
+            Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Trickle<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        ready!(self.poll_pending(cx));
+        let len = std::cmp::min(buf.len(), 1);
+        Pin::new(&mut self.inner).poll_read(cx, &mut buf[..len])
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Trickle<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Error>> {
+        ready!(self.poll_pending(cx));
+        let len = std::cmp::min(buf.len(), 1);
+        Pin::new(&mut self.inner).poll_write(cx, &buf[..len])
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[test]
+fn write_encrypt() {
+    let (cipher, key, iv) = init_secrets();
+
+    let mut encryptor = Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+    block_on(encryptor.write_all(TEST)).unwrap();
+    block_on(encryptor.close()).unwrap();
+
+    let expected = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+    assert_eq!(encryptor.into_inner(), expected);
+}
+
+#[test]
+fn read_decrypt() {
+    let (cipher, key, iv) = init_secrets();
+    let encrypted = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+
+    let mut decryptor = Decryptor::new(Cursor::new(encrypted), cipher, &key, &iv).unwrap();
+    let mut decrypted = Vec::new();
+    block_on(decryptor.read_to_end(&mut decrypted)).unwrap();
+
+    assert_eq!(decrypted, TEST);
+}
+
+#[test]
+fn read_decrypt_lines() {
+    let (cipher, key, iv) = init_secrets();
+    let plaintext = b"first line\nsecond line\nthird line";
+    let encrypted = encrypt(cipher, &key, Some(&iv), plaintext).unwrap();
+
+    let decryptor = Decryptor::new(Cursor::new(encrypted), cipher, &key, &iv).unwrap();
+    let lines: Vec<String> = block_on(decryptor.lines().try_collect()).unwrap();
+
+    assert_eq!(lines, &["first line", "second line", "third line"]);
+}
+
+#[test]
+fn read_encrypt_write_decrypt() {
+    let (cipher, key, iv) = init_secrets();
+
+    let mut encryptor = Encryptor::new(Cursor::new(TEST), cipher, &key, &iv).unwrap();
+    let mut encrypted = Vec::new();
+    block_on(encryptor.read_to_end(&mut encrypted)).unwrap();
+
+    let mut decryptor = Decryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+    block_on(decryptor.write_all(&encrypted)).unwrap();
+    block_on(decryptor.close()).unwrap();
+
+    assert_eq!(decryptor.into_inner(), TEST);
+}
+
+#[test]
+fn pending_mid_block() {
+    let (cipher, key, iv) = init_secrets();
+    let encrypted = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+
+    // Every block straddles several polls, with `Poll::Pending` returned in between.
+    let source = BufReader::with_capacity(1, Trickle::new(Cursor::new(encrypted.clone())));
+    let mut decryptor = Decryptor::new(source, cipher, &key, &iv).unwrap();
+    let mut decrypted = Vec::new();
+    block_on(decryptor.read_to_end(&mut decrypted)).unwrap();
+    assert_eq!(decrypted, TEST);
+
+    let mut encryptor = Encryptor::new(Trickle::new(Vec::new()), cipher, &key, &iv).unwrap();
+    for chunk in TEST.chunks(3) {
+        block_on(encryptor.write_all(chunk)).unwrap();
+    }
+    block_on(encryptor.close()).unwrap();
+    assert_eq!(encryptor.into_inner().inner, encrypted);
+}
@@ -0,0 +1,175 @@
+//! Tests for the tokio `AsyncRead`/`AsyncBufRead`/`AsyncWrite` cryptostreams.
+
+use super::symm::{decrypt, encrypt};
+use super::TEST;
+use crate::tokio::{Decryptor, Encryptor};
+use crate::Cipher;
+use ::tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use ::tokio::io::{BufReader, ReadBuf};
+use std::io::Error;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+fn init_secrets() -> (Cipher, [u8; 128 / 8], [u8; 128 / 8]) {
+    (Cipher::aes_128_cbc(), rand::random(), rand::random())
+}
+
+/// Reads and writes a single byte at a time, returning `Poll::Pending` before each one.
+struct Trickle<T> {
+    inner: T,
+    pending: bool,
+}
+
+impl<T> Trickle<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            pending: true,
+        }
+    }
+
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.pending = !self.pending;
+        if self.pending {
+            Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Trickle<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<Result<(), Error>> {
+        ::std::task::ready!(self.poll_pending(cx));
+        let mut byte = [0u8; 1];
+        let mut one = ReadBuf::new(&mut byte);
+        ::std::task::ready!(Pin::new(&mut self.inner).poll_read(cx, &mut one))?;
+        buf.put_slice(one.filled());
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Trickle<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Error>> {
+        ::std::task::ready!(self.poll_pending(cx));
+        let len = std::cmp::min(buf.len(), 1);
+        Pin::new(&mut self.inner).poll_write(cx, &buf[..len])
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[::tokio::test]
+async fn write_encrypt() {
+    let (cipher, key, iv) = init_secrets();
+
+    let mut encryptor = Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+    encryptor.write_all(TEST).await.unwrap();
+    encryptor.shutdown().await.unwrap();
+
+    let expected = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+    assert_eq!(encryptor.into_inner(), expected);
+}
+
+#[::tokio::test]
+async fn write_decrypt() {
+    let (cipher, key, iv) = init_secrets();
+    let encrypted = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+
+    let mut decryptor = Decryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+    decryptor.write_all(&encrypted).await.unwrap();
+    decryptor.shutdown().await.unwrap();
+
+    assert_eq!(decryptor.into_inner(), TEST);
+}
+
+#[::tokio::test]
+async fn read_encrypt() {
+    let (cipher, key, iv) = init_secrets();
+
+    let mut encryptor = Encryptor::new(TEST, cipher, &key, &iv).unwrap();
+    let mut encrypted = Vec::new();
+    encryptor.read_to_end(&mut encrypted).await.unwrap();
+
+    let decrypted = decrypt(cipher, &key, Some(&iv), &encrypted).unwrap();
+    assert_eq!(decrypted, TEST);
+}
+
+#[::tokio::test]
+async fn read_decrypt_lines() {
+    let (cipher, key, iv) = init_secrets();
+    let plaintext = b"first line\nsecond line\nthird line";
+    let encrypted = encrypt(cipher, &key, Some(&iv), plaintext).unwrap();
+
+    let decryptor = Decryptor::new(encrypted.as_slice(), cipher, &key, &iv).unwrap();
+    let mut lines = decryptor.lines();
+    let mut decrypted = Vec::new();
+    while let Some(line) = lines.next_line().await.unwrap() {
+        decrypted.push(line);
+    }
+
+    assert_eq!(decrypted, &["first line", "second line", "third line"]);
+}
+
+#[::tokio::test]
+async fn pending_mid_block() {
+    let (cipher, key, iv) = init_secrets();
+    let encrypted = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+
+    // Decrypt from a source that only returns one byte at a time, interspersed with pending
+    // polls, so that every block straddles several polls.
+    let source = BufReader::with_capacity(1, Trickle::new(encrypted.as_slice()));
+    let mut decryptor = Decryptor::new(source, cipher, &key, &iv).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).await.unwrap();
+    assert_eq!(decrypted, TEST);
+
+    // And encrypt into a destination that behaves the same way.
+    let mut encryptor = Encryptor::new(Trickle::new(Vec::new()), cipher, &key, &iv).unwrap();
+    for chunk in TEST.chunks(5) {
+        encryptor.write_all(chunk).await.unwrap();
+        encryptor.flush().await.unwrap();
+    }
+    encryptor.shutdown().await.unwrap();
+    assert_eq!(encryptor.into_inner().inner, encrypted);
+}
+
+#[::tokio::test]
+async fn empty_roundtrip() {
+    let (cipher, key, iv) = init_secrets();
+
+    let mut encryptor = Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+    encryptor.shutdown().await.unwrap();
+    assert!(encryptor.into_inner().is_empty());
+
+    let mut decryptor = Decryptor::new(&b""[..], cipher, &key, &iv).unwrap();
+    let mut decrypted = Vec::new();
+    assert_eq!(decryptor.read_to_end(&mut decrypted).await.unwrap(), 0);
+}
+
+#[::tokio::test]
+async fn bad_padding_is_an_error() {
+    let (cipher, key, iv) = init_secrets();
+    let mut encrypted = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+    let last = encrypted.len() - 1;
+    encrypted[last] ^= 0xFF;
+
+    let mut decryptor = Decryptor::new(encrypted.as_slice(), cipher, &key, &iv).unwrap();
+    let mut decrypted = Vec::new();
+    assert!(decryptor.read_to_end(&mut decrypted).await.is_err());
+}
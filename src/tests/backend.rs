@@ -0,0 +1,320 @@
+//! Tests checking the RustCrypto backend against the OpenSSL backend.
+
+use crate::backend::{Backend, Error, OpenSsl, RustCrypto, StreamCipher, TAG_LEN};
+use crate::{Cipher, Mode};
+use rand::Rng;
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    (0..len).map(|_| rand::random()).collect()
+}
+
+/// Feeds `input` to `crypter` in randomly sized chunks, returning the output and the result of
+/// finalizing it.
+fn transform(crypter: &mut dyn StreamCipher, input: &[u8]) -> (Vec<u8>, Result<(), Error>) {
+    let block_size = crypter.block_size();
+    let mut rng = rand::thread_rng();
+    let mut output = vec![0u8; input.len() + block_size * 2];
+    let mut written = 0;
+
+    let mut remaining = input;
+    while !remaining.is_empty() {
+        let len = rng.gen_range(0, remaining.len() + 1);
+        written += crypter
+            .update(&remaining[..len], &mut output[written..])
+            .unwrap();
+        remaining = &remaining[len..];
+    }
+
+    let result = crypter
+        .finalize(&mut output[written..])
+        .map(|n| written += n);
+    output.truncate(written);
+    (output, result)
+}
+
+fn encrypt<B: Backend>(cipher: Cipher, key: &[u8], iv: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let mut crypter = B::new_cipher(cipher, Mode::Encrypt, key, iv).unwrap();
+    let (output, result) = transform(crypter.as_mut(), plaintext);
+    result.unwrap();
+    output
+}
+
+fn decrypt<B: Backend>(
+    cipher: Cipher,
+    key: &[u8],
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut crypter = B::new_cipher(cipher, Mode::Decrypt, key, iv).unwrap();
+    let (output, result) = transform(crypter.as_mut(), ciphertext);
+    result.map(|_| output)
+}
+
+fn encrypt_aead<B: Backend>(
+    cipher: Cipher,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> (Vec<u8>, [u8; TAG_LEN]) {
+    let mut crypter = B::new_cipher(cipher, Mode::Encrypt, key, nonce).unwrap();
+    crypter.aad_update(aad).unwrap();
+    let (output, result) = transform(crypter.as_mut(), plaintext);
+    result.unwrap();
+    let mut tag = [0u8; TAG_LEN];
+    crypter.get_tag(&mut tag).unwrap();
+    (output, tag)
+}
+
+fn decrypt_aead<B: Backend>(
+    cipher: Cipher,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut crypter = B::new_cipher(cipher, Mode::Decrypt, key, nonce).unwrap();
+    crypter.aad_update(aad).unwrap();
+    crypter.set_tag(tag).unwrap();
+    let (output, result) = transform(crypter.as_mut(), ciphertext);
+    result.map(|_| output)
+}
+
+fn ciphers() -> Vec<Cipher> {
+    vec![
+        Cipher::aes_128_cbc(),
+        Cipher::aes_192_cbc(),
+        Cipher::aes_256_cbc(),
+        Cipher::aes_128_ctr(),
+        Cipher::aes_192_ctr(),
+        Cipher::aes_256_ctr(),
+        Cipher::chacha20(),
+    ]
+}
+
+fn aead_ciphers() -> Vec<Cipher> {
+    vec![
+        Cipher::aes_128_gcm(),
+        Cipher::aes_192_gcm(),
+        Cipher::aes_256_gcm(),
+        Cipher::chacha20_poly1305(),
+    ]
+}
+
+fn seal<B: Backend>(
+    cipher: Cipher,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Vec<u8> {
+    let mut sealed = Vec::new();
+    B::seal(cipher, key, nonce, aad, plaintext, &mut sealed).unwrap();
+    sealed
+}
+
+fn open<B: Backend>(
+    cipher: Cipher,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    sealed: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+    let mut opened = Vec::new();
+    B::open(cipher, key, nonce, aad, ciphertext, tag, &mut opened).map(|_| opened)
+}
+
+#[test]
+fn matches_openssl() {
+    for cipher in ciphers() {
+        let key = random_bytes(cipher.key_len());
+        let iv = random_bytes(cipher.iv_len().unwrap());
+
+        for &len in &[0, 1, 15, 16, 17, 63, 64, 65, 1000] {
+            let plaintext = random_bytes(len);
+
+            let expected = encrypt::<OpenSsl>(cipher, &key, &iv, &plaintext);
+            let encrypted = encrypt::<RustCrypto>(cipher, &key, &iv, &plaintext);
+            assert_eq!(encrypted, expected, "{:?} with {} bytes", cipher, len);
+
+            let decrypted = decrypt::<RustCrypto>(cipher, &key, &iv, &expected);
+            assert_eq!(decrypted.unwrap(), plaintext);
+        }
+    }
+}
+
+#[test]
+fn aead_matches_openssl() {
+    for cipher in aead_ciphers() {
+        let key = random_bytes(cipher.key_len());
+        let nonce = random_bytes(12);
+        let aad = random_bytes(rand::thread_rng().gen_range(0, 40));
+
+        for &len in &[0, 1, 15, 16, 17, 63, 64, 65, 1000] {
+            let plaintext = random_bytes(len);
+
+            let expected = seal::<OpenSsl>(cipher, &key, &nonce, &aad, &plaintext);
+            let sealed = seal::<RustCrypto>(cipher, &key, &nonce, &aad, &plaintext);
+            assert_eq!(sealed, expected, "{:?} with {} bytes", cipher, len);
+
+            let opened = open::<RustCrypto>(cipher, &key, &nonce, &aad, &expected);
+            assert_eq!(opened.unwrap(), plaintext);
+            let opened = open::<OpenSsl>(cipher, &key, &nonce, &aad, &sealed);
+            assert_eq!(opened.unwrap(), plaintext);
+        }
+    }
+}
+
+#[test]
+fn streaming_aead_matches_openssl() {
+    for cipher in aead_ciphers() {
+        let key = random_bytes(cipher.key_len());
+        // GCM also accepts nonces of any other length, which are hashed into the counter block.
+        let nonce_lens: &[usize] = if cipher == Cipher::chacha20_poly1305() {
+            &[12]
+        } else {
+            &[12, 1, 8, 16, 60]
+        };
+
+        for &nonce_len in nonce_lens {
+            let nonce = random_bytes(nonce_len);
+            let aad = random_bytes(rand::thread_rng().gen_range(0, 40));
+
+            for &len in &[0, 1, 15, 16, 17, 63, 64, 65, 1000] {
+                let plaintext = random_bytes(len);
+
+                let expected = encrypt_aead::<OpenSsl>(cipher, &key, &nonce, &aad, &plaintext);
+                let encrypted = encrypt_aead::<RustCrypto>(cipher, &key, &nonce, &aad, &plaintext);
+                assert_eq!(
+                    encrypted, expected,
+                    "{:?} with a {}-byte nonce and {} bytes",
+                    cipher, nonce_len, len
+                );
+
+                let (ciphertext, tag) = expected;
+                let decrypted =
+                    decrypt_aead::<RustCrypto>(cipher, &key, &nonce, &aad, &ciphertext, &tag);
+                assert_eq!(decrypted.unwrap(), plaintext);
+
+                let mut tampered = tag;
+                tampered[0] ^= 0x01;
+                let result =
+                    decrypt_aead::<RustCrypto>(cipher, &key, &nonce, &aad, &ciphertext, &tampered);
+                assert!(matches!(result, Err(Error::AuthenticationFailed)));
+            }
+        }
+    }
+}
+
+#[test]
+fn aead_tampering_is_detected() {
+    for cipher in aead_ciphers() {
+        let key = random_bytes(cipher.key_len());
+        let nonce = random_bytes(12);
+        let sealed = seal::<RustCrypto>(cipher, &key, &nonce, b"header", b"hello, world");
+
+        for i in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 0x01;
+            let result = open::<RustCrypto>(cipher, &key, &nonce, b"header", &tampered);
+            assert!(matches!(result, Err(Error::AuthenticationFailed)));
+        }
+
+        let result = open::<RustCrypto>(cipher, &key, &nonce, b"footer", &sealed);
+        assert!(matches!(result, Err(Error::AuthenticationFailed)));
+    }
+}
+
+#[test]
+fn aead_rejects_truncated_tags() {
+    for cipher in aead_ciphers() {
+        let key = random_bytes(cipher.key_len());
+        let nonce = random_bytes(12);
+        let sealed = seal::<RustCrypto>(cipher, &key, &nonce, b"", b"hello, world");
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+
+        // Even a correct prefix of the tag is rejected, by either backend.
+        for len in [1, 8, 15] {
+            let mut opened = Vec::new();
+            let result = RustCrypto::open(
+                cipher,
+                &key,
+                &nonce,
+                b"",
+                ciphertext,
+                &tag[..len],
+                &mut opened,
+            );
+            assert!(matches!(result, Err(Error::AuthenticationFailed)));
+            let result = OpenSsl::open(
+                cipher,
+                &key,
+                &nonce,
+                b"",
+                ciphertext,
+                &tag[..len],
+                &mut opened,
+            );
+            assert!(matches!(result, Err(Error::AuthenticationFailed)));
+            assert!(opened.is_empty());
+        }
+    }
+}
+
+#[test]
+fn cbc_bad_padding() {
+    let cipher = Cipher::aes_128_cbc();
+    let key: [u8; 16] = rand::random();
+    let iv: [u8; 16] = rand::random();
+
+    // A final block of zeroes can never be validly padded.
+    let mut crypter = OpenSsl::new_cipher(cipher, Mode::Encrypt, &key, &iv).unwrap();
+    crypter.pad(false);
+    let (encrypted, result) = transform(crypter.as_mut(), &[0u8; 32]);
+    result.unwrap();
+
+    let result = decrypt::<RustCrypto>(cipher, &key, &iv, &encrypted);
+    assert!(matches!(result, Err(Error::BadPadding)));
+    let result = decrypt::<RustCrypto>(cipher, &key, &iv, &encrypted[..31]);
+    assert!(matches!(result, Err(Error::IncompleteBlock)));
+}
+
+#[test]
+fn cbc_without_padding() {
+    let cipher = Cipher::aes_256_cbc();
+    let key: [u8; 32] = rand::random();
+    let iv: [u8; 16] = rand::random();
+    let plaintext = random_bytes(64);
+
+    let mut crypter = RustCrypto::new_cipher(cipher, Mode::Encrypt, &key, &iv).unwrap();
+    crypter.pad(false);
+    let (encrypted, result) = transform(crypter.as_mut(), &plaintext);
+    result.unwrap();
+    assert_eq!(encrypted.len(), plaintext.len());
+
+    let mut crypter = OpenSsl::new_cipher(cipher, Mode::Decrypt, &key, &iv).unwrap();
+    crypter.pad(false);
+    let (decrypted, result) = transform(crypter.as_mut(), &encrypted);
+    result.unwrap();
+    assert_eq!(decrypted, plaintext);
+}
+
+#[test]
+fn falls_back_to_openssl() {
+    // Neither an unimplemented cipher nor an invalid key length is handled by RustCrypto.
+    let key: [u8; 16] = rand::random();
+    let iv: [u8; 16] = rand::random();
+    let plaintext = random_bytes(100);
+
+    let cipher = Cipher::aes_128_ofb();
+    let expected = encrypt::<OpenSsl>(cipher, &key, &iv, &plaintext);
+    assert_eq!(
+        encrypt::<RustCrypto>(cipher, &key, &iv, &plaintext),
+        expected
+    );
+
+    let result = RustCrypto::new_cipher(Cipher::aes_256_cbc(), Mode::Encrypt, &key, &iv);
+    assert!(result.is_err());
+}
@@ -0,0 +1,108 @@
+//! Tests for the `BufRead` implementations of the `bufread` cryptostreams.
+
+use crate::Cipher;
+use crate::{bufread, write};
+use std::io::{BufRead, Cursor, Read, Seek, SeekFrom, Write};
+
+fn log(lines: usize) -> String {
+    (0..lines)
+        .map(|i| format!("{} the quick brown fox jumps over the lazy dog\n", i))
+        .collect()
+}
+
+fn encrypt(plaintext: &[u8], cipher: Cipher, key: &[u8], iv: &[u8]) -> Vec<u8> {
+    let mut encryptor = write::Encryptor::new(Vec::new(), cipher, key, iv).unwrap();
+    encryptor.write_all(plaintext).unwrap();
+    encryptor.finish().unwrap()
+}
+
+#[test]
+fn lines() {
+    let log = log(1000);
+
+    for &cipher in &[Cipher::aes_128_cbc(), Cipher::aes_128_ctr()] {
+        let (key, iv): ([u8; 16], [u8; 16]) = (rand::random(), rand::random());
+        let encrypted = encrypt(log.as_bytes(), cipher, &key, &iv);
+
+        let decryptor = bufread::Decryptor::new(&encrypted[..], cipher, &key, &iv).unwrap();
+        let lines: Vec<String> = decryptor.lines().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 1000);
+        assert_eq!(lines.join("\n") + "\n", log);
+
+        // Split on something other than newlines, with a buffer much smaller than the log.
+        let decryptor =
+            bufread::Decryptor::with_capacity(64, &encrypted[..], cipher, &key, &iv).unwrap();
+        let words = decryptor.split(b' ').count();
+        assert_eq!(words, log.split(' ').count());
+    }
+}
+
+#[test]
+fn mixed_with_read() {
+    let log = log(100);
+    let cipher = Cipher::aes_256_cbc();
+    let (key, iv): ([u8; 32], [u8; 16]) = (rand::random(), rand::random());
+    let encrypted = encrypt(log.as_bytes(), cipher, &key, &iv);
+
+    let mut decryptor = bufread::Decryptor::new(&encrypted[..], cipher, &key, &iv).unwrap();
+    let mut decrypted = String::new();
+    decryptor.read_line(&mut decrypted).unwrap();
+
+    // Anything buffered by `fill_buf()` is returned by `read()` before decrypting any more.
+    let mut buf = [0u8; 7];
+    decryptor.read_exact(&mut buf).unwrap();
+    decrypted.push_str(std::str::from_utf8(&buf).unwrap());
+
+    assert!(!decryptor.fill_buf().unwrap().is_empty());
+    decryptor.consume(3);
+    decrypted.push_str(&log[decrypted.len()..][..3]);
+
+    decryptor.read_to_string(&mut decrypted).unwrap();
+    assert_eq!(decrypted, log);
+    assert!(decryptor.fill_buf().unwrap().is_empty());
+}
+
+#[test]
+fn encryptor() {
+    let log = log(100);
+    let cipher = Cipher::aes_128_cbc();
+    let (key, iv): ([u8; 16], [u8; 16]) = (rand::random(), rand::random());
+
+    let mut encryptor = bufread::Encryptor::new(log.as_bytes(), cipher, &key, &iv).unwrap();
+    let mut encrypted = Vec::new();
+    loop {
+        let len = match encryptor.fill_buf().unwrap() {
+            [] => break,
+            buf => {
+                encrypted.extend_from_slice(buf);
+                buf.len()
+            }
+        };
+        encryptor.consume(len);
+    }
+
+    assert_eq!(encrypted, encrypt(log.as_bytes(), cipher, &key, &iv));
+}
+
+#[test]
+fn seek_discards_buffer() {
+    let log = log(100);
+    let cipher = Cipher::aes_128_ctr();
+    let (key, iv): ([u8; 16], [u8; 16]) = (rand::random(), rand::random());
+    let encrypted = encrypt(log.as_bytes(), cipher, &key, &iv);
+
+    let mut decryptor = bufread::Decryptor::new(Cursor::new(encrypted), cipher, &key, &iv).unwrap();
+    let mut line = String::new();
+    decryptor.read_line(&mut line).unwrap();
+    assert_eq!(decryptor.stream_position().unwrap(), line.len() as u64);
+
+    decryptor.seek(SeekFrom::Current(-5)).unwrap();
+    let mut tail = String::new();
+    decryptor.read_line(&mut tail).unwrap();
+    assert_eq!(tail, &line[line.len() - 5..]);
+
+    decryptor.seek(SeekFrom::Start(1000)).unwrap();
+    let mut rest = String::new();
+    decryptor.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, &log[1000..]);
+}
@@ -0,0 +1,156 @@
+//! Tests for cryptostreams created with a non-default buffer capacity.
+
+use super::symm::encrypt;
+use crate::Cipher;
+use crate::{bufread, read, write};
+use std::io::{Read, Write};
+
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn init_secrets() -> (Cipher, [u8; 128 / 8], [u8; 128 / 8]) {
+    (Cipher::aes_128_cbc(), rand::random(), rand::random())
+}
+
+#[test]
+fn roundtrip() {
+    let (cipher, key, iv) = init_secrets();
+    let plaintext = plaintext(50_000);
+    let expected = encrypt(cipher, &key, Some(&iv), &plaintext).unwrap();
+
+    for &capacity in &[0, 1, 16, 100, 4096, 65536] {
+        let mut encryptor =
+            read::Encryptor::with_capacity(capacity, &plaintext[..], cipher, &key, &iv).unwrap();
+        let mut encrypted = Vec::new();
+        encryptor.read_to_end(&mut encrypted).unwrap();
+        assert_eq!(encrypted, expected, "{}", capacity);
+
+        let mut encryptor =
+            write::Encryptor::with_capacity(capacity, Vec::new(), cipher, &key, &iv).unwrap();
+        encryptor.write_all(&plaintext).unwrap();
+        assert_eq!(encryptor.finish().unwrap(), expected, "{}", capacity);
+
+        // Reads of every size, including those smaller than a block.
+        for &len in &[1, 15, 17, 1000] {
+            let mut decryptor =
+                read::Decryptor::with_capacity(capacity, &expected[..], cipher, &key, &iv).unwrap();
+            let mut decrypted = Vec::new();
+            let mut buf = vec![0u8; len];
+            loop {
+                match decryptor.read(&mut buf).unwrap() {
+                    0 => break,
+                    n => decrypted.extend_from_slice(&buf[..n]),
+                }
+            }
+            assert_eq!(decrypted, plaintext, "{} {}", capacity, len);
+        }
+
+        let mut decryptor =
+            write::Decryptor::with_capacity(capacity, Vec::new(), cipher, &key, &iv).unwrap();
+        decryptor.write_all(&expected).unwrap();
+        assert_eq!(decryptor.finish().unwrap(), plaintext, "{}", capacity);
+    }
+}
+
+#[test]
+fn large_reads_and_writes() {
+    let (cipher, key, iv) = init_secrets();
+    let plaintext = plaintext(100_000);
+    let encrypted = encrypt(cipher, &key, Some(&iv), &plaintext).unwrap();
+
+    // A single read transforms as much as the capacity and the caller's buffer allow, less the
+    // block retained for unpadding.
+    let mut decryptor =
+        bufread::Decryptor::with_capacity(32 * 1024, &encrypted[..], cipher, &key, &iv).unwrap();
+    let mut buf = vec![0u8; 64 * 1024];
+    assert_eq!(decryptor.read(&mut buf).unwrap(), 32 * 1024 - 16);
+
+    let mut decryptor = bufread::Decryptor::new(&encrypted[..], cipher, &key, &iv).unwrap();
+    // Short of filling `buf`, by no more than the slack left for the cipher, a partial block, and
+    // the block retained for unpadding.
+    let read = decryptor.read(&mut buf[..1000]).unwrap();
+    assert!(read > 1000 - 4 * 16 && read.is_multiple_of(16), "{}", read);
+
+    // A single write consumes everything, regardless of the capacity.
+    let mut encryptor =
+        write::Encryptor::with_capacity(1024, Vec::new(), cipher, &key, &iv).unwrap();
+    assert_eq!(encryptor.write(&plaintext).unwrap(), plaintext.len());
+    assert_eq!(encryptor.finish().unwrap().len(), encrypted.len());
+}
+
+#[test]
+fn aead() {
+    /// A `Write` destination recording the size of the largest single write.
+    #[derive(Default)]
+    struct LargestWrite(Vec<u8>, usize);
+
+    impl Write for LargestWrite {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.extend_from_slice(buf);
+            self.1 = std::cmp::max(self.1, buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let cipher = Cipher::aes_256_gcm();
+    let (key, iv): ([u8; 32], [u8; 12]) = (rand::random(), rand::random());
+    let plaintext = plaintext(50_000);
+    let mut encryptor = write::AeadEncryptor::new(Vec::new(), cipher, &key, &iv, b"").unwrap();
+    encryptor.write_all(&plaintext).unwrap();
+    let expected = encryptor.finish().unwrap();
+
+    for &capacity in &[0, 100, 4096, 65536] {
+        let mut encryptor = write::AeadEncryptor::with_capacity(
+            capacity,
+            LargestWrite::default(),
+            cipher,
+            &key,
+            &iv,
+            b"",
+        )
+        .unwrap();
+        encryptor.write_all(&plaintext).unwrap();
+        let encrypted = encryptor.finish().unwrap();
+        assert_eq!(encrypted.0, expected, "{}", capacity);
+        assert!(encrypted.1 <= std::cmp::max(capacity, 64), "{}", capacity);
+
+        let mut decryptor = write::AeadDecryptor::with_capacity(
+            capacity,
+            LargestWrite::default(),
+            cipher,
+            &key,
+            &iv,
+            b"",
+        )
+        .unwrap();
+        decryptor.write_all(&expected).unwrap();
+        let decrypted = decryptor.finish().unwrap();
+        assert_eq!(decrypted.0, plaintext, "{}", capacity);
+        assert!(decrypted.1 <= std::cmp::max(capacity, 64), "{}", capacity);
+
+        // A single read transforms no more than the capacity.
+        let mut buf = vec![0u8; 100_000];
+        let mut encryptor =
+            read::AeadEncryptor::with_capacity(capacity, &plaintext[..], cipher, &key, &iv, b"")
+                .unwrap();
+        let n = encryptor.read(&mut buf).unwrap();
+        assert!(n > 0 && n <= std::cmp::max(capacity, 32), "{}", capacity);
+        let mut encrypted = buf[..n].to_vec();
+        encryptor.read_to_end(&mut encrypted).unwrap();
+        assert_eq!(encrypted, expected, "{}", capacity);
+
+        let mut decryptor =
+            bufread::AeadDecryptor::with_capacity(capacity, &expected[..], cipher, &key, &iv, b"")
+                .unwrap();
+        let n = decryptor.read(&mut buf).unwrap();
+        assert!(n > 0 && n <= std::cmp::max(capacity, 32), "{}", capacity);
+        let mut decrypted = buf[..n].to_vec();
+        decryptor.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext, "{}", capacity);
+    }
+}
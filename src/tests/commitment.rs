@@ -0,0 +1,263 @@
+//! Tests for the key-committing variants of the authenticated cryptostreams.
+
+use super::TEST;
+use crate::Cipher;
+use crate::{bufread, read, write, Error};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+fn init_secrets() -> (Cipher, [u8; 256 / 8], [u8; 96 / 8]) {
+    (Cipher::aes_256_gcm(), rand::random(), rand::random())
+}
+
+fn encrypt(cipher: Cipher, key: &[u8], iv: &[u8]) -> Vec<u8> {
+    let mut encryptor =
+        write::AeadEncryptor::with_key_commitment(Vec::new(), cipher, key, iv, b"aad").unwrap();
+    encryptor.write_all(TEST).unwrap();
+    encryptor.finish().unwrap()
+}
+
+#[test]
+fn aead_roundtrip() {
+    let (cipher, key, iv) = init_secrets();
+    let encrypted = encrypt(cipher, &key, &iv);
+    assert_eq!(encrypted.len(), 32 + TEST.len() + 16);
+
+    // The `read` encryptor produces the same output.
+    let mut encryptor =
+        read::AeadEncryptor::with_key_commitment(TEST, cipher, &key, &iv, b"aad").unwrap();
+    let mut read_encrypted = Vec::new();
+    encryptor.read_to_end(&mut read_encrypted).unwrap();
+    assert_eq!(read_encrypted, encrypted);
+
+    let mut decryptor =
+        write::AeadDecryptor::with_key_commitment(Vec::new(), cipher, &key, &iv, b"aad").unwrap();
+    // Written a byte at a time, so that the commitment arrives piecemeal.
+    for byte in &encrypted {
+        decryptor.write_all(std::slice::from_ref(byte)).unwrap();
+    }
+    assert_eq!(decryptor.finish().unwrap(), TEST);
+
+    let mut decryptor =
+        read::AeadDecryptor::with_key_commitment(&encrypted[..], cipher, &key, &iv, b"aad")
+            .unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, TEST);
+}
+
+#[test]
+fn wrong_key_fails_early() {
+    let (cipher, key, iv) = init_secrets();
+    let encrypted = encrypt(cipher, &key, &iv);
+    let other_key: [u8; 32] = rand::random();
+
+    let err = bufread::AeadDecryptor::with_key_commitment(
+        &encrypted[..],
+        cipher,
+        &other_key,
+        &iv,
+        b"aad",
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(err, Error::KeyCommitmentMismatch));
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // Nothing is written out by the `write` decryptor, which can't be used any further.
+    let mut decrypted = Vec::new();
+    let mut decryptor =
+        write::AeadDecryptor::with_key_commitment(&mut decrypted, cipher, &other_key, &iv, b"aad")
+            .unwrap();
+    let err = decryptor.write_all(&encrypted).unwrap_err();
+    assert!(matches!(Error::from(err), Error::KeyCommitmentMismatch));
+    assert!(decryptor.try_finish().is_err());
+    drop(decryptor);
+    assert!(decrypted.is_empty());
+
+    // The commitment is also bound to the IV.
+    let mut other_iv = iv;
+    other_iv[0] ^= 1;
+    let err =
+        read::AeadDecryptor::with_key_commitment(&encrypted[..], cipher, &key, &other_iv, b"aad")
+            .err()
+            .unwrap();
+    assert!(matches!(err, Error::KeyCommitmentMismatch));
+}
+
+#[test]
+fn truncated_commitment() {
+    let (cipher, key, iv) = init_secrets();
+    let encrypted = encrypt(cipher, &key, &iv);
+
+    let err = read::AeadDecryptor::with_key_commitment(&encrypted[..20], cipher, &key, &iv, b"")
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::Truncated));
+
+    let mut decryptor =
+        write::AeadDecryptor::with_key_commitment(Vec::new(), cipher, &key, &iv, b"aad").unwrap();
+    decryptor.write_all(&encrypted[..20]).unwrap();
+    let err = decryptor.finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn segmented() {
+    let cipher = Cipher::chacha20_poly1305();
+    let key: [u8; 32] = rand::random();
+    let nonce_prefix: [u8; 7] = rand::random();
+    let plaintext: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+
+    let mut encryptor =
+        write::SegmentedEncryptor::with_key_commitment(Vec::new(), cipher, &key, &nonce_prefix)
+            .unwrap();
+    encryptor.write_all(&plaintext).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut encryptor =
+        read::SegmentedEncryptor::with_key_commitment(&plaintext[..], cipher, &key, &nonce_prefix)
+            .unwrap();
+    let mut read_encrypted = Vec::new();
+    encryptor.read_to_end(&mut read_encrypted).unwrap();
+    assert_eq!(read_encrypted, encrypted);
+
+    let mut decryptor =
+        write::SegmentedDecryptor::with_key_commitment(Vec::new(), cipher, &key, &nonce_prefix)
+            .unwrap();
+    decryptor.write_all(&encrypted).unwrap();
+    assert_eq!(decryptor.finish().unwrap(), plaintext);
+
+    // Seeking accounts for the commitment ahead of the first segment.
+    let mut decryptor = read::SegmentedDecryptor::with_key_commitment(
+        Cursor::new(&encrypted),
+        cipher,
+        &key,
+        &nonce_prefix,
+    )
+    .unwrap();
+    let mut buf = [0u8; 100];
+    decryptor.seek(SeekFrom::Start(70_000)).unwrap();
+    decryptor.read_exact(&mut buf).unwrap();
+    assert_eq!(buf[..], plaintext[70_000..70_100]);
+    assert_eq!(decryptor.seek(SeekFrom::End(-100)).unwrap(), 199_900);
+    decryptor.read_exact(&mut buf).unwrap();
+    assert_eq!(buf[..], plaintext[199_900..]);
+
+    let other_key: [u8; 32] = rand::random();
+    let err = read::SegmentedDecryptor::with_key_commitment(
+        &encrypted[..],
+        cipher,
+        &other_key,
+        &nonce_prefix,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(err, Error::KeyCommitmentMismatch));
+
+    let mut decryptor = write::SegmentedDecryptor::with_key_commitment(
+        Vec::new(),
+        cipher,
+        &other_key,
+        &nonce_prefix,
+    )
+    .unwrap();
+    assert!(decryptor.write_all(&encrypted).is_err());
+    assert!(decryptor.try_finish().is_err());
+}
+
+#[test]
+fn dropped_before_commitment() {
+    /// A `Write` destination recording whether it was ever written to or flushed.
+    #[derive(Default)]
+    struct Untouched(bool);
+
+    impl Write for Untouched {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 = true;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.0 = true;
+            Ok(())
+        }
+    }
+
+    let (cipher, key, iv) = init_secrets();
+    let encrypted = encrypt(cipher, &key, &iv);
+
+    let mut writer = Untouched::default();
+    let mut decryptor =
+        write::AeadDecryptor::with_key_commitment(&mut writer, cipher, &key, &iv, b"aad").unwrap();
+    decryptor.write_all(&encrypted[..20]).unwrap();
+    drop(decryptor);
+    assert!(!writer.0);
+
+    let nonce_prefix: [u8; 7] = rand::random();
+    let mut decryptor =
+        write::SegmentedDecryptor::with_key_commitment(&mut writer, cipher, &key, &nonce_prefix)
+            .unwrap();
+    decryptor.write_all(&encrypted[..20]).unwrap();
+    drop(decryptor);
+    assert!(!writer.0);
+}
+
+#[test]
+fn segmented_with_segment_size() {
+    let cipher = Cipher::aes_256_gcm();
+    let key: [u8; 32] = rand::random();
+    let nonce_prefix: [u8; 7] = rand::random();
+    let plaintext: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+
+    let mut encryptor = write::SegmentedEncryptor::with_key_commitment_and_segment_size(
+        Vec::new(),
+        cipher,
+        &key,
+        &nonce_prefix,
+        1000,
+    )
+    .unwrap();
+    encryptor.write_all(&plaintext).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut encryptor = read::SegmentedEncryptor::with_key_commitment_and_segment_size(
+        &plaintext[..],
+        cipher,
+        &key,
+        &nonce_prefix,
+        1000,
+    )
+    .unwrap();
+    let mut read_encrypted = Vec::new();
+    encryptor.read_to_end(&mut read_encrypted).unwrap();
+    assert_eq!(read_encrypted, encrypted);
+
+    let mut decryptor = bufread::SegmentedDecryptor::with_key_commitment_and_segment_size(
+        &encrypted[..],
+        cipher,
+        &key,
+        &nonce_prefix,
+        1000,
+    )
+    .unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, plaintext);
+
+    let mut decryptor = write::SegmentedDecryptor::with_key_commitment_and_segment_size(
+        Vec::new(),
+        cipher,
+        &key,
+        &nonce_prefix,
+        1000,
+    )
+    .unwrap();
+    decryptor.write_all(&encrypted).unwrap();
+    assert_eq!(decryptor.finish().unwrap(), plaintext);
+
+    // The default segment size doesn't match.
+    let mut decryptor =
+        read::SegmentedDecryptor::with_key_commitment(&encrypted[..], cipher, &key, &nonce_prefix)
+            .unwrap();
+    assert!(decryptor.read_to_end(&mut Vec::new()).is_err());
+}
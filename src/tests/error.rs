@@ -0,0 +1,120 @@
+//! Tests for the typed errors returned by the cryptostreams.
+
+use super::TEST;
+use crate::Cipher;
+use crate::{read, write, Error};
+use std::io::{ErrorKind, Read, Write};
+
+#[test]
+fn invalid_lengths() {
+    let cipher = Cipher::aes_256_cbc();
+    let (key, iv): ([u8; 32], [u8; 16]) = (rand::random(), rand::random());
+
+    let err = write::Encryptor::new(Vec::new(), cipher, &key[..16], &iv)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::InvalidKeyLength));
+    let err = read::Decryptor::new(TEST, cipher, &key, &iv[..12])
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::InvalidIvLength));
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let err = write::AeadEncryptor::new(
+        Vec::new(),
+        Cipher::aes_256_gcm(),
+        &key[..24],
+        &iv[..12],
+        b"",
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(err, Error::InvalidKeyLength));
+    let err = read::SegmentedDecryptor::new(TEST, Cipher::aes_128_gcm(), &key, &[0u8; 7])
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::InvalidKeyLength));
+}
+
+#[test]
+fn recoverable_from_io_errors() {
+    let cipher = Cipher::aes_256_gcm();
+    let (key, iv): ([u8; 32], [u8; 12]) = (rand::random(), rand::random());
+
+    let mut encryptor = write::AeadEncryptor::new(Vec::new(), cipher, &key, &iv, b"").unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let mut encrypted = encryptor.finish().unwrap();
+    encrypted[0] ^= 0x01;
+
+    let mut decryptor = read::AeadDecryptor::new(&encrypted[..], cipher, &key, &iv, b"").unwrap();
+    let err = decryptor.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(matches!(
+        err.get_ref().and_then(|e| e.downcast_ref()),
+        Some(Error::AuthenticationFailed)
+    ));
+    assert!(matches!(Error::from(err), Error::AuthenticationFailed));
+
+    // A ciphertext cut short of its prepended IV is truncated.
+    let cipher = Cipher::aes_256_cbc();
+    let mut decryptor = write::Decryptor::with_prepended_iv(Vec::new(), cipher, &key);
+    decryptor.write_all(&iv).unwrap();
+    let err = decryptor.finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert!(matches!(Error::from(err), Error::Truncated));
+}
+
+#[test]
+fn io_errors_pass_through() {
+    let err = Error::from(std::io::Error::new(ErrorKind::BrokenPipe, "gone"));
+    assert!(matches!(&err, Error::Io(_)));
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+
+    let err = std::io::Error::from(err);
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+    assert_eq!(err.to_string(), "gone");
+}
+
+#[test]
+fn header_errors() {
+    use crate::header::Header;
+    use crate::password::Kdf;
+
+    let iv = [0u8; 16];
+    let err = Header::new(Cipher::aes_128_gcm(), &iv[..12]).err().unwrap();
+    assert!(matches!(err, Error::UnsupportedCipher));
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    let err = Header::new(Cipher::aes_128_cbc(), &iv[..12]).err().unwrap();
+    assert!(matches!(err, Error::InvalidIvLength));
+    let err = Header::new(Cipher::aes_128_cbc(), &iv)
+        .unwrap()
+        .with_key_id(&[])
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::InvalidKeyId));
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let err = Header::read(&b"CSTR\x02\x01\x10"[..]).err().unwrap();
+    assert!(matches!(err, Error::UnsupportedVersion(2)));
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    let err = Header::read(&b"XSTR\x01\x01\x10"[..]).err().unwrap();
+    assert!(matches!(err, Error::InvalidHeader));
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = Header::read(&b"CSTR\x01\x01"[..]).err().unwrap();
+    assert!(matches!(err, Error::Truncated));
+
+    let cipher = Cipher::aes_128_cbc();
+    let err = read::Decryptor::with_password(&[0xFF, 0][..], cipher, b"password")
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::UnsupportedKdf));
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    let kdf = Kdf::Pbkdf2 {
+        iterations: u32::MAX,
+    };
+    let err = write::Encryptor::with_password(Vec::new(), cipher, b"password", kdf)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::InvalidKdfParameters));
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,155 @@
+//! Tests for the encrypt-then-MAC cryptostream variants.
+
+use super::symm::encrypt;
+use super::TEST;
+use crate::Cipher;
+use crate::{read, write};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use std::io::{ErrorKind, Read, Write};
+
+const MAC_LEN: usize = 32;
+
+fn init_secrets() -> ([u8; 32], [u8; 32], [u8; 16]) {
+    (rand::random(), rand::random(), rand::random())
+}
+
+fn etm_encrypt(plaintext: &[u8], cipher: Cipher, key: &[u8], mac_key: &[u8], iv: &[u8]) -> Vec<u8> {
+    let mut encryptor = write::EtmEncryptor::new(Vec::new(), cipher, key, mac_key, iv).unwrap();
+    encryptor.write_all(plaintext).unwrap();
+    encryptor.finish().unwrap()
+}
+
+fn read_decrypt(
+    ciphertext: &[u8],
+    cipher: Cipher,
+    key: &[u8],
+    mac_key: &[u8],
+    iv: &[u8],
+) -> std::io::Result<Vec<u8>> {
+    let mut decryptor = read::EtmDecryptor::new(ciphertext, cipher, key, mac_key, iv).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted)?;
+    Ok(decrypted)
+}
+
+fn write_decrypt(
+    ciphertext: &[u8],
+    cipher: Cipher,
+    key: &[u8],
+    mac_key: &[u8],
+    iv: &[u8],
+) -> std::io::Result<Vec<u8>> {
+    let mut decryptor = write::EtmDecryptor::new(Vec::new(), cipher, key, mac_key, iv).unwrap();
+    decryptor.write_all(ciphertext)?;
+    decryptor.finish()
+}
+
+#[test]
+fn matches_hmac_over_iv_and_ciphertext() {
+    for &cipher in &[Cipher::aes_256_cbc(), Cipher::aes_256_ctr()] {
+        let (key, mac_key, iv) = init_secrets();
+        let encrypted = etm_encrypt(TEST, cipher, &key, &mac_key, &iv);
+
+        let ciphertext = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+        let mac_key = PKey::hmac(&mac_key).unwrap();
+        let mut signer = Signer::new(MessageDigest::sha256(), &mac_key).unwrap();
+        signer.update(&iv).unwrap();
+        signer.update(&ciphertext).unwrap();
+        let mac = signer.sign_to_vec().unwrap();
+
+        assert_eq!(&encrypted[..ciphertext.len()], &ciphertext[..]);
+        assert_eq!(&encrypted[ciphertext.len()..], &mac[..]);
+
+        let mut encryptor = read::EtmEncryptor::new(TEST, cipher, &key, &key, &iv).unwrap();
+        let mut encrypted_by_read = Vec::new();
+        encryptor.read_to_end(&mut encrypted_by_read).unwrap();
+        assert_eq!(&encrypted_by_read[..ciphertext.len()], &ciphertext[..]);
+        assert_eq!(encrypted_by_read.len(), ciphertext.len() + MAC_LEN);
+    }
+}
+
+#[test]
+fn roundtrip() {
+    for &cipher in &[Cipher::aes_128_cbc(), Cipher::aes_128_ctr()] {
+        let (key, mac_key, iv) = init_secrets();
+        let key = &key[..16];
+        for &len in &[0, 1, 15, 16, 17, 100] {
+            let plaintext = &TEST[..std::cmp::min(len, TEST.len())];
+            let encrypted = etm_encrypt(plaintext, cipher, key, &mac_key, &iv);
+
+            let decrypted = read_decrypt(&encrypted, cipher, key, &mac_key, &iv).unwrap();
+            assert_eq!(decrypted, plaintext);
+            let decrypted = write_decrypt(&encrypted, cipher, key, &mac_key, &iv).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+}
+
+#[test]
+fn tampering_is_detected() {
+    let cipher = Cipher::aes_256_cbc();
+    let (key, mac_key, iv) = init_secrets();
+    let encrypted = etm_encrypt(TEST, cipher, &key, &mac_key, &iv);
+
+    for i in 0..encrypted.len() {
+        let mut tampered = encrypted.clone();
+        tampered[i] ^= 0x01;
+
+        let err = read_decrypt(&tampered, cipher, &key, &mac_key, &iv).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = write_decrypt(&tampered, cipher, &key, &mac_key, &iv).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    // Neither the IV nor the MAC key may differ.
+    let mut other_iv = iv;
+    other_iv[0] ^= 0x01;
+    assert!(read_decrypt(&encrypted, cipher, &key, &mac_key, &other_iv).is_err());
+    assert!(read_decrypt(&encrypted, cipher, &key, &key, &iv).is_err());
+
+    // Nor may the ciphertext be truncated, even to a block boundary.
+    let truncated = &encrypted[..encrypted.len() - 16];
+    assert!(read_decrypt(truncated, cipher, &key, &mac_key, &iv).is_err());
+    assert!(write_decrypt(truncated, cipher, &key, &mac_key, &iv).is_err());
+}
+
+#[test]
+fn buffered_withholds_plaintext() {
+    let cipher = Cipher::aes_256_ctr();
+    let (key, mac_key, iv) = init_secrets();
+    let plaintext: Vec<u8> = (0..10_000).map(|_| rand::random()).collect();
+    let encrypted = etm_encrypt(&plaintext, cipher, &key, &mac_key, &iv);
+    let mut tampered = encrypted.clone();
+    tampered[0] ^= 0x01;
+
+    let mut decryptor =
+        read::EtmDecryptor::buffered(&encrypted[..], cipher, &key, &mac_key, &iv).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, plaintext);
+
+    let mut decryptor =
+        read::EtmDecryptor::buffered(&tampered[..], cipher, &key, &mac_key, &iv).unwrap();
+    let mut buffer = [0u8; 100];
+    let err = decryptor.read(&mut buffer).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    let err = decryptor.read(&mut buffer).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let mut output = Vec::new();
+    let mut decryptor =
+        write::EtmDecryptor::buffered(&mut output, cipher, &key, &mac_key, &iv).unwrap();
+    decryptor.write_all(&tampered).unwrap();
+    assert_eq!(
+        decryptor.finish().unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    assert!(output.is_empty());
+
+    let mut decryptor =
+        write::EtmDecryptor::buffered(Vec::new(), cipher, &key, &mac_key, &iv).unwrap();
+    decryptor.write_all(&encrypted).unwrap();
+    assert_eq!(decryptor.finish().unwrap(), plaintext);
+}
@@ -0,0 +1,243 @@
+//! Tests for finishing the `write` cryptostreams, and the errors doing so may report.
+
+use super::symm::encrypt;
+use super::TEST;
+use crate::write;
+use crate::Cipher;
+use std::io::{Error, ErrorKind, Write};
+
+/// A `Write` destination that fails once more than `limit` bytes have been written to it.
+#[derive(Debug)]
+struct FailingWriter {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl FailingWriter {
+    fn new(limit: usize) -> Self {
+        Self {
+            written: Vec::new(),
+            limit,
+        }
+    }
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written.len() + buf.len() > self.limit {
+            return Err(Error::other("No space left on device"));
+        }
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn init_secrets() -> (Cipher, [u8; 128 / 8], [u8; 128 / 8]) {
+    (Cipher::aes_128_cbc(), rand::random(), rand::random())
+}
+
+#[test]
+fn finish_reports_error() {
+    let (cipher, key, iv) = init_secrets();
+
+    // Room for the whole blocks of plaintext, but not the padded final block.
+    let limit = TEST.len() - TEST.len() % 16;
+    let mut encryptor =
+        write::Encryptor::new(FailingWriter::new(limit), cipher, &key, &iv).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let err = encryptor.finish().unwrap_err();
+    assert_eq!(err.to_string(), "No space left on device");
+
+    let nonce_prefix: [u8; 7] = rand::random();
+    let mut encryptor = write::SegmentedEncryptor::new(
+        FailingWriter::new(TEST.len()),
+        Cipher::aes_128_gcm(),
+        &key,
+        &nonce_prefix,
+    )
+    .unwrap();
+    encryptor.write_all(TEST).unwrap();
+    assert!(encryptor.finish().is_err());
+}
+
+#[test]
+fn try_finish() {
+    let (cipher, key, iv) = init_secrets();
+
+    let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    assert!(!encryptor.is_finished());
+    encryptor.try_finish().unwrap();
+    assert!(encryptor.is_finished());
+
+    // Finishing again changes nothing, and nothing more is written.
+    encryptor.try_finish().unwrap();
+    assert_eq!(encryptor.write(b"more").unwrap(), 0);
+    let encrypted = encryptor.finish().unwrap();
+    assert_eq!(encrypted, encrypt(cipher, &key, Some(&iv), TEST).unwrap());
+
+    // Plaintext held back by a buffered `EtmDecryptor` is released once the MAC is verified.
+    let mac_key: [u8; 32] = rand::random();
+    let mut encryptor = write::EtmEncryptor::new(Vec::new(), cipher, &key, &mac_key, &iv).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut decrypted = Vec::new();
+    let mut decryptor =
+        write::EtmDecryptor::buffered(&mut decrypted, cipher, &key, &mac_key, &iv).unwrap();
+    decryptor.write_all(&encrypted).unwrap();
+    decryptor.try_finish().unwrap();
+    assert!(decryptor.is_finished());
+    drop(decryptor);
+    assert_eq!(decrypted, TEST);
+}
+
+#[test]
+fn try_finish_reports_error() {
+    let (cipher, key, iv) = init_secrets();
+
+    let limit = TEST.len() - TEST.len() % 16;
+    let mut encryptor =
+        write::Encryptor::new(FailingWriter::new(limit), cipher, &key, &iv).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    assert!(encryptor.try_finish().is_err());
+    assert!(!encryptor.is_finished());
+
+    // The cryptostream can't be finished (or written to) once it has failed, and dropping it
+    // doesn't attempt to finish it again.
+    assert!(encryptor.try_finish().is_err());
+    assert!(encryptor.write(b"more").is_err());
+    drop(encryptor);
+
+    // Ciphertext ending within the header of a prepended IV.
+    let mut decryptor = write::Decryptor::with_prepended_iv(Vec::new(), cipher, &key);
+    decryptor.write_all(&iv[..10]).unwrap();
+    let err = decryptor.try_finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert!(!decryptor.is_finished());
+}
+
+#[test]
+fn poisoned_by_write_error() {
+    let (cipher, key, iv) = init_secrets();
+
+    let mut encryptor = write::Encryptor::new(FailingWriter::new(16), cipher, &key, &iv).unwrap();
+    assert!(encryptor.write(TEST).is_err());
+    // Whatever state the failed write left the cipher in, nothing more is written.
+    assert!(encryptor.write(TEST).is_err());
+    assert!(encryptor.try_finish().is_err());
+    drop(encryptor);
+
+    // Tampered ciphertext fails to authenticate, after which dropping the decryptor is silent.
+    let mut encrypted = {
+        let mut encryptor =
+            write::AeadEncryptor::new(Vec::new(), Cipher::aes_128_gcm(), &key, &iv[..12], b"")
+                .unwrap();
+        encryptor.write_all(TEST).unwrap();
+        encryptor.finish().unwrap()
+    };
+    encrypted[0] ^= 1;
+    let mut decryptor =
+        write::AeadDecryptor::new(Vec::new(), Cipher::aes_128_gcm(), &key, &iv[..12], b"").unwrap();
+    decryptor.write_all(&encrypted).unwrap();
+    let err = decryptor.try_finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(!decryptor.is_finished());
+    drop(decryptor);
+}
+
+#[test]
+fn drop_guard_poison() {
+    let (cipher, key, iv) = init_secrets();
+
+    // An encryptor which can't be finished on drop flags the poison, as its output is truncated.
+    let limit = TEST.len() - TEST.len() % 16;
+    let poison = write::Poison::new();
+    let mut writer = FailingWriter::new(limit);
+    let mut encryptor = write::Encryptor::new(&mut writer, cipher, &key, &iv).unwrap();
+    encryptor.set_drop_guard(write::DropGuard::Poison(poison.clone()));
+    encryptor.write_all(TEST).unwrap();
+    drop(encryptor);
+    assert_eq!(writer.written.len(), limit);
+    assert!(poison.is_poisoned());
+
+    // One finished on drop without error doesn't.
+    let poison = write::Poison::new();
+    let mut encrypted = Vec::new();
+    let mut encryptor = write::Encryptor::new(&mut encrypted, cipher, &key, &iv).unwrap();
+    encryptor.set_drop_guard(write::DropGuard::Poison(poison.clone()));
+    encryptor.write_all(TEST).unwrap();
+    drop(encryptor);
+    assert!(!poison.is_poisoned());
+    assert_eq!(encrypted, encrypt(cipher, &key, Some(&iv), TEST).unwrap());
+
+    let nonce_prefix: [u8; 7] = rand::random();
+    let poison = write::Poison::new();
+    let mut encryptor = write::SegmentedEncryptor::new(
+        FailingWriter::new(TEST.len()),
+        Cipher::aes_128_gcm(),
+        &key,
+        &nonce_prefix,
+    )
+    .unwrap();
+    encryptor.set_drop_guard(write::DropGuard::Poison(poison.clone()));
+    encryptor.write_all(TEST).unwrap();
+    drop(encryptor);
+    assert!(poison.is_poisoned());
+
+    // Nor are the decryptors that can't write anything out on drop finished there.
+    let poison = write::Poison::new();
+    let mut decryptor = write::Decryptor::with_prepended_iv(Vec::new(), cipher, &key);
+    decryptor.set_drop_guard(write::DropGuard::Poison(poison.clone()));
+    decryptor.write_all(&iv[..10]).unwrap();
+    drop(decryptor);
+    assert!(poison.is_poisoned());
+
+    let mac_key: [u8; 32] = rand::random();
+    let mut encryptor = write::EtmEncryptor::new(Vec::new(), cipher, &key, &mac_key, &iv).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+    let poison = write::Poison::new();
+    let mut decrypted = Vec::new();
+    let mut decryptor =
+        write::EtmDecryptor::buffered(&mut decrypted, cipher, &key, &mac_key, &iv).unwrap();
+    decryptor.set_drop_guard(write::DropGuard::Poison(poison.clone()));
+    decryptor.write_all(&encrypted).unwrap();
+    drop(decryptor);
+    assert!(poison.is_poisoned());
+    assert!(decrypted.is_empty());
+}
+
+#[test]
+fn drop_guard_panic() {
+    let (cipher, key, iv) = init_secrets();
+
+    let result = std::panic::catch_unwind(|| {
+        let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+        encryptor.set_drop_guard(write::DropGuard::Panic);
+        encryptor.write_all(TEST).unwrap();
+    });
+    assert!(result.is_err());
+
+    // Finishing the cryptostream disarms the guard, even if finishing fails.
+    let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+    encryptor.set_drop_guard(write::DropGuard::Panic);
+    encryptor.write_all(TEST).unwrap();
+    encryptor.finish().unwrap();
+
+    let limit = TEST.len() - TEST.len() % 16;
+    let mut encryptor =
+        write::Encryptor::new(FailingWriter::new(limit), cipher, &key, &iv).unwrap();
+    encryptor.set_drop_guard(write::DropGuard::Panic);
+    encryptor.write_all(TEST).unwrap();
+    assert!(encryptor.finish().is_err());
+
+    let mut decryptor = write::Decryptor::with_prepended_iv(Vec::new(), cipher, &key);
+    decryptor.set_drop_guard(write::DropGuard::Panic);
+    decryptor.write_all(&iv[..10]).unwrap();
+    assert!(decryptor.finish().is_err());
+}
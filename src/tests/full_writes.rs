@@ -0,0 +1,93 @@
+//! Tests that a single `write()` to any of the `write` cryptostreams consumes the entire input.
+
+use crate::Cipher;
+use crate::{read, write};
+use std::io::{Read, Write};
+
+/// Much larger than the buffers of the cryptostreams, and not a multiple of any block size.
+const LEN: usize = 1024 * 1024 + 7;
+
+fn plaintext() -> Vec<u8> {
+    (0..LEN).map(|i| (i % 253) as u8).collect()
+}
+
+/// Writes `input` with a single call to `write()`, asserting that all of it was consumed.
+fn write_once<W: Write>(writer: &mut W, input: &[u8]) {
+    assert_eq!(writer.write(input).unwrap(), input.len());
+}
+
+#[test]
+fn basic() {
+    let plaintext = plaintext();
+
+    for &cipher in &[Cipher::aes_128_cbc(), Cipher::aes_128_ctr()] {
+        let (key, iv): ([u8; 16], [u8; 16]) = (rand::random(), rand::random());
+
+        let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+        write_once(&mut encryptor, &plaintext);
+        let encrypted = encryptor.finish().unwrap();
+
+        let mut decryptor = write::Decryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+        write_once(&mut decryptor, &encrypted);
+        assert!(decryptor.finish().unwrap() == plaintext);
+
+        // The header of a prepended IV is consumed along with the ciphertext following it.
+        let mut encryptor = write::Encryptor::with_prepended_iv(Vec::new(), cipher, &key).unwrap();
+        write_once(&mut encryptor, &plaintext);
+        let encrypted = encryptor.finish().unwrap();
+
+        let mut decryptor = write::Decryptor::with_prepended_iv(Vec::new(), cipher, &key);
+        write_once(&mut decryptor, &encrypted);
+        assert!(decryptor.finish().unwrap() == plaintext);
+    }
+}
+
+#[test]
+fn authenticated() {
+    let plaintext = plaintext();
+    let cipher = Cipher::aes_256_gcm();
+    let (key, iv): ([u8; 32], [u8; 12]) = (rand::random(), rand::random());
+
+    let mut encryptor = write::AeadEncryptor::new(Vec::new(), cipher, &key, &iv, b"").unwrap();
+    write_once(&mut encryptor, &plaintext);
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut decryptor = write::AeadDecryptor::new(Vec::new(), cipher, &key, &iv, b"").unwrap();
+    write_once(&mut decryptor, &encrypted);
+    assert!(decryptor.finish().unwrap() == plaintext);
+
+    let cipher = Cipher::aes_256_cbc();
+    let (key, mac_key, iv): ([u8; 32], [u8; 32], [u8; 16]) =
+        (rand::random(), rand::random(), rand::random());
+    let mut encryptor = write::EtmEncryptor::new(Vec::new(), cipher, &key, &mac_key, &iv).unwrap();
+    write_once(&mut encryptor, &plaintext);
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut decryptor = write::EtmDecryptor::new(Vec::new(), cipher, &key, &mac_key, &iv).unwrap();
+    write_once(&mut decryptor, &encrypted);
+    assert!(decryptor.finish().unwrap() == plaintext);
+}
+
+#[test]
+fn segmented() {
+    let plaintext = plaintext();
+    let cipher = Cipher::chacha20_poly1305();
+    let (key, nonce_prefix): ([u8; 32], [u8; 7]) = (rand::random(), rand::random());
+
+    let mut encryptor =
+        write::SegmentedEncryptor::new(Vec::new(), cipher, &key, &nonce_prefix).unwrap();
+    write_once(&mut encryptor, &plaintext);
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut decryptor =
+        write::SegmentedDecryptor::new(Vec::new(), cipher, &key, &nonce_prefix).unwrap();
+    write_once(&mut decryptor, &encrypted);
+    assert!(decryptor.finish().unwrap() == plaintext);
+
+    // And the same ciphertext can still be read back as usual.
+    let mut decryptor =
+        read::SegmentedDecryptor::new(&encrypted[..], cipher, &key, &nonce_prefix).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert!(decrypted == plaintext);
+}
@@ -0,0 +1,122 @@
+//! Tests for the self-describing cryptostream header.
+
+use super::TEST;
+use crate::header::Header;
+use crate::Cipher;
+use crate::{read, write, Error};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+fn encrypt(plaintext: &[u8], header: &Header, key: &[u8]) -> Vec<u8> {
+    let mut encryptor = write::Encryptor::with_header(Vec::new(), header, key).unwrap();
+    encryptor.write_all(plaintext).unwrap();
+    encryptor.finish().unwrap()
+}
+
+#[test]
+fn auto_roundtrip() {
+    for &cipher in &[
+        Cipher::aes_128_cbc(),
+        Cipher::aes_256_ctr(),
+        Cipher::aes_192_ofb(),
+        Cipher::aes_128_cfb128(),
+        Cipher::chacha20(),
+    ] {
+        let key: Vec<u8> = (0..cipher.key_len()).map(|_| rand::random()).collect();
+        let iv: Vec<u8> = (0..cipher.iv_len().unwrap())
+            .map(|_| rand::random())
+            .collect();
+        let header = Header::new(cipher, &iv).unwrap();
+
+        let encrypted = encrypt(TEST, &header, &key);
+        assert_eq!(&encrypted[..header.encoded_len()], &header.to_bytes()[..]);
+
+        let mut decryptor = read::Decryptor::auto(&encrypted[..], &key).unwrap();
+        let mut decrypted = Vec::new();
+        decryptor.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted, TEST);
+
+        // The read encryptor produces the same output.
+        let mut encryptor = read::Encryptor::with_header(TEST, &header, &key).unwrap();
+        let mut encrypted_by_read = Vec::new();
+        encryptor.read_to_end(&mut encrypted_by_read).unwrap();
+        assert_eq!(encrypted_by_read, encrypted);
+    }
+}
+
+#[test]
+fn key_id() {
+    let cipher = Cipher::aes_256_cbc();
+    let (key, iv): ([u8; 32], [u8; 16]) = (rand::random(), rand::random());
+    let header = Header::new(cipher, &iv)
+        .unwrap()
+        .with_key_id(b"2024-rotation")
+        .unwrap();
+    let encrypted = encrypt(TEST, &header, &key);
+
+    let mut reader = &encrypted[..];
+    let header = Header::read(&mut reader).unwrap();
+    assert_eq!(header.key_id(), Some(&b"2024-rotation"[..]));
+    assert_eq!(header.iv(), iv);
+
+    let mut decryptor = read::Decryptor::from_header(reader, &header, &key).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, TEST);
+
+    for key_id in &[&[][..], &[0; 256][..]] {
+        let err = Header::new(cipher, &iv).unwrap().with_key_id(key_id);
+        assert!(matches!(err, Err(Error::InvalidKeyId)));
+    }
+}
+
+#[test]
+fn ctr_seek_skips_header() {
+    let cipher = Cipher::aes_128_ctr();
+    let (key, iv): ([u8; 16], [u8; 16]) = (rand::random(), rand::random());
+    let header = Header::new(cipher, &iv).unwrap().with_key_id(b"k").unwrap();
+    let plaintext: Vec<u8> = (0..1000).map(|_| rand::random()).collect();
+    let encrypted = encrypt(&plaintext, &header, &key);
+
+    let mut decryptor = read::Decryptor::auto(Cursor::new(encrypted), &key).unwrap();
+    let mut buffer = [0u8; 100];
+    assert_eq!(decryptor.seek(SeekFrom::Start(300)).unwrap(), 300);
+    decryptor.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer[..], &plaintext[300..400]);
+}
+
+#[test]
+fn rejects_unsupported() {
+    let iv = [0u8; 12];
+    let err = Header::new(Cipher::aes_256_gcm(), &iv).err().unwrap();
+    assert!(matches!(err, Error::UnsupportedCipher));
+    let err = Header::new(Cipher::aes_256_cbc(), &iv).err().unwrap();
+    assert!(matches!(err, Error::InvalidIvLength));
+
+    let header = Header::new(Cipher::aes_256_cbc(), &[0u8; 16]).unwrap();
+    let valid = header.to_bytes();
+    let key = [0u8; 32];
+
+    for (index, value) in &[(0, b'X'), (6, 12)] {
+        let mut invalid = valid.clone();
+        invalid[*index] = *value;
+        let err = read::Decryptor::auto(&invalid[..], &key).err().unwrap();
+        assert!(matches!(err, Error::InvalidHeader), "byte {}", index);
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+    for version in &[0, 2] {
+        let mut invalid = valid.clone();
+        invalid[4] = *version;
+        let err = read::Decryptor::auto(&invalid[..], &key).err().unwrap();
+        assert!(matches!(err, Error::UnsupportedVersion(v) if v == *version));
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+    for value in &[0, 200] {
+        let mut invalid = valid.clone();
+        invalid[5] = *value;
+        let err = read::Decryptor::auto(&invalid[..], &key).err().unwrap();
+        assert!(matches!(err, Error::UnsupportedCipher));
+    }
+
+    let err = read::Decryptor::auto(&valid[..10], &key).err().unwrap();
+    assert!(matches!(err, Error::Truncated));
+}
@@ -0,0 +1,92 @@
+//! Tests for the encryptors generating their own IVs.
+
+use super::symm::decrypt;
+use super::TEST;
+use crate::Cipher;
+use crate::{read, write};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+#[test]
+fn random_iv() {
+    let cipher = Cipher::aes_256_cbc();
+    let key: [u8; 32] = rand::random();
+
+    let mut encryptor = write::Encryptor::with_random_iv(Vec::new(), cipher, &key).unwrap();
+    let iv = encryptor.iv().to_vec();
+    assert_eq!(iv.len(), 16);
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+    assert_eq!(decrypt(cipher, &key, Some(&iv), &encrypted).unwrap(), TEST);
+
+    let mut encryptor = read::Encryptor::with_random_iv(TEST, cipher, &key).unwrap();
+    assert_ne!(encryptor.iv(), &iv[..]);
+    let iv = encryptor.iv().to_vec();
+    let mut encrypted = Vec::new();
+    encryptor.read_to_end(&mut encrypted).unwrap();
+    assert_eq!(decrypt(cipher, &key, Some(&iv), &encrypted).unwrap(), TEST);
+}
+
+#[test]
+fn prepended_iv_roundtrip() {
+    let cipher = Cipher::aes_128_cbc();
+    let key: [u8; 16] = rand::random();
+
+    let mut encryptor = write::Encryptor::with_prepended_iv(Vec::new(), cipher, &key).unwrap();
+    let iv = encryptor.iv().to_vec();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+    assert_eq!(&encrypted[..16], &iv[..]);
+
+    let mut decryptor = read::Decryptor::with_prepended_iv(&encrypted[..], cipher, &key).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, TEST);
+
+    let mut encryptor = read::Encryptor::with_prepended_iv(TEST, cipher, &key).unwrap();
+    let mut encrypted = Vec::new();
+    encryptor.read_to_end(&mut encrypted).unwrap();
+
+    // Write a byte at a time to exercise accumulating the IV.
+    let mut decryptor = write::Decryptor::with_prepended_iv(Vec::new(), cipher, &key);
+    for byte in &encrypted {
+        decryptor.write_all(std::slice::from_ref(byte)).unwrap();
+    }
+    assert_eq!(decryptor.finish().unwrap(), TEST);
+}
+
+#[test]
+fn prepended_iv_truncated() {
+    let cipher = Cipher::aes_128_cbc();
+    let key: [u8; 16] = rand::random();
+    let iv = [0u8; 10];
+
+    let err = read::Decryptor::with_prepended_iv(&iv[..], cipher, &key)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    let mut decryptor = write::Decryptor::with_prepended_iv(Vec::new(), cipher, &key);
+    decryptor.write_all(&iv).unwrap();
+    assert_eq!(
+        decryptor.finish().unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn ctr_seek_skips_iv() {
+    let cipher = Cipher::aes_256_ctr();
+    let key: [u8; 32] = rand::random();
+    let plaintext: Vec<u8> = (0..1000).map(|_| rand::random()).collect();
+
+    let mut encryptor = write::Encryptor::with_prepended_iv(Vec::new(), cipher, &key).unwrap();
+    encryptor.write_all(&plaintext).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut decryptor =
+        read::Decryptor::with_prepended_iv(Cursor::new(encrypted), cipher, &key).unwrap();
+    let mut buffer = [0u8; 100];
+    assert_eq!(decryptor.seek(SeekFrom::End(-200)).unwrap(), 800);
+    decryptor.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer[..], &plaintext[800..900]);
+}
@@ -1,9 +1,40 @@
+mod aead;
+#[cfg(feature = "futures")]
+mod async_futures;
+#[cfg(feature = "async-tokio")]
+mod async_tokio;
+#[cfg(feature = "rustcrypto")]
+mod backend;
+mod buf_read;
+mod capacity;
+mod commitment;
+mod error;
+mod etm;
+mod finish;
+mod full_writes;
+mod header;
+mod iv;
+mod openssl_enc;
+mod padding;
+mod password;
 mod random_read;
-
-use openssl::symm::{Cipher, Crypter, Mode};
-use std::io::prelude::*;
+mod secretstream;
+mod seek;
+mod segment;
+mod siv;
+mod stream;
+mod symm;
+mod transform;
+mod truncation;
+mod vectored;
+#[cfg(feature = "zeroize")]
+mod zeroize;
+
+use self::symm::Crypter;
 use crate::read;
 use crate::write;
+use crate::{Cipher, Mode};
+use std::io::prelude::*;
 
 pub const TEST: &[u8] = b"It was the best of times, it was the worst of times.";
 
@@ -183,7 +214,7 @@ fn finish_empty_write_encrypt() {
     encryptor.finish().unwrap();
 }
 
-fn init_secrets() -> (Cipher, [u8; 128/8], [u8; 128/8]) {
+fn init_secrets() -> (Cipher, [u8; 128 / 8], [u8; 128 / 8]) {
     let cipher = Cipher::aes_128_cbc();
     let key: [u8; 128 / 8] = rand::random();
     let iv: [u8; 128 / 8] = rand::random();
@@ -201,20 +232,21 @@ fn finish_empty_read_encrypt() {
 }
 
 fn encrypt(plaintext: &[u8], cipher: Cipher, key: &[u8], iv: &[u8]) -> Vec<u8> {
-    let mut cryptor = openssl::symm::Crypter::new(cipher, Mode::Encrypt, key, Some(iv))
+    let mut cryptor = Crypter::new(cipher, Mode::Encrypt, key, Some(iv))
         .expect("Failed to create OpenSSL encryptor!");
-    let mut encrypted = Vec::new();
-    encrypted.resize(plaintext.len() + cipher.block_size(), 0);
-    let mut bytes_written = cryptor.update(plaintext, &mut encrypted)
+    let mut encrypted = vec![0; plaintext.len() + cipher.block_size()];
+    let mut bytes_written = cryptor
+        .update(plaintext, &mut encrypted)
         .expect("OpenSSL update for encryption failed!");
-    bytes_written += cryptor.finalize(&mut encrypted[bytes_written..])
+    bytes_written += cryptor
+        .finalize(&mut encrypted[bytes_written..])
         .expect("OpenSSL finalization for encryption failed!");
     encrypted.truncate(bytes_written);
     encrypted
 }
 
 fn verify_transform(plaintext: &[u8], ciphertext: &[u8], cipher: Cipher, key: &[u8], iv: &[u8]) {
-    let encrypted = encrypt(plaintext, cipher, &key, &iv);
+    let encrypted = encrypt(plaintext, cipher, key, iv);
     assert_eq!(ciphertext, encrypted.as_slice());
 }
 
@@ -227,7 +259,7 @@ fn read_encrypt_less_than_block() {
     let mut encrypted = Vec::new();
 
     match encryptor.read_to_end(&mut encrypted) {
-        Ok(16) => {},
+        Ok(16) => {}
         _ => panic!("Failed to read encrypted bytes!"),
     };
     drop(encryptor);
@@ -237,7 +269,6 @@ fn read_encrypt_less_than_block() {
     verify_transform(plaintext, &encrypted, cipher, &key, &iv);
 }
 
-
 #[test]
 fn read_decrypt_less_than_block() {
     let (cipher, key, iv) = init_secrets();
@@ -248,7 +279,7 @@ fn read_decrypt_less_than_block() {
     let mut decrypted = Vec::new();
 
     match decryptor.read_to_end(&mut decrypted) {
-        Ok(16) => {},
+        Ok(16) => {}
         _ => panic!("Failed to read encrypted bytes!"),
     };
 
@@ -265,15 +296,15 @@ fn write_encrypt_less_than_block() {
     let ciphertext = Vec::new();
     let mut encryptor = write::Encryptor::new(ciphertext, cipher, &key, &iv).unwrap();
 
-    encryptor.write_all(plaintext)
+    encryptor
+        .write_all(plaintext)
         .expect("Failed to write all bytes to encryptor!");
 
     // Here we must ensure the encryptor is flushed/dropped before comparing the results.
     // Merely dropping the encryptor leaves us unable to access the results, so call
     // `.finish()` instead.
-    let ciphertext = encryptor.finish()
-        .expect("Failed to finish encryptor!");
-    verify_transform(&plaintext, &ciphertext, cipher, &key, &iv);
+    let ciphertext = encryptor.finish().expect("Failed to finish encryptor!");
+    verify_transform(plaintext, &ciphertext, cipher, &key, &iv);
 }
 
 #[test]
@@ -285,13 +316,17 @@ fn write_decrypt_less_than_block() {
     let decrypted = Vec::new();
     let mut decryptor = write::Decryptor::new(decrypted, cipher, &key, &iv).unwrap();
 
-    decryptor.write_all(&encrypted)
+    decryptor
+        .write_all(&encrypted)
         .expect("Failed to write all bytes to decryptor!");
 
     // Here we must ensure the encryptor is flushed/dropped before comparing the results.
     // Merely dropping the encryptor leaves us unable to access the results, so call
     // `.finish()` instead.
-    let decrypted = decryptor.finish()
-        .expect("Failed to finish decryptor!");
-    assert_eq!(plaintext, decrypted.as_slice(), "Mismatch of original and decrypted contents!");
+    let decrypted = decryptor.finish().expect("Failed to finish decryptor!");
+    assert_eq!(
+        plaintext,
+        decrypted.as_slice(),
+        "Mismatch of original and decrypted contents!"
+    );
 }
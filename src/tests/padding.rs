@@ -0,0 +1,212 @@
+//! Tests for the configurable padding schemes.
+
+use super::symm::{encrypt, Crypter};
+use super::TEST;
+use crate::padding::Padding;
+use crate::{read, write};
+use crate::{Cipher, Mode};
+use std::io::{ErrorKind, Read, Write};
+
+const SCHEMES: [Padding; 5] = [
+    Padding::Pkcs7,
+    Padding::Zero,
+    Padding::Iso10126,
+    Padding::AnsiX923,
+    Padding::Iso7816_4,
+];
+
+fn init_secrets() -> ([u8; 16], [u8; 16]) {
+    (rand::random(), rand::random())
+}
+
+/// Encrypts or decrypts `input` with OpenSSL, without any padding.
+fn unpadded(mode: Mode, input: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    let mut crypter = Crypter::new(Cipher::aes_128_cbc(), mode, key, Some(iv)).unwrap();
+    crypter.pad(false);
+    let mut output = vec![0u8; input.len() + 16];
+    let mut written = crypter.update(input, &mut output).unwrap();
+    written += crypter.finalize(&mut output[written..]).unwrap();
+    output.truncate(written);
+    output
+}
+
+fn write_encrypt(plaintext: &[u8], key: &[u8], iv: &[u8], padding: Padding) -> Vec<u8> {
+    let cipher = Cipher::aes_128_cbc();
+    let mut encryptor =
+        write::Encryptor::with_padding(Vec::new(), cipher, key, iv, padding).unwrap();
+    encryptor.write_all(plaintext).unwrap();
+    encryptor.finish().unwrap()
+}
+
+fn read_decrypt(
+    ciphertext: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    padding: Padding,
+) -> std::io::Result<Vec<u8>> {
+    let cipher = Cipher::aes_128_cbc();
+    let mut decryptor =
+        read::Decryptor::with_padding(ciphertext, cipher, key, iv, padding).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted)?;
+    Ok(decrypted)
+}
+
+fn write_decrypt(
+    ciphertext: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    padding: Padding,
+) -> std::io::Result<Vec<u8>> {
+    let cipher = Cipher::aes_128_cbc();
+    let mut decryptor =
+        write::Decryptor::with_padding(Vec::new(), cipher, key, iv, padding).unwrap();
+    decryptor.write_all(ciphertext)?;
+    decryptor.finish()
+}
+
+#[test]
+fn roundtrip() {
+    let (key, iv) = init_secrets();
+    for &padding in &SCHEMES {
+        for &len in &[0, 1, 15, 16, 17, 52] {
+            let plaintext = &TEST[..len];
+            let encrypted = write_encrypt(plaintext, &key, &iv, padding);
+            assert_eq!(encrypted.len() % 16, 0);
+
+            assert_eq!(
+                read_decrypt(&encrypted, &key, &iv, padding).unwrap(),
+                plaintext
+            );
+            assert_eq!(
+                write_decrypt(&encrypted, &key, &iv, padding).unwrap(),
+                plaintext
+            );
+
+            let cipher = Cipher::aes_128_cbc();
+            let mut encryptor =
+                read::Encryptor::with_padding(plaintext, cipher, &key, &iv, padding).unwrap();
+            let mut encrypted_by_read = Vec::new();
+            encryptor.read_to_end(&mut encrypted_by_read).unwrap();
+            assert_eq!(encrypted_by_read.len(), encrypted.len());
+            assert_eq!(
+                read_decrypt(&encrypted_by_read, &key, &iv, padding).unwrap(),
+                plaintext
+            );
+        }
+    }
+}
+
+#[test]
+fn encodings() {
+    let (key, iv) = init_secrets();
+    let plaintext = &TEST[..13];
+    let padded = |padding| {
+        let encrypted = write_encrypt(plaintext, &key, &iv, padding);
+        let decrypted = unpadded(Mode::Decrypt, &encrypted, &key, &iv);
+        assert_eq!(&decrypted[..13], plaintext);
+        decrypted[13..].to_vec()
+    };
+
+    assert_eq!(padded(Padding::Pkcs7), [3, 3, 3]);
+    assert_eq!(padded(Padding::Zero), [0, 0, 0]);
+    assert_eq!(padded(Padding::Iso10126)[2], 3);
+    assert_eq!(padded(Padding::AnsiX923), [0, 0, 3]);
+    assert_eq!(padded(Padding::Iso7816_4), [0x80, 0, 0]);
+
+    // PKCS#7 remains the default, and matches OpenSSL's own padding.
+    let cipher = Cipher::aes_128_cbc();
+    let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+    assert_eq!(encrypted, encrypt(cipher, &key, Some(&iv), TEST).unwrap());
+
+    // Zero padding adds nothing to plaintext that is already a whole number of blocks long.
+    assert_eq!(
+        write_encrypt(&TEST[..32], &key, &iv, Padding::Zero).len(),
+        32
+    );
+}
+
+#[test]
+fn no_padding() {
+    let (key, iv) = init_secrets();
+    let plaintext = &TEST[..32];
+    let encrypted = write_encrypt(plaintext, &key, &iv, Padding::None);
+    assert_eq!(encrypted, unpadded(Mode::Encrypt, plaintext, &key, &iv));
+    assert_eq!(
+        read_decrypt(&encrypted, &key, &iv, Padding::None).unwrap(),
+        plaintext
+    );
+    assert_eq!(
+        write_decrypt(&encrypted, &key, &iv, Padding::None).unwrap(),
+        plaintext
+    );
+
+    let cipher = Cipher::aes_128_cbc();
+    let mut encryptor =
+        write::Encryptor::with_padding(Vec::new(), cipher, &key, &iv, Padding::None).unwrap();
+    encryptor.write_all(&TEST[..17]).unwrap();
+    let err = encryptor.finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(matches!(
+        crate::Error::from(err),
+        crate::Error::IncompletePlaintext
+    ));
+
+    let mut encryptor =
+        read::Encryptor::with_padding(&TEST[..17], cipher, &key, &iv, Padding::None).unwrap();
+    let err = encryptor.read_to_end(&mut Vec::new()).unwrap_err();
+    assert!(matches!(
+        crate::Error::from(err),
+        crate::Error::IncompletePlaintext
+    ));
+}
+
+#[test]
+fn bad_padding() {
+    let (key, iv) = init_secrets();
+    // A final block ending in a zero byte is invalid for every scheme but zero padding.
+    let encrypted = unpadded(Mode::Encrypt, &[0u8; 32], &key, &iv);
+
+    for &padding in &[
+        Padding::Pkcs7,
+        Padding::Iso10126,
+        Padding::AnsiX923,
+        Padding::Iso7816_4,
+    ] {
+        let err = read_decrypt(&encrypted, &key, &iv, padding).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(matches!(crate::Error::from(err), crate::Error::BadPadding));
+
+        let err = write_decrypt(&encrypted, &key, &iv, padding).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    // PKCS#7 and ANSI X9.23 also check the bytes preceding the length.
+    let mut block = [7u8; 16];
+    block[10] = 0;
+    let encrypted = unpadded(Mode::Encrypt, &block, &key, &iv);
+    assert!(read_decrypt(&encrypted, &key, &iv, Padding::Pkcs7).is_err());
+    assert!(read_decrypt(&encrypted, &key, &iv, Padding::AnsiX923).is_err());
+    assert_eq!(
+        read_decrypt(&encrypted, &key, &iv, Padding::Iso10126).unwrap(),
+        &block[..9]
+    );
+}
+
+#[test]
+fn stream_ciphers_ignore_padding() {
+    let (key, iv) = init_secrets();
+    let cipher = Cipher::aes_128_ctr();
+    for &padding in &SCHEMES {
+        let mut encryptor =
+            write::Encryptor::with_padding(Vec::new(), cipher, &key, &iv, padding).unwrap();
+        encryptor.write_all(&TEST[..17]).unwrap();
+        let encrypted = encryptor.finish().unwrap();
+        assert_eq!(
+            encrypted,
+            encrypt(cipher, &key, Some(&iv), &TEST[..17]).unwrap()
+        );
+    }
+}
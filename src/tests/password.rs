@@ -0,0 +1,232 @@
+//! Tests for the password-keyed cryptostreams.
+
+use super::TEST;
+use crate::password::{Kdf, KdfLimits};
+use crate::Cipher;
+use crate::{read, write, Error};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+const PASSWORD: &[u8] = b"correct horse battery staple";
+
+/// Parameters cheap enough not to slow the tests down.
+fn kdfs() -> Vec<Kdf> {
+    vec![
+        Kdf::Pbkdf2 { iterations: 1000 },
+        Kdf::Scrypt {
+            log_n: 10,
+            r: 8,
+            p: 1,
+        },
+        #[cfg(feature = "argon2")]
+        Kdf::Argon2id {
+            memory_kib: 64,
+            iterations: 1,
+            lanes: 1,
+        },
+    ]
+}
+
+fn encrypt(plaintext: &[u8], cipher: Cipher, kdf: Kdf) -> Vec<u8> {
+    let mut encryptor = write::Encryptor::with_password(Vec::new(), cipher, PASSWORD, kdf).unwrap();
+    encryptor.write_all(plaintext).unwrap();
+    encryptor.finish().unwrap()
+}
+
+fn decrypt(ciphertext: &[u8], cipher: Cipher, password: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decryptor = read::Decryptor::with_password(ciphertext, cipher, password)?;
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted)?;
+    Ok(decrypted)
+}
+
+#[test]
+fn roundtrip() {
+    let cipher = Cipher::aes_256_cbc();
+    for kdf in kdfs() {
+        let encrypted = encrypt(TEST, cipher, kdf);
+        assert_eq!(decrypt(&encrypted, cipher, PASSWORD).unwrap(), TEST);
+
+        // The read encryptor and write decryptor produce and accept the same format.
+        let mut encryptor = read::Encryptor::with_password(TEST, cipher, PASSWORD, kdf).unwrap();
+        let mut encrypted = Vec::new();
+        encryptor.read_to_end(&mut encrypted).unwrap();
+
+        // Write a byte at a time to exercise accumulating the header.
+        let mut decryptor = write::Decryptor::with_password(Vec::new(), cipher, PASSWORD);
+        for byte in &encrypted {
+            decryptor.write_all(std::slice::from_ref(byte)).unwrap();
+        }
+        assert_eq!(decryptor.finish().unwrap(), TEST);
+    }
+}
+
+#[test]
+fn salt_is_random() {
+    let cipher = Cipher::aes_128_ctr();
+    let kdf = Kdf::Pbkdf2 { iterations: 1000 };
+    assert_ne!(encrypt(TEST, cipher, kdf), encrypt(TEST, cipher, kdf));
+}
+
+#[test]
+fn wrong_password() {
+    let cipher = Cipher::aes_256_cbc();
+    let encrypted = encrypt(TEST, cipher, Kdf::Pbkdf2 { iterations: 1000 });
+    // Usually rejected as badly padded, but the padding can happen to check out by chance.
+    if let Ok(decrypted) = decrypt(&encrypted, cipher, b"wrong password") {
+        assert_ne!(decrypted, TEST);
+    }
+}
+
+#[test]
+fn rejects_bad_headers() {
+    let cipher = Cipher::aes_256_cbc();
+
+    let err = decrypt(&[0xFF, 0, 0, 0, 0], cipher, PASSWORD).unwrap_err();
+    assert!(matches!(Error::from(err), Error::UnsupportedKdf));
+
+    let mut excessive = vec![1];
+    excessive.extend_from_slice(&u32::MAX.to_be_bytes());
+    excessive.push(0);
+    let err = decrypt(&excessive, cipher, PASSWORD).unwrap_err();
+    assert!(matches!(Error::from(err), Error::InvalidKdfParameters));
+
+    let encrypted = encrypt(TEST, cipher, Kdf::Pbkdf2 { iterations: 1000 });
+    let err = decrypt(&encrypted[..10], cipher, PASSWORD).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    let mut decryptor = write::Decryptor::with_password(Vec::new(), cipher, PASSWORD);
+    decryptor.write_all(&encrypted[..10]).unwrap();
+    let err = decryptor.finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    let kdf = Kdf::Pbkdf2 {
+        iterations: Kdf::MAX_PBKDF2_ITERATIONS + 1,
+    };
+    let err = write::Encryptor::with_password(Vec::new(), cipher, PASSWORD, kdf)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::InvalidKdfParameters));
+}
+
+#[test]
+fn rejects_excessive_scrypt_memory() {
+    let cipher = Cipher::aes_256_cbc();
+
+    // Both within the old `N·r·p <= 2^32` limit, but needing 512 GiB and 256 GiB respectively.
+    for (log_n, r, p) in [(24, 256, 1), (1, 1, 1 << 31)] {
+        let mut header = vec![2, log_n];
+        header.extend_from_slice(&u32::to_be_bytes(r));
+        header.extend_from_slice(&u32::to_be_bytes(p));
+        header.push(0);
+        let err = decrypt(&header, cipher, PASSWORD).unwrap_err();
+        assert!(matches!(Error::from(err), Error::InvalidKdfParameters));
+
+        let kdf = Kdf::Scrypt { log_n, r, p };
+        let err = write::Encryptor::with_password(Vec::new(), cipher, PASSWORD, kdf)
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidKdfParameters));
+    }
+
+    // The default parameters need 128 MiB.
+    let mut encryptor =
+        write::Encryptor::with_password(Vec::new(), cipher, PASSWORD, Kdf::scrypt()).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    encryptor.finish().unwrap();
+}
+
+#[test]
+fn rejects_short_salts() {
+    let cipher = Cipher::aes_256_cbc();
+
+    for salt_len in [0, 8, 15] {
+        let mut header = vec![1];
+        header.extend_from_slice(&1000u32.to_be_bytes());
+        header.push(salt_len as u8);
+        header.resize(header.len() + salt_len, 0);
+        let err = decrypt(&header, cipher, PASSWORD).unwrap_err();
+        assert!(matches!(Error::from(err), Error::InvalidKdfParameters));
+
+        let mut decryptor = write::Decryptor::with_password(Vec::new(), cipher, PASSWORD);
+        let err = decryptor.write_all(&header).unwrap_err();
+        assert!(matches!(Error::from(err), Error::InvalidKdfParameters));
+    }
+}
+
+#[test]
+fn default_limits() {
+    let limits = KdfLimits::default();
+    assert_eq!(limits.pbkdf2_iterations, 10_000_000);
+    assert_eq!(limits.argon2_memory_kib, 256 * 1024);
+
+    // Headers exceeding the defaults are rejected before deriving anything.
+    let cipher = Cipher::aes_256_cbc();
+    let mut header = vec![1];
+    header.extend_from_slice(&(Kdf::MAX_PBKDF2_ITERATIONS + 1).to_be_bytes());
+    let err = decrypt(&header, cipher, PASSWORD).unwrap_err();
+    assert!(matches!(Error::from(err), Error::InvalidKdfParameters));
+
+    #[cfg(feature = "argon2")]
+    {
+        let mut header = vec![3];
+        header.extend_from_slice(&(Kdf::MAX_ARGON2_MEMORY_KIB + 1).to_be_bytes());
+        header.extend_from_slice(&1u32.to_be_bytes());
+        header.extend_from_slice(&1u32.to_be_bytes());
+        let err = decrypt(&header, cipher, PASSWORD).unwrap_err();
+        assert!(matches!(Error::from(err), Error::InvalidKdfParameters));
+    }
+}
+
+#[test]
+fn explicit_limits() {
+    let cipher = Cipher::aes_256_cbc();
+    let kdf = Kdf::Pbkdf2 { iterations: 1000 };
+    let encrypted = encrypt(TEST, cipher, kdf);
+
+    let limits = |pbkdf2_iterations| KdfLimits {
+        pbkdf2_iterations,
+        ..KdfLimits::default()
+    };
+
+    let mut decryptor =
+        read::Decryptor::with_password_and_limits(&encrypted[..], cipher, PASSWORD, limits(1000))
+            .unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, TEST);
+
+    let mut decryptor =
+        write::Decryptor::with_password_and_limits(Vec::new(), cipher, PASSWORD, limits(1000));
+    decryptor.write_all(&encrypted).unwrap();
+    assert_eq!(decryptor.finish().unwrap(), TEST);
+
+    let err =
+        read::Decryptor::with_password_and_limits(&encrypted[..], cipher, PASSWORD, limits(999))
+            .err()
+            .unwrap();
+    assert!(matches!(err, Error::InvalidKdfParameters));
+
+    let mut decryptor =
+        write::Decryptor::with_password_and_limits(Vec::new(), cipher, PASSWORD, limits(999));
+    let err = decryptor.write_all(&encrypted).unwrap_err();
+    assert!(matches!(Error::from(err), Error::InvalidKdfParameters));
+}
+
+#[test]
+fn ctr_seek_skips_header() {
+    let cipher = Cipher::aes_128_ctr();
+    let plaintext: Vec<u8> = (0..1000).map(|_| rand::random()).collect();
+    let encrypted = encrypt(&plaintext, cipher, Kdf::Pbkdf2 { iterations: 1000 });
+
+    let mut decryptor =
+        read::Decryptor::with_password(Cursor::new(encrypted), cipher, PASSWORD).unwrap();
+    let mut buffer = [0u8; 100];
+
+    assert_eq!(decryptor.seek(SeekFrom::Start(500)).unwrap(), 500);
+    decryptor.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer[..], &plaintext[500..600]);
+
+    assert_eq!(decryptor.seek(SeekFrom::End(-100)).unwrap(), 900);
+    decryptor.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer[..], &plaintext[900..]);
+}
@@ -4,7 +4,8 @@
 //! large destination buffer.
 
 use crate::bufread;
-use openssl::symm::{Cipher, Crypter, Mode};
+use super::symm::Crypter;
+use crate::{Cipher, Mode};
 use std::io::{BufReader, Read};
 use std::io;
 
@@ -0,0 +1,204 @@
+//! Tests for compatibility with libsodium's `crypto_secretstream_xchacha20poly1305`, against
+//! fixtures generated with libsodium 1.0.18 (via Python's `ctypes`) under the key in
+//! `secretstream_key.bin`:
+//!
+//! * `secretstream_messages.bin`: `enc_plaintext.txt` pushed as the messages in [`MESSAGES`], with
+//!   an explicit `crypto_secretstream_xchacha20poly1305_rekey()` before the last.
+//! * `secretstream_chunked.bin` and `secretstream_chunked_exact.bin`: 10000 and 8192 bytes of
+//!   [`chunked_plaintext()`], pushed in chunks of 4096 bytes as in the file encryption example of
+//!   the libsodium documentation.
+
+use crate::secretstream::{PullStream, PushStream, Tag, ABYTES, HEADER_LEN};
+use crate::{bufread, read, write, Error};
+use std::convert::TryInto;
+use std::io::{ErrorKind, Read, Write};
+
+const KEY: &[u8] = include_bytes!("fixtures/secretstream_key.bin");
+const PLAINTEXT: &[u8] = include_bytes!("fixtures/enc_plaintext.txt");
+const MESSAGES_FIXTURE: &[u8] = include_bytes!("fixtures/secretstream_messages.bin");
+const CHUNKED_FIXTURE: &[u8] = include_bytes!("fixtures/secretstream_chunked.bin");
+const CHUNKED_EXACT_FIXTURE: &[u8] = include_bytes!("fixtures/secretstream_chunked_exact.bin");
+
+/// The end of each message within `PLAINTEXT`, with its associated data and tag.
+const MESSAGES: &[(usize, &[u8], Tag)] = &[
+    (20, b"", Tag::Message),
+    (50, b"associated data", Tag::Push),
+    (90, b"", Tag::Rekey),
+    (120, b"", Tag::Message),
+    (PLAINTEXT.len(), b"", Tag::Final),
+];
+
+fn chunked_plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| ((i * 7) % 251) as u8).collect()
+}
+
+fn header(fixture: &[u8]) -> &[u8; HEADER_LEN] {
+    fixture[..HEADER_LEN].try_into().unwrap()
+}
+
+#[test]
+fn pull_messages() {
+    let mut stream = PullStream::new(KEY, header(MESSAGES_FIXTURE)).unwrap();
+    let mut ciphertext = &MESSAGES_FIXTURE[HEADER_LEN..];
+    let mut decrypted = Vec::new();
+
+    let mut start = 0;
+    for (i, &(end, ad, tag)) in MESSAGES.iter().enumerate() {
+        if i == MESSAGES.len() - 1 {
+            stream.rekey().unwrap();
+        }
+        let (message, rest) = ciphertext.split_at(end - start + ABYTES);
+        assert_eq!(stream.pull(message, ad, &mut decrypted).unwrap(), tag);
+        ciphertext = rest;
+        start = end;
+    }
+
+    assert!(ciphertext.is_empty());
+    assert_eq!(decrypted, PLAINTEXT);
+}
+
+#[test]
+fn push_messages() {
+    let mut stream = PushStream::with_header(KEY, header(MESSAGES_FIXTURE)).unwrap();
+    let mut encrypted = MESSAGES_FIXTURE[..HEADER_LEN].to_vec();
+
+    let mut start = 0;
+    for (i, &(end, ad, tag)) in MESSAGES.iter().enumerate() {
+        if i == MESSAGES.len() - 1 {
+            stream.rekey().unwrap();
+        }
+        stream
+            .push(&PLAINTEXT[start..end], ad, tag, &mut encrypted)
+            .unwrap();
+        start = end;
+    }
+
+    assert_eq!(encrypted, MESSAGES_FIXTURE);
+}
+
+#[test]
+fn pull_failures_leave_stream_intact() {
+    let mut stream = PullStream::new(KEY, header(MESSAGES_FIXTURE)).unwrap();
+    let message = &MESSAGES_FIXTURE[HEADER_LEN..][..20 + ABYTES];
+    let mut decrypted = Vec::new();
+
+    let mut tampered = message.to_vec();
+    tampered[0] ^= 1;
+    let err = stream.pull(&tampered, b"", &mut decrypted).unwrap_err();
+    assert!(matches!(err, Error::AuthenticationFailed));
+    let err = stream.pull(message, b"ad", &mut decrypted).unwrap_err();
+    assert!(matches!(err, Error::AuthenticationFailed));
+    let err = stream
+        .pull(&message[..16], b"", &mut decrypted)
+        .unwrap_err();
+    assert!(matches!(err, Error::Truncated));
+    assert!(decrypted.is_empty());
+
+    assert_eq!(
+        stream.pull(message, b"", &mut decrypted).unwrap(),
+        Tag::Message
+    );
+    assert_eq!(decrypted, PLAINTEXT[..20]);
+}
+
+#[test]
+fn decrypt_chunked() {
+    for (fixture, len) in [(CHUNKED_FIXTURE, 10000), (CHUNKED_EXACT_FIXTURE, 8192)] {
+        let plaintext = chunked_plaintext(len);
+
+        let mut decryptor = read::SecretStreamDecryptor::new(fixture, KEY).unwrap();
+        let mut decrypted = Vec::new();
+        decryptor.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        // Written in uneven pieces, so that chunks (and the header) arrive piecemeal.
+        let mut decryptor = write::SecretStreamDecryptor::new(Vec::new(), KEY).unwrap();
+        for piece in fixture.chunks(1000) {
+            decryptor.write_all(piece).unwrap();
+        }
+        assert_eq!(decryptor.finish().unwrap(), plaintext);
+    }
+}
+
+#[test]
+fn roundtrip() {
+    for len in [10000, 8192, 100, 0] {
+        let plaintext = chunked_plaintext(len);
+
+        let mut encryptor = write::SecretStreamEncryptor::new(Vec::new(), KEY).unwrap();
+        encryptor.write_all(&plaintext).unwrap();
+        let encrypted = encryptor.finish().unwrap();
+        // Plaintext filling its last chunk is followed by an empty final chunk, as with libsodium.
+        let chunks = len / 4096 + 1;
+        assert_eq!(encrypted.len(), HEADER_LEN + len + chunks * ABYTES);
+
+        let mut decryptor = bufread::SecretStreamDecryptor::new(&encrypted[..], KEY).unwrap();
+        let mut decrypted = Vec::new();
+        decryptor.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        let mut encryptor =
+            read::SecretStreamEncryptor::with_chunk_size(&plaintext[..], KEY, 1000).unwrap();
+        let mut encrypted = Vec::new();
+        encryptor.read_to_end(&mut encrypted).unwrap();
+        let mut decryptor =
+            write::SecretStreamDecryptor::with_chunk_size(Vec::new(), KEY, 1000).unwrap();
+        decryptor.write_all(&encrypted).unwrap();
+        assert_eq!(decryptor.finish().unwrap(), plaintext);
+    }
+}
+
+#[test]
+fn tampering_and_truncation() {
+    fn decrypt(ciphertext: &[u8], key: &[u8]) -> Error {
+        let mut decryptor = read::SecretStreamDecryptor::new(ciphertext, key).unwrap();
+        let err = decryptor.read_to_end(&mut Vec::new()).unwrap_err();
+        Error::from(err)
+    }
+
+    let mut tampered = CHUNKED_FIXTURE.to_vec();
+    tampered[5000] ^= 1;
+    assert!(matches!(
+        decrypt(&tampered, KEY),
+        Error::AuthenticationFailed
+    ));
+
+    let other_key: [u8; 32] = rand::random();
+    assert!(matches!(
+        decrypt(CHUNKED_FIXTURE, &other_key),
+        Error::AuthenticationFailed
+    ));
+
+    // Cut off at a chunk boundary, before the final chunk, and within the header.
+    let boundary = HEADER_LEN + 2 * (4096 + ABYTES);
+    for len in [boundary, HEADER_LEN, 10] {
+        assert!(matches!(
+            decrypt(&CHUNKED_FIXTURE[..len], KEY),
+            Error::Truncated
+        ));
+
+        let mut decryptor = write::SecretStreamDecryptor::new(Vec::new(), KEY).unwrap();
+        decryptor.write_all(&CHUNKED_FIXTURE[..len]).unwrap();
+        let err = decryptor.finish().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    // Nothing may follow the final chunk.
+    let (mut stream, header) = PushStream::new(KEY).unwrap();
+    let mut extended = header.to_vec();
+    stream
+        .push(b"full", b"", Tag::Final, &mut extended)
+        .unwrap();
+    stream.push(b"", b"", Tag::Final, &mut extended).unwrap();
+    let mut decryptor =
+        read::SecretStreamDecryptor::with_chunk_size(&extended[..], KEY, 4).unwrap();
+    let mut decrypted = Vec::new();
+    let err = decryptor.read_to_end(&mut decrypted).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(decrypted.is_empty());
+
+    let err = read::SecretStreamDecryptor::new(CHUNKED_FIXTURE, &KEY[..16])
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::InvalidKeyLength));
+}
@@ -0,0 +1,156 @@
+//! Tests for seeking within decrypting cryptostreams.
+
+use super::symm::encrypt;
+use crate::Cipher;
+use crate::{read, write};
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom, Write};
+
+const SEGMENT_SIZE: usize = 100;
+
+fn plaintext(len: usize) -> Vec<u8> {
+    let mut plaintext = vec![0u8; len];
+    openssl::rand::rand_bytes(&mut plaintext).unwrap();
+    plaintext
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, pos: SeekFrom, len: usize) -> (u64, Vec<u8>) {
+    let position = reader.seek(pos).unwrap();
+    let mut buffer = vec![0u8; len];
+    reader.read_exact(&mut buffer).unwrap();
+    (position, buffer)
+}
+
+#[test]
+fn ctr_seek() {
+    let cipher = Cipher::aes_256_ctr();
+    let key: [u8; 256 / 8] = rand::random();
+    // Start close to the end of the counter space to exercise carrying into the upper bytes.
+    let mut iv = [0xFFu8; 128 / 8];
+    iv[0] = rand::random();
+
+    let plaintext = plaintext(10_000);
+    let encrypted = encrypt(cipher, &key, Some(&iv), &plaintext).unwrap();
+    let mut decryptor = read::Decryptor::new(Cursor::new(encrypted), cipher, &key, &iv).unwrap();
+
+    for &(pos, expected) in &[
+        (SeekFrom::Start(5000), 5000),
+        (SeekFrom::Start(17), 17),
+        (SeekFrom::Current(100), 17 + 10 + 100),
+        (SeekFrom::Current(-50), 127 + 10 - 50),
+        (SeekFrom::End(-25), 9975),
+        (SeekFrom::Start(0), 0),
+    ] {
+        let (position, decrypted) = read_at(&mut decryptor, pos, 10);
+        assert_eq!(position, expected);
+        assert_eq!(decrypted, &plaintext[expected as usize..][..10]);
+    }
+
+    let mut decrypted = Vec::new();
+    decryptor.seek(SeekFrom::Start(1234)).unwrap();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert!(decrypted == plaintext[1234..]);
+    assert_eq!(decryptor.stream_position().unwrap(), 10_000);
+
+    assert_eq!(decryptor.seek(SeekFrom::End(0)).unwrap(), 10_000);
+    assert_eq!(decryptor.read(&mut [0u8; 16]).unwrap(), 0);
+}
+
+#[test]
+fn cbc_seek_is_unsupported() {
+    let cipher = Cipher::aes_128_cbc();
+    let key: [u8; 128 / 8] = rand::random();
+    let iv: [u8; 128 / 8] = rand::random();
+
+    let encrypted = encrypt(cipher, &key, Some(&iv), b"hello").unwrap();
+    let mut decryptor = read::Decryptor::new(Cursor::new(encrypted), cipher, &key, &iv).unwrap();
+
+    let err = decryptor.seek(SeekFrom::Start(1)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+}
+
+#[test]
+fn segmented_seek() {
+    let cipher = Cipher::chacha20_poly1305();
+    let key: [u8; 256 / 8] = rand::random();
+    let nonce_prefix: [u8; 7] = rand::random();
+
+    let plaintext = plaintext(SEGMENT_SIZE * 5 + 42);
+    let mut encryptor = write::SegmentedEncryptor::with_segment_size(
+        Vec::new(),
+        cipher,
+        &key,
+        &nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    encryptor.write_all(&plaintext).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut decryptor = read::SegmentedDecryptor::with_segment_size(
+        Cursor::new(encrypted),
+        cipher,
+        &key,
+        &nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+
+    for &(pos, expected) in &[
+        (SeekFrom::Start(250), 250),
+        (SeekFrom::Start(300), 300),
+        (SeekFrom::Start(95), 95),
+        (SeekFrom::Current(-95), 10),
+        (SeekFrom::End(-42), 500),
+        (SeekFrom::End(-10), 532),
+        (SeekFrom::Start(0), 0),
+    ] {
+        let (position, decrypted) = read_at(&mut decryptor, pos, 10);
+        assert_eq!(position, expected);
+        assert_eq!(decrypted, &plaintext[expected as usize..][..10]);
+    }
+
+    let mut decrypted = Vec::new();
+    decryptor.seek(SeekFrom::Start(123)).unwrap();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert!(decrypted == plaintext[123..]);
+
+    assert_eq!(
+        decryptor.seek(SeekFrom::End(0)).unwrap(),
+        plaintext.len() as u64
+    );
+    assert_eq!(decryptor.read(&mut [0u8; 16]).unwrap(), 0);
+}
+
+#[test]
+fn segmented_seek_to_end_of_full_segment() {
+    let cipher = Cipher::aes_128_gcm();
+    let key: [u8; 128 / 8] = rand::random();
+    let nonce_prefix: [u8; 7] = rand::random();
+
+    let plaintext = plaintext(SEGMENT_SIZE * 2);
+    let mut encryptor = write::SegmentedEncryptor::with_segment_size(
+        Vec::new(),
+        cipher,
+        &key,
+        &nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    encryptor.write_all(&plaintext).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut decryptor = read::SegmentedDecryptor::with_segment_size(
+        Cursor::new(encrypted),
+        cipher,
+        &key,
+        &nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+
+    assert_eq!(decryptor.seek(SeekFrom::End(0)).unwrap(), 200);
+    assert_eq!(decryptor.read(&mut [0u8; 16]).unwrap(), 0);
+
+    let (_, decrypted) = read_at(&mut decryptor, SeekFrom::Start(100), 100);
+    assert_eq!(decrypted, &plaintext[100..]);
+}
@@ -0,0 +1,223 @@
+//! Tests for the segmented (STREAM) cryptostream variants.
+
+use crate::Cipher;
+use crate::{bufread, read, write};
+use std::io::{ErrorKind, Read, Write};
+
+const SEGMENT_SIZE: usize = 16;
+const SEGMENT_LEN: usize = SEGMENT_SIZE + 16;
+
+fn init_secrets() -> (Cipher, [u8; 256 / 8], [u8; 7]) {
+    (Cipher::aes_256_gcm(), rand::random(), rand::random())
+}
+
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i as u8).collect()
+}
+
+fn encrypt(plaintext: &[u8], cipher: Cipher, key: &[u8], nonce_prefix: &[u8; 7]) -> Vec<u8> {
+    let mut encryptor = write::SegmentedEncryptor::with_segment_size(
+        Vec::new(),
+        cipher,
+        key,
+        nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    encryptor.write_all(plaintext).unwrap();
+    encryptor.finish().unwrap()
+}
+
+fn decrypt(
+    ciphertext: &[u8],
+    cipher: Cipher,
+    key: &[u8],
+    nonce_prefix: &[u8; 7],
+) -> (Vec<u8>, std::io::Result<()>) {
+    let mut decryptor = read::SegmentedDecryptor::with_segment_size(
+        ciphertext,
+        cipher,
+        key,
+        nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    let mut decrypted = Vec::new();
+    let result = decryptor.read_to_end(&mut decrypted).map(|_| ());
+    (decrypted, result)
+}
+
+#[test]
+fn roundtrip() {
+    for &cipher in &[Cipher::aes_128_gcm(), Cipher::chacha20_poly1305()] {
+        let key: [u8; 32] = rand::random();
+        let key = &key[..cipher.key_len()];
+        let nonce_prefix: [u8; 7] = rand::random();
+
+        for &len in &[
+            0,
+            1,
+            SEGMENT_SIZE - 1,
+            SEGMENT_SIZE,
+            SEGMENT_SIZE + 1,
+            SEGMENT_SIZE * 3,
+        ] {
+            let plaintext = plaintext(len);
+            let encrypted = encrypt(&plaintext, cipher, key, &nonce_prefix);
+
+            let segments = std::cmp::max(1, len.div_ceil(SEGMENT_SIZE));
+            assert_eq!(encrypted.len(), len + segments * 16);
+
+            let (decrypted, result) = decrypt(&encrypted, cipher, key, &nonce_prefix);
+            result.unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+}
+
+#[test]
+fn read_encrypt_matches_write_encrypt() {
+    let (cipher, key, nonce_prefix) = init_secrets();
+    let plaintext = plaintext(SEGMENT_SIZE * 2 + 5);
+
+    let mut encryptor = read::SegmentedEncryptor::with_segment_size(
+        plaintext.as_slice(),
+        cipher,
+        &key,
+        &nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    let mut encrypted = Vec::new();
+    encryptor.read_to_end(&mut encrypted).unwrap();
+
+    assert_eq!(encrypted, encrypt(&plaintext, cipher, &key, &nonce_prefix));
+}
+
+#[test]
+fn write_decrypt_roundtrip() {
+    let (cipher, key, nonce_prefix) = init_secrets();
+    let plaintext = plaintext(SEGMENT_SIZE * 4);
+    let encrypted = encrypt(&plaintext, cipher, &key, &nonce_prefix);
+
+    let mut decryptor = write::SegmentedDecryptor::with_segment_size(
+        Vec::new(),
+        cipher,
+        &key,
+        &nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    for chunk in encrypted.chunks(5) {
+        decryptor.write_all(chunk).unwrap();
+    }
+
+    assert_eq!(decryptor.finish().unwrap(), plaintext);
+}
+
+#[test]
+fn default_segment_size_roundtrip() {
+    let (cipher, key, nonce_prefix) = init_secrets();
+    let plaintext = plaintext(200 * 1024);
+
+    let mut encryptor =
+        write::SegmentedEncryptor::new(Vec::new(), cipher, &key, &nonce_prefix).unwrap();
+    encryptor.write_all(&plaintext).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+    assert_eq!(encrypted.len(), plaintext.len() + 4 * 16);
+
+    let mut decryptor =
+        bufread::SegmentedDecryptor::new(&encrypted[..], cipher, &key, &nonce_prefix).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert!(decrypted == plaintext);
+}
+
+#[test]
+fn truncation_is_detected() {
+    let (cipher, key, nonce_prefix) = init_secrets();
+    let plaintext = plaintext(SEGMENT_SIZE * 3);
+    let encrypted = encrypt(&plaintext, cipher, &key, &nonce_prefix);
+
+    // Dropping whole segments leaves a stream without a final segment
+    for segments in 0..3 {
+        let (decrypted, result) = decrypt(
+            &encrypted[..segments * SEGMENT_LEN],
+            cipher,
+            &key,
+            &nonce_prefix,
+        );
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(
+            decrypted,
+            &plaintext[..segments.saturating_sub(1) * SEGMENT_SIZE]
+        );
+    }
+
+    let (_, result) = decrypt(
+        &encrypted[..encrypted.len() - 1],
+        cipher,
+        &key,
+        &nonce_prefix,
+    );
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn reordering_is_detected() {
+    let (cipher, key, nonce_prefix) = init_secrets();
+    let plaintext = plaintext(SEGMENT_SIZE * 3);
+    let encrypted = encrypt(&plaintext, cipher, &key, &nonce_prefix);
+
+    let mut reordered = Vec::new();
+    reordered.extend_from_slice(&encrypted[SEGMENT_LEN..][..SEGMENT_LEN]);
+    reordered.extend_from_slice(&encrypted[..SEGMENT_LEN]);
+    reordered.extend_from_slice(&encrypted[2 * SEGMENT_LEN..]);
+
+    let (decrypted, result) = decrypt(&reordered, cipher, &key, &nonce_prefix);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    assert!(decrypted.is_empty());
+}
+
+#[test]
+fn tampered_segment_releases_no_plaintext() {
+    let (cipher, key, nonce_prefix) = init_secrets();
+    let plaintext = plaintext(SEGMENT_SIZE * 3);
+    let mut encrypted = encrypt(&plaintext, cipher, &key, &nonce_prefix);
+    encrypted[SEGMENT_LEN + 3] ^= 0x80;
+
+    let (decrypted, result) = decrypt(&encrypted, cipher, &key, &nonce_prefix);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(decrypted, &plaintext[..SEGMENT_SIZE]);
+
+    let mut decryptor = write::SegmentedDecryptor::with_segment_size(
+        Vec::new(),
+        cipher,
+        &key,
+        &nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    let err = decryptor.write_all(&encrypted).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn trailing_data_is_detected() {
+    let (cipher, key, nonce_prefix) = init_secrets();
+    let mut encrypted = encrypt(&plaintext(SEGMENT_SIZE), cipher, &key, &nonce_prefix);
+    encrypted.extend_from_slice(&encrypt(b"more", cipher, &key, &nonce_prefix));
+
+    let (_, result) = decrypt(&encrypted, cipher, &key, &nonce_prefix);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn wrong_nonce_prefix_is_rejected() {
+    let (cipher, key, nonce_prefix) = init_secrets();
+    let encrypted = encrypt(b"hello", cipher, &key, &nonce_prefix);
+
+    let (decrypted, result) = decrypt(&encrypted, cipher, &key, &[0u8; 7]);
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    assert!(decrypted.is_empty());
+}
@@ -0,0 +1,145 @@
+//! Tests for the segmented cryptostreams sealed with AES-GCM-SIV.
+
+use super::symm::Crypter;
+use super::TEST;
+use crate::transform::Transformer;
+use crate::{bufread, read, siv, write, Error};
+use crate::{Cipher, Mode};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn encrypt(cipher: Cipher, key: &[u8], nonce_prefix: &[u8; 7], plaintext: &[u8]) -> Vec<u8> {
+    let mut encryptor =
+        write::SegmentedEncryptor::with_segment_size(Vec::new(), cipher, key, nonce_prefix, 64)
+            .unwrap();
+    encryptor.write_all(plaintext).unwrap();
+    encryptor.finish().unwrap()
+}
+
+/// The first AES-128-GCM-SIV test vectors of RFC 8452, appendix C.1.
+#[test]
+fn rfc8452_vectors() {
+    let cipher = siv::aes_128_gcm_siv().unwrap();
+    let key = hex("01000000000000000000000000000000");
+    let nonce = hex("030000000000000000000000");
+
+    for (plaintext, expected) in [
+        ("", "dc20e2d83f25705bb49e439eca56de25"),
+        (
+            "0100000000000000",
+            "b5d839330ac7b786578782fff6013b815b287c22493a364c",
+        ),
+    ] {
+        let plaintext = hex(plaintext);
+        let mut crypter = Crypter::new(cipher, Mode::Encrypt, &key, Some(&nonce)).unwrap();
+        let mut sealed = vec![0u8; plaintext.len() + 16];
+        let written = crypter.update(&plaintext, &mut sealed).unwrap();
+        crypter.finalize(&mut sealed[written..]).unwrap();
+        crypter.get_tag(&mut sealed[plaintext.len()..]).unwrap();
+        assert_eq!(sealed, hex(expected));
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let cipher = siv::aes_256_gcm_siv().unwrap();
+    let key: [u8; 32] = rand::random();
+    let nonce_prefix: [u8; 7] = rand::random();
+    let plaintext: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+
+    // Including an empty final segment, as the plaintext is a multiple of the segment size.
+    for plaintext in [&plaintext[..], &plaintext[..640], b""] {
+        let encrypted = encrypt(cipher, &key, &nonce_prefix, plaintext);
+
+        let mut decryptor = write::SegmentedDecryptor::with_segment_size(
+            Vec::new(),
+            cipher,
+            &key,
+            &nonce_prefix,
+            64,
+        )
+        .unwrap();
+        decryptor.write_all(&encrypted).unwrap();
+        assert_eq!(decryptor.finish().unwrap(), plaintext);
+
+        let mut encryptor =
+            read::SegmentedEncryptor::with_segment_size(plaintext, cipher, &key, &nonce_prefix, 64)
+                .unwrap();
+        let mut read_encrypted = Vec::new();
+        encryptor.read_to_end(&mut read_encrypted).unwrap();
+        assert_eq!(read_encrypted, encrypted);
+    }
+
+    let encrypted = encrypt(cipher, &key, &nonce_prefix, &plaintext);
+    let mut decryptor = bufread::SegmentedDecryptor::with_segment_size(
+        Cursor::new(&encrypted),
+        cipher,
+        &key,
+        &nonce_prefix,
+        64,
+    )
+    .unwrap();
+    decryptor.seek(SeekFrom::Start(500)).unwrap();
+    let mut buf = [0u8; 100];
+    decryptor.read_exact(&mut buf).unwrap();
+    assert_eq!(buf[..], plaintext[500..600]);
+
+    // Tampering is detected as with any other AEAD cipher.
+    let mut tampered = encrypted;
+    tampered[100] ^= 1;
+    let mut decryptor =
+        read::SegmentedDecryptor::with_segment_size(&tampered[..], cipher, &key, &nonce_prefix, 64)
+            .unwrap();
+    let err = decryptor.read_to_end(&mut Vec::new()).unwrap_err();
+    assert!(matches!(Error::from(err), Error::AuthenticationFailed));
+}
+
+#[test]
+fn nonce_reuse_only_reveals_equal_segments() {
+    let key: [u8; 16] = rand::random();
+    let nonce_prefix: [u8; 7] = rand::random();
+    let first = [0x17u8; 128];
+    let mut second = first;
+    second[70] ^= 1;
+
+    // With AES-GCM, the XOR of the ciphertexts is the XOR of the plaintexts.
+    let cipher = Cipher::aes_128_gcm();
+    let a = encrypt(cipher, &key, &nonce_prefix, &first);
+    let b = encrypt(cipher, &key, &nonce_prefix, &second);
+    assert_eq!(a[80 + 6] ^ b[80 + 6], 1);
+
+    // With AES-GCM-SIV, the first (identical) segment is sealed identically but the second,
+    // differing in a single bit, is sealed beyond recognition.
+    let cipher = siv::aes_128_gcm_siv().unwrap();
+    let a = encrypt(cipher, &key, &nonce_prefix, &first);
+    let b = encrypt(cipher, &key, &nonce_prefix, &second);
+    assert_eq!(a[..80], b[..80]);
+    let differing = a[80..160].iter().zip(&b[80..160]).filter(|(a, b)| a != b);
+    assert!(differing.count() > 40);
+}
+
+#[test]
+fn rejected_by_other_cryptostreams() {
+    let cipher = siv::aes_256_gcm_siv().unwrap();
+    let key: [u8; 32] = rand::random();
+    let iv: [u8; 12] = rand::random();
+
+    let err = write::AeadEncryptor::new(Vec::new(), cipher, &key, &iv, b"")
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::UnsupportedCipher));
+    let err = read::AeadDecryptor::new(TEST, cipher, &key, &iv, b"")
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::UnsupportedCipher));
+    let err = Transformer::new(Mode::Encrypt, cipher, &key, &iv)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::UnsupportedCipher));
+}
@@ -0,0 +1,97 @@
+//! Tests for the unbuffered in-place path taken by keystream ciphers (CTR, CFB, OFB, ChaCha20).
+
+use super::symm::encrypt;
+use crate::Cipher;
+use crate::{bufread, read, write};
+use std::io::{Read, Write};
+
+fn keystream_ciphers() -> Vec<Cipher> {
+    vec![
+        Cipher::aes_128_ctr(),
+        Cipher::aes_256_ctr(),
+        Cipher::aes_128_cfb128(),
+        Cipher::aes_128_cfb8(),
+        Cipher::aes_128_ofb(),
+        Cipher::chacha20(),
+    ]
+}
+
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7) as u8).collect()
+}
+
+fn secrets(cipher: Cipher) -> (Vec<u8>, Vec<u8>) {
+    let (key, iv): ([u8; 32], [u8; 16]) = (rand::random(), rand::random());
+    (
+        key[..cipher.key_len()].to_vec(),
+        iv[..cipher.iv_len().unwrap()].to_vec(),
+    )
+}
+
+#[test]
+fn matches_openssl() {
+    for cipher in keystream_ciphers() {
+        let (key, iv) = secrets(cipher);
+        let plaintext = plaintext(10_000);
+        let expected = encrypt(cipher, &key, Some(&iv), &plaintext).unwrap();
+        assert_eq!(expected.len(), plaintext.len());
+
+        let mut encryptor = read::Encryptor::new(&plaintext[..], cipher, &key, &iv).unwrap();
+        let mut encrypted = Vec::new();
+        encryptor.read_to_end(&mut encrypted).unwrap();
+        assert_eq!(encrypted, expected);
+
+        let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+        encryptor.write_all(&plaintext).unwrap();
+        assert_eq!(encryptor.finish().unwrap(), expected);
+
+        let mut decryptor = write::Decryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+        decryptor.write_all(&expected).unwrap();
+        assert_eq!(decryptor.finish().unwrap(), plaintext);
+    }
+}
+
+#[test]
+fn writes_are_never_short() {
+    let plaintext = plaintext(3 * 4096 + 17);
+
+    for cipher in keystream_ciphers() {
+        let (key, iv) = secrets(cipher);
+        let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+        assert_eq!(encryptor.write(&plaintext).unwrap(), plaintext.len());
+        assert_eq!(encryptor.write(&[]).unwrap(), 0);
+        let encrypted = encryptor.finish().unwrap();
+        assert_eq!(encrypted.len(), plaintext.len());
+
+        let mut decryptor = write::Decryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+        assert_eq!(decryptor.write(&encrypted).unwrap(), encrypted.len());
+        assert_eq!(decryptor.finish().unwrap(), plaintext);
+    }
+}
+
+#[test]
+fn reads_are_byte_exact() {
+    let plaintext = plaintext(1000);
+
+    for cipher in keystream_ciphers() {
+        let (key, iv) = secrets(cipher);
+        let encrypted = encrypt(cipher, &key, Some(&iv), &plaintext).unwrap();
+
+        // A single read returns everything the source has to offer, rather than a buffer's worth.
+        let mut decryptor = bufread::Decryptor::new(&encrypted[..], cipher, &key, &iv).unwrap();
+        let mut decrypted = vec![0u8; 2000];
+        assert_eq!(decryptor.read(&mut decrypted).unwrap(), plaintext.len());
+        assert_eq!(&decrypted[..plaintext.len()], &plaintext[..]);
+        assert_eq!(decryptor.read(&mut decrypted).unwrap(), 0);
+
+        // Reads of a single byte at a time need no buffering either.
+        let mut decryptor = bufread::Decryptor::new(&encrypted[..], cipher, &key, &iv).unwrap();
+        let mut decrypted = Vec::new();
+        let mut byte = [0u8];
+        assert_eq!(decryptor.read(&mut []).unwrap(), 0);
+        while decryptor.read(&mut byte).unwrap() == 1 {
+            decrypted.push(byte[0]);
+        }
+        assert_eq!(decrypted, plaintext);
+    }
+}
@@ -0,0 +1,84 @@
+//! OpenSSL's `symm` functions, taking the crate's own [`Cipher`] and [`Mode`], as the reference
+//! implementation the cryptostreams are checked against.
+
+use crate::{Cipher, Mode};
+use openssl::error::ErrorStack;
+use std::ops::{Deref, DerefMut};
+
+fn to_openssl(cipher: Cipher) -> openssl::symm::Cipher {
+    cipher
+        .to_openssl()
+        .expect("Cipher not provided by the linked OpenSSL!")
+}
+
+pub fn encrypt(
+    cipher: Cipher,
+    key: &[u8],
+    iv: Option<&[u8]>,
+    data: &[u8],
+) -> Result<Vec<u8>, ErrorStack> {
+    openssl::symm::encrypt(to_openssl(cipher), key, iv, data)
+}
+
+pub fn decrypt(
+    cipher: Cipher,
+    key: &[u8],
+    iv: Option<&[u8]>,
+    data: &[u8],
+) -> Result<Vec<u8>, ErrorStack> {
+    openssl::symm::decrypt(to_openssl(cipher), key, iv, data)
+}
+
+pub fn encrypt_aead(
+    cipher: Cipher,
+    key: &[u8],
+    iv: Option<&[u8]>,
+    aad: &[u8],
+    data: &[u8],
+    tag: &mut [u8],
+) -> Result<Vec<u8>, ErrorStack> {
+    openssl::symm::encrypt_aead(to_openssl(cipher), key, iv, aad, data, tag)
+}
+
+pub fn decrypt_aead(
+    cipher: Cipher,
+    key: &[u8],
+    iv: Option<&[u8]>,
+    aad: &[u8],
+    data: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, ErrorStack> {
+    openssl::symm::decrypt_aead(to_openssl(cipher), key, iv, aad, data, tag)
+}
+
+/// An `openssl::symm::Crypter`, created from a [`Cipher`] and [`Mode`].
+pub struct Crypter(openssl::symm::Crypter);
+
+impl Crypter {
+    pub fn new(
+        cipher: Cipher,
+        mode: Mode,
+        key: &[u8],
+        iv: Option<&[u8]>,
+    ) -> Result<Self, ErrorStack> {
+        let mode = match mode {
+            Mode::Encrypt => openssl::symm::Mode::Encrypt,
+            Mode::Decrypt => openssl::symm::Mode::Decrypt,
+        };
+        openssl::symm::Crypter::new(to_openssl(cipher), mode, key, iv).map(Crypter)
+    }
+}
+
+impl Deref for Crypter {
+    type Target = openssl::symm::Crypter;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Crypter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
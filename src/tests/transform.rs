@@ -0,0 +1,130 @@
+//! Tests for the in-place `Transformer`.
+
+use super::symm::encrypt;
+use super::TEST;
+use crate::transform::Transformer;
+use crate::{write, Error};
+use crate::{Cipher, Mode};
+use std::io::Write;
+
+fn secrets(cipher: Cipher) -> (Vec<u8>, Vec<u8>) {
+    let (key, iv): ([u8; 32], [u8; 16]) = (rand::random(), rand::random());
+    (
+        key[..cipher.key_len()].to_vec(),
+        iv[..cipher.iv_len().unwrap()].to_vec(),
+    )
+}
+
+#[test]
+fn matches_cryptostreams() {
+    for &cipher in &[
+        Cipher::aes_256_ctr(),
+        Cipher::aes_128_cfb8(),
+        Cipher::aes_128_ofb(),
+        Cipher::chacha20(),
+    ] {
+        let (key, iv) = secrets(cipher);
+        let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key, &iv).unwrap();
+        encryptor.write_all(TEST).unwrap();
+        let expected = encryptor.finish().unwrap();
+
+        let mut data = TEST.to_vec();
+        let mut transformer = Transformer::new(Mode::Encrypt, cipher, &key, &iv).unwrap();
+        for chunk in data[..40].chunks_mut(7) {
+            transformer.update_in_place(chunk).unwrap();
+        }
+        assert_eq!(transformer.position(), 40);
+        transformer.finish_in_place(&mut data[40..]).unwrap();
+        assert_eq!(data, expected);
+
+        let transformer = Transformer::new(Mode::Decrypt, cipher, &key, &iv).unwrap();
+        transformer.finish_in_place(&mut data).unwrap();
+        assert_eq!(data, TEST);
+    }
+}
+
+#[test]
+fn large_buffers_in_place() {
+    // Buffers spanning many of the chunks a scratch buffer would be filled in.
+    let plaintext: Vec<u8> = (0..100_003).map(|i| i as u8).collect();
+    for &cipher in &[
+        Cipher::aes_192_ctr(),
+        Cipher::aes_256_cfb128(),
+        Cipher::aes_256_ofb(),
+        Cipher::chacha20(),
+    ] {
+        let (key, iv) = secrets(cipher);
+        let expected = encrypt(cipher, &key, Some(&iv), &plaintext).unwrap();
+
+        let mut data = plaintext.clone();
+        let mut transformer = Transformer::new(Mode::Encrypt, cipher, &key, &iv).unwrap();
+        transformer.update_in_place(&mut data[..65_537]).unwrap();
+        transformer.finish_in_place(&mut data[65_537..]).unwrap();
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn ctr_seek() {
+    let cipher = Cipher::aes_128_ctr();
+    let (key, iv) = secrets(cipher);
+    let plaintext = vec![0x5Au8; 1000];
+    let encrypted = encrypt(cipher, &key, Some(&iv), &plaintext).unwrap();
+
+    let mut transformer = Transformer::new(Mode::Decrypt, cipher, &key, &iv).unwrap();
+    for &position in &[500, 17, 999, 0] {
+        transformer.seek(position).unwrap();
+        let mut data = encrypted[position as usize..].to_vec();
+        transformer.update_in_place(&mut data).unwrap();
+        assert_eq!(data, &plaintext[position as usize..]);
+        assert_eq!(transformer.position(), 1000);
+    }
+
+    let (key, iv) = secrets(Cipher::chacha20());
+    let mut transformer = Transformer::new(Mode::Decrypt, Cipher::chacha20(), &key, &iv).unwrap();
+    assert!(matches!(
+        transformer.seek(10),
+        Err(Error::UnsupportedCipher)
+    ));
+}
+
+#[test]
+fn rejects_block_and_aead_ciphers() {
+    for &cipher in &[
+        Cipher::aes_128_cbc(),
+        Cipher::aes_128_ecb(),
+        Cipher::aes_128_gcm(),
+        Cipher::chacha20_poly1305(),
+    ] {
+        let key = vec![0u8; cipher.key_len()];
+        let iv = vec![0u8; cipher.iv_len().unwrap_or(0)];
+        let result = Transformer::new(Mode::Encrypt, cipher, &key, &iv);
+        assert!(matches!(result, Err(Error::UnsupportedCipher)));
+    }
+
+    let result = Transformer::new(Mode::Encrypt, Cipher::aes_128_ctr(), &[0u8; 32], &[0u8; 16]);
+    assert!(matches!(result, Err(Error::InvalidKeyLength)));
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn bytes_mut() {
+    use bytes::{BufMut, BytesMut};
+
+    let cipher = Cipher::aes_256_ctr();
+    let (key, iv) = secrets(cipher);
+    let expected = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+
+    let mut transformer = Transformer::new(Mode::Encrypt, cipher, &key, &iv).unwrap();
+    let mut buf = BytesMut::with_capacity(64);
+    let mut encrypted = Vec::new();
+    for chunk in TEST.chunks(20) {
+        buf.put_slice(chunk);
+        let frame = transformer.update_bytes(&mut buf).unwrap();
+        assert_eq!(frame.len(), chunk.len());
+        assert!(buf.is_empty());
+        encrypted.extend_from_slice(&frame);
+    }
+
+    assert_eq!(encrypted, expected);
+}
@@ -0,0 +1,178 @@
+//! Tests for the detection of truncated ciphertext, chopping it at every possible offset.
+
+use super::TEST;
+use crate::padding::Padding;
+use crate::password::{Digest, EncKdf};
+use crate::Cipher;
+use crate::{read, write, Error};
+use std::io::{ErrorKind, Read, Write};
+
+const PASSWORD: &[u8] = b"hunter2";
+const FIXTURE: &[u8] = include_bytes!("fixtures/enc_aes256cbc_md5.bin");
+const HEADER_LEN: usize = 16;
+
+fn read_to_end<R: Read>(mut reader: R) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// Whether the fixture truncated to `len` bytes ends within its header or partway through a block.
+fn mid_block(len: usize) -> bool {
+    len < HEADER_LEN || !(len - HEADER_LEN).is_multiple_of(16)
+}
+
+#[test]
+fn block_cipher() {
+    let cipher = Cipher::aes_256_cbc();
+    let kdf = EncKdf::BytesToKey(Digest::Md5);
+
+    for len in 0..FIXTURE.len() {
+        let truncated = &FIXTURE[..len];
+        let result = read::Decryptor::with_openssl_enc(truncated, cipher, PASSWORD, kdf)
+            .and_then(read_to_end);
+
+        match len {
+            // Nothing but the header is indistinguishable from the encryption of empty plaintext.
+            HEADER_LEN => assert_eq!(result.unwrap(), b""),
+            // Within the header, or partway through a block.
+            _ if mid_block(len) => {
+                assert!(matches!(result, Err(Error::Truncated)), "{}", len)
+            }
+            // At a block boundary, which can only be detected by the padding of the final block.
+            _ => assert!(matches!(result, Err(Error::BadPadding)), "{}", len),
+        }
+
+        let mut decryptor = write::Decryptor::with_openssl_enc(Vec::new(), cipher, PASSWORD, kdf);
+        decryptor.write_all(truncated).unwrap();
+        let result = decryptor.finish();
+        match len {
+            HEADER_LEN => assert_eq!(result.unwrap(), b""),
+            _ if mid_block(len) => {
+                assert_eq!(result.unwrap_err().kind(), ErrorKind::UnexpectedEof)
+            }
+            _ => assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData),
+        }
+    }
+}
+
+#[test]
+fn block_cipher_without_padding() {
+    let cipher = Cipher::aes_128_cbc();
+    let (key, iv): ([u8; 16], [u8; 16]) = (rand::random(), rand::random());
+    let mut encryptor =
+        write::Encryptor::with_padding(Vec::new(), cipher, &key, &iv, Padding::None).unwrap();
+    encryptor.write_all(&TEST[..48]).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    for len in 1..encrypted.len() {
+        let decryptor =
+            read::Decryptor::with_padding(&encrypted[..len], cipher, &key, &iv, Padding::None)
+                .unwrap();
+        match read_to_end(decryptor) {
+            Ok(decrypted) => assert_eq!(decrypted, &TEST[..len]),
+            Err(err) => assert!(matches!(err, Error::Truncated) && !len.is_multiple_of(16)),
+        }
+    }
+}
+
+#[test]
+fn aead() {
+    let cipher = Cipher::aes_256_gcm();
+    let (key, iv): ([u8; 32], [u8; 12]) = (rand::random(), rand::random());
+    let mut encryptor = write::AeadEncryptor::new(Vec::new(), cipher, &key, &iv, b"").unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    for len in 0..encrypted.len() {
+        let decryptor =
+            read::AeadDecryptor::new(&encrypted[..len], cipher, &key, &iv, b"").unwrap();
+        let err = read_to_end(decryptor).unwrap_err();
+        match len < 16 {
+            // Not even the tag made it.
+            true => assert!(matches!(err, Error::Truncated), "{}", len),
+            false => assert!(matches!(err, Error::AuthenticationFailed), "{}", len),
+        }
+
+        let mut decryptor = write::AeadDecryptor::new(Vec::new(), cipher, &key, &iv, b"").unwrap();
+        decryptor.write_all(&encrypted[..len]).unwrap();
+        let err = decryptor.finish().unwrap_err();
+        match len < 16 {
+            true => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+            false => assert_eq!(err.kind(), ErrorKind::InvalidData),
+        }
+    }
+}
+
+#[test]
+fn etm() {
+    let cipher = Cipher::aes_256_ctr();
+    let (key, mac_key, iv): ([u8; 32], [u8; 32], [u8; 16]) =
+        (rand::random(), rand::random(), rand::random());
+    let mut encryptor = write::EtmEncryptor::new(Vec::new(), cipher, &key, &mac_key, &iv).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    for len in 0..encrypted.len() {
+        let decryptor =
+            read::EtmDecryptor::new(&encrypted[..len], cipher, &key, &mac_key, &iv).unwrap();
+        let err = read_to_end(decryptor).unwrap_err();
+        match len < 32 {
+            true => assert!(matches!(err, Error::Truncated), "{}", len),
+            false => assert!(matches!(err, Error::AuthenticationFailed), "{}", len),
+        }
+    }
+}
+
+#[test]
+fn segmented() {
+    const SEGMENT_SIZE: usize = 16;
+    const SEGMENT_LEN: usize = SEGMENT_SIZE + 16;
+
+    let cipher = Cipher::aes_128_gcm();
+    let (key, nonce_prefix): ([u8; 16], [u8; 7]) = (rand::random(), rand::random());
+    let mut encryptor = write::SegmentedEncryptor::with_segment_size(
+        Vec::new(),
+        cipher,
+        &key,
+        &nonce_prefix,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    for len in 0..encrypted.len() {
+        let decryptor = read::SegmentedDecryptor::with_segment_size(
+            &encrypted[..len],
+            cipher,
+            &key,
+            &nonce_prefix,
+            SEGMENT_SIZE,
+        )
+        .unwrap();
+        let err = read_to_end(decryptor).unwrap_err();
+        match len % SEGMENT_LEN < 16 {
+            // Cut off at a segment boundary, or before the end of the last segment's tag.
+            true => assert!(matches!(err, Error::Truncated), "{}", len),
+            false => assert!(matches!(err, Error::AuthenticationFailed), "{}", len),
+        }
+
+        let mut decryptor = write::SegmentedDecryptor::with_segment_size(
+            Vec::new(),
+            cipher,
+            &key,
+            &nonce_prefix,
+            SEGMENT_SIZE,
+        )
+        .unwrap();
+        let err = decryptor
+            .write_all(&encrypted[..len])
+            .and_then(|_| decryptor.finish().map(|_| ()))
+            .unwrap_err();
+        match len % SEGMENT_LEN < 16 {
+            true => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+            false => assert_eq!(err.kind(), ErrorKind::InvalidData),
+        }
+    }
+}
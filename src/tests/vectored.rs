@@ -0,0 +1,157 @@
+//! Tests for the vectored `read_vectored()` and `write_vectored()` implementations.
+
+use super::symm::encrypt;
+use super::TEST;
+use crate::Cipher;
+use crate::{bufread, read, write};
+use std::io::{IoSlice, IoSliceMut, Read, Write};
+
+/// A `Write` destination counting the number of writes made to it.
+#[derive(Default)]
+struct CountingWriter {
+    written: Vec<u8>,
+    writes: usize,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writes += 1;
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_vectored() {
+    let header = b"HEADER\n";
+    let mut message = header.to_vec();
+    message.extend_from_slice(TEST);
+
+    for &cipher in &[Cipher::aes_128_cbc(), Cipher::aes_128_ctr()] {
+        let (key, iv): ([u8; 16], [u8; 16]) = (rand::random(), rand::random());
+        let expected = encrypt(cipher, &key, Some(&iv), &message).unwrap();
+
+        let mut encryptor =
+            write::Encryptor::new(CountingWriter::default(), cipher, &key, &iv).unwrap();
+        assert!(encryptor.is_write_vectored());
+        let bufs = [IoSlice::new(header), IoSlice::new(&[]), IoSlice::new(TEST)];
+        assert_eq!(encryptor.write_vectored(&bufs).unwrap(), message.len());
+        let writer = encryptor.finish().unwrap();
+        assert_eq!(writer.written, expected);
+        // Once for the slices, and (for CBC) once more for the final padded block.
+        assert_eq!(writer.writes, cipher.block_size().min(2));
+
+        let mut decryptor =
+            write::Decryptor::new(CountingWriter::default(), cipher, &key, &iv).unwrap();
+        assert!(decryptor.is_write_vectored());
+        let (first, second) = expected.split_at(5);
+        let bufs = [IoSlice::new(first), IoSlice::new(second)];
+        assert_eq!(decryptor.write_vectored(&bufs).unwrap(), expected.len());
+        assert_eq!(decryptor.finish().unwrap().written, message);
+    }
+}
+
+#[test]
+fn write_vectored_beyond_capacity() {
+    let cipher = Cipher::aes_128_cbc();
+    let (key, iv): ([u8; 16], [u8; 16]) = (rand::random(), rand::random());
+    let plaintext = vec![0x17u8; 300];
+
+    let mut encryptor =
+        write::Encryptor::with_capacity(128, CountingWriter::default(), cipher, &key, &iv).unwrap();
+    let bufs: Vec<IoSlice> = plaintext.chunks(50).map(IoSlice::new).collect();
+    // Everything is consumed, written a buffer at a time.
+    assert_eq!(encryptor.write_vectored(&bufs).unwrap(), plaintext.len());
+    let writer = encryptor.finish().unwrap();
+    assert!(writer.writes > 2);
+    assert_eq!(
+        writer.written,
+        encrypt(cipher, &key, Some(&iv), &plaintext).unwrap()
+    );
+}
+
+#[test]
+fn write_vectored_aead() {
+    let cipher = Cipher::aes_256_gcm();
+    let (key, iv): ([u8; 32], [u8; 12]) = (rand::random(), rand::random());
+    let bufs: Vec<IoSlice> = TEST.chunks(10).map(IoSlice::new).collect();
+
+    // Every slice is consumed, not just the first.
+    let mut encryptor = write::AeadEncryptor::new(Vec::new(), cipher, &key, &iv, b"aad").unwrap();
+    assert_eq!(encryptor.write_vectored(&bufs).unwrap(), TEST.len());
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut encryptor = write::AeadEncryptor::new(Vec::new(), cipher, &key, &iv, b"aad").unwrap();
+    encryptor.write_all(TEST).unwrap();
+    assert_eq!(encrypted, encryptor.finish().unwrap());
+
+    let mut decryptor = write::AeadDecryptor::new(Vec::new(), cipher, &key, &iv, b"aad").unwrap();
+    let bufs: Vec<IoSlice> = encrypted.chunks(7).map(IoSlice::new).collect();
+    assert_eq!(decryptor.write_vectored(&bufs).unwrap(), encrypted.len());
+    assert_eq!(decryptor.finish().unwrap(), TEST);
+}
+
+#[test]
+fn write_vectored_segmented() {
+    let cipher = Cipher::chacha20_poly1305();
+    let (key, nonce_prefix): ([u8; 32], [u8; 7]) = (rand::random(), rand::random());
+    // Slices both shorter and longer than a segment, spanning segment boundaries.
+    let bufs: Vec<IoSlice> = [&TEST[..5], &[], &TEST[5..30], &TEST[30..]]
+        .iter()
+        .map(|buf| IoSlice::new(buf))
+        .collect();
+
+    let mut encryptor =
+        write::SegmentedEncryptor::with_segment_size(Vec::new(), cipher, &key, &nonce_prefix, 16)
+            .unwrap();
+    assert_eq!(encryptor.write_vectored(&bufs).unwrap(), TEST.len());
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut encryptor =
+        write::SegmentedEncryptor::with_segment_size(Vec::new(), cipher, &key, &nonce_prefix, 16)
+            .unwrap();
+    encryptor.write_all(TEST).unwrap();
+    assert_eq!(encrypted, encryptor.finish().unwrap());
+
+    let mut decryptor =
+        write::SegmentedDecryptor::with_segment_size(Vec::new(), cipher, &key, &nonce_prefix, 16)
+            .unwrap();
+    let bufs: Vec<IoSlice> = encrypted.chunks(11).map(IoSlice::new).collect();
+    assert_eq!(decryptor.write_vectored(&bufs).unwrap(), encrypted.len());
+    assert_eq!(decryptor.finish().unwrap(), TEST);
+}
+
+#[test]
+fn read_vectored() {
+    for &cipher in &[Cipher::aes_256_cbc(), Cipher::aes_256_ctr()] {
+        let (key, iv): ([u8; 32], [u8; 16]) = (rand::random(), rand::random());
+        let encrypted = encrypt(cipher, &key, Some(&iv), TEST).unwrap();
+
+        let mut decryptor = read::Decryptor::new(&encrypted[..], cipher, &key, &iv).unwrap();
+        let (mut a, mut b, mut c) = ([0u8; 10], [0u8; 0], [0u8; 100]);
+        let mut bufs = [
+            IoSliceMut::new(&mut a),
+            IoSliceMut::new(&mut b),
+            IoSliceMut::new(&mut c),
+        ];
+        // Everything but the block retained for unpadding (in CBC mode) is decrypted at once.
+        let read = decryptor.read_vectored(&mut bufs).unwrap();
+        assert_eq!(read, TEST.len() - TEST.len() % cipher.block_size());
+        let mut rest = Vec::new();
+        decryptor.read_to_end(&mut rest).unwrap();
+        assert_eq!([&a[..], &c[..read - 10], &rest[..]].concat(), TEST);
+
+        // Whatever doesn't fit is returned by the next read.
+        let mut encryptor = bufread::Encryptor::new(TEST, cipher, &key, &iv).unwrap();
+        let (mut a, mut b) = ([0u8; 3], [0u8; 4]);
+        let mut bufs = [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)];
+        assert_eq!(encryptor.read_vectored(&mut bufs).unwrap(), 7);
+        let mut rest = Vec::new();
+        encryptor.read_to_end(&mut rest).unwrap();
+        assert_eq!([&a[..], &b[..], &rest[..]].concat(), encrypted);
+    }
+}
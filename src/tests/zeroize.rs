@@ -0,0 +1,168 @@
+//! Tests for the `zeroize` feature, which wipes keys and internal buffers once they're dropped.
+
+use super::TEST;
+use crate::password::Kdf;
+use crate::secret::{BufReader, Secret, Wipe};
+use crate::Cipher;
+use crate::{bufread, read, write, Zeroizing};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::io::{BufRead, Cursor, Read, Seek, SeekFrom, Write};
+
+/// An allocator checking the allocations freed by a thread while it is `WATCHING` for any that
+/// still hold the `MARKER`, i.e. plaintext that was never wiped.
+struct Watcher;
+
+const MARKER: &[u8] = b"plaintext that must be wiped";
+
+thread_local! {
+    static WATCHING: Cell<bool> = const { Cell::new(false) };
+    static LEFT_BEHIND: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Watcher {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // Thread locals may already be gone when a thread frees its last allocations.
+        if WATCHING.try_with(Cell::get).unwrap_or(false) {
+            let freed = std::slice::from_raw_parts(ptr, layout.size());
+            if freed.windows(MARKER.len()).any(|w| w == MARKER) {
+                LEFT_BEHIND.with(|n| n.set(n.get() + 1));
+            }
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static WATCHER: Watcher = Watcher;
+
+/// Runs `f`, returning the number of allocations it freed that still held the `MARKER`.
+fn left_behind(f: impl FnOnce()) -> usize {
+    LEFT_BEHIND.with(|n| n.set(0));
+    WATCHING.with(|w| w.set(true));
+    f();
+    WATCHING.with(|w| w.set(false));
+    LEFT_BEHIND.with(Cell::get)
+}
+
+#[test]
+fn wipe() {
+    let mut block = [0x17u8; 16];
+    block.wipe();
+    assert_eq!(block, [0u8; 16]);
+
+    // Vectors are left empty, having had their spare capacity wiped as well.
+    let mut buffer = Secret::new(vec![0x17u8; 100]);
+    buffer.truncate(10);
+    buffer.wipe();
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn zeroizing_keys() {
+    let cipher = Cipher::aes_256_ctr();
+    let key = Zeroizing::new([0x42u8; 32]);
+    let iv: [u8; 16] = rand::random();
+
+    let mut encryptor = write::Encryptor::new(Vec::new(), cipher, &key[..], &iv).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    let key = Zeroizing::new(key.to_vec());
+    let mut decryptor = read::Decryptor::new(Cursor::new(encrypted), cipher, &key, &iv).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, TEST);
+
+    // The wiped buffer of `read` cryptostreams seeks just as `std::io::BufReader` does.
+    decryptor.seek(SeekFrom::Start(7)).unwrap();
+    let mut buf = [0u8; 5];
+    decryptor.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, TEST[7..12]);
+}
+
+#[test]
+fn password() {
+    let cipher = Cipher::aes_128_cbc();
+    let password = Zeroizing::new(b"hunter2".to_vec());
+    let kdf = Kdf::Pbkdf2 { iterations: 1000 };
+
+    let mut encryptor =
+        write::Encryptor::with_password(Vec::new(), cipher, &password, kdf).unwrap();
+    encryptor.write_all(TEST).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    let mut decryptor = write::Decryptor::with_password(Vec::new(), cipher, &password);
+    decryptor.write_all(&encrypted).unwrap();
+    assert_eq!(decryptor.finish().unwrap(), TEST);
+
+    let mut decryptor =
+        bufread::Decryptor::with_password(&encrypted[..], cipher, &password).unwrap();
+    let mut decrypted = Vec::new();
+    decryptor.read_to_end(&mut decrypted).unwrap();
+    assert_eq!(decrypted, TEST);
+}
+
+#[test]
+fn buf_reader() {
+    let data: Vec<u8> = (0..100).collect();
+    let mut reader = BufReader::with_capacity(7, Cursor::new(&data));
+
+    let mut buf = [0u8; 3];
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [0, 1, 2]);
+    assert_eq!(reader.fill_buf().unwrap(), &data[3..7]);
+
+    // Relative seeks are relative to what has been consumed, not what has been buffered.
+    assert_eq!(reader.seek(SeekFrom::Current(2)).unwrap(), 5);
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [5, 6, 7]);
+
+    // Reads larger than the buffer bypass it.
+    assert_eq!(reader.seek(SeekFrom::Start(50)).unwrap(), 50);
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, &data[50..]);
+    assert_eq!(reader.into_inner().position(), 100);
+}
+
+#[test]
+fn buffered_etm_growth() {
+    let cipher = Cipher::aes_256_ctr();
+    let (key, mac_key, iv): ([u8; 32], [u8; 32], [u8; 16]) =
+        (rand::random(), rand::random(), rand::random());
+    // Enough plaintext for the buffers holding it back to be grown several times over.
+    let plaintext = MARKER.repeat(10_000);
+
+    let mut encryptor = write::EtmEncryptor::new(Vec::new(), cipher, &key, &mac_key, &iv).unwrap();
+    encryptor.write_all(&plaintext).unwrap();
+    let encrypted = encryptor.finish().unwrap();
+
+    // The decrypted plaintext is written into buffers allocated up front, which are only freed
+    // once no longer watched.
+    let mut decrypted = vec![0u8; plaintext.len()];
+    let leaks = left_behind(|| {
+        let mut decryptor =
+            read::EtmDecryptor::buffered(&encrypted[..], cipher, &key, &mac_key, &iv).unwrap();
+        decryptor.read_exact(&mut decrypted).unwrap();
+        assert_eq!(decryptor.read(&mut [0u8; 1]).unwrap(), 0);
+    });
+    assert_eq!(decrypted, plaintext);
+    assert_eq!(leaks, 0, "read::EtmDecryptor left plaintext behind");
+
+    let mut output = Vec::with_capacity(plaintext.len());
+    let leaks = left_behind(|| {
+        let mut decryptor =
+            write::EtmDecryptor::buffered(&mut output, cipher, &key, &mac_key, &iv).unwrap();
+        for chunk in encrypted.chunks(1000) {
+            decryptor.write_all(chunk).unwrap();
+        }
+        decryptor.finish().unwrap();
+    });
+    assert_eq!(output, plaintext);
+    assert_eq!(leaks, 0, "write::EtmDecryptor left plaintext behind");
+}
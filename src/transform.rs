@@ -0,0 +1,184 @@
+//! A low-level interface for transforming data in place with a keystream cipher.
+//!
+//! The cryptostreams in the other modules copy their input through internal buffers on its way to
+//! the wrapped stream. Where that copy is unwelcome (e.g. in a proxy that already owns the buffers
+//! it receives data into) a [`Transformer`] can be used to encrypt or decrypt those buffers
+//! directly instead. It is keyed exactly as the cryptostreams are, and is limited to the keystream
+//! ciphers (CTR, CFB, OFB, ChaCha20, ...) that never need to retain or pad their input, and whose
+//! output is therefore always exactly as long as their input.
+//!
+//! With the `bytes` feature enabled, [`Transformer::update_bytes()`] transforms the contents of a
+//! `bytes::BytesMut` and splits them off as a frozen `Bytes` ready to be sent on.
+//!
+//! ```
+//! use cryptostream::transform::Transformer;
+//! use cryptostream::{Cipher, Mode};
+//!
+//! let cipher = Cipher::aes_128_ctr();
+//! let key = [0x42u8; 16];
+//! let iv = [0x24u8; 16];
+//!
+//! let mut data = *b"attack at dawn";
+//! let mut encryptor = Transformer::new(Mode::Encrypt, cipher, &key, &iv)?;
+//! encryptor.update_in_place(&mut data[..6])?;
+//! encryptor.finish_in_place(&mut data[6..])?;
+//! assert_ne!(&data, b"attack at dawn");
+//!
+//! Transformer::new(Mode::Decrypt, cipher, &key, &iv)?.finish_in_place(&mut data)?;
+//! assert_eq!(&data, b"attack at dawn");
+//! # Ok::<(), cryptostream::Error>(())
+//! ```
+
+use crate::aead;
+use crate::backend::StreamCipher;
+use crate::cipher::{Cipher, Mode};
+use crate::ctr::Counter;
+use crate::padding::{self, Padding};
+use crate::Error;
+#[cfg(feature = "bytes")]
+use bytes::{Bytes, BytesMut};
+use std::ops::{Deref, DerefMut};
+
+/// Encrypts or decrypts data in place with a keystream cipher.
+///
+/// Unlike the cryptostreams, a `Transformer` is not attached to a stream: each call to
+/// [`update_in_place()`](Self::update_in_place) transforms the next bytes of the ciphertext or
+/// plaintext in the buffer it is given, picking up where the previous call left off.
+pub struct Transformer {
+    crypter: Box<dyn StreamCipher>,
+    /// Only set for CTR mode ciphers, which are the only ones that support seeking.
+    counter: Option<Counter>,
+    /// The number of bytes transformed so far, or the position seeked to.
+    position: u64,
+}
+
+impl Transformer {
+    /// Creates a `Transformer` that encrypts or decrypts (per `mode`) with `cipher`.
+    ///
+    /// Returns [`Error::UnsupportedCipher`] if `cipher` is not a keystream cipher (one with a
+    /// block size of one byte), or is an AEAD cipher, whose tag can only be handled by the
+    /// `AeadEncryptor` and `AeadDecryptor` cryptostreams.
+    pub fn new(mode: Mode, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, Error> {
+        if cipher.block_size() != 1 || aead::is_aead(cipher) {
+            return Err(Error::UnsupportedCipher);
+        }
+
+        Self::keystream(cipher, mode, key, iv)
+    }
+
+    /// Creates a `Transformer` for any cipher with a block size of one byte, including the AEAD
+    /// ciphers (used without their tag) that the unauthenticated cryptostreams accept.
+    fn keystream(cipher: Cipher, mode: Mode, key: &[u8], iv: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            crypter: padding::new_cipher(cipher, mode, key, iv, Padding::None)?,
+            counter: Counter::new(cipher, mode, key, iv),
+            position: 0,
+        })
+    }
+
+    /// Encrypts or decrypts `data` in place.
+    pub fn update_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.crypter.update_in_place(data)?;
+        self.position += data.len() as u64;
+        Ok(())
+    }
+
+    /// Encrypts or decrypts the final `data` of the stream in place, consuming the `Transformer`.
+    ///
+    /// As keystream ciphers have nothing to pad or retain, this is equivalent to a last call to
+    /// [`update_in_place()`](Self::update_in_place), and `data` may be empty.
+    pub fn finish_in_place(mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.update_in_place(data)?;
+
+        let mut output = [0u8; 32];
+        let written = self.crypter.finalize(&mut output)?;
+        debug_assert_eq!(written, 0);
+        Ok(())
+    }
+
+    /// Encrypts or decrypts the contents of `buf` in place, then splits them off into the
+    /// returned `Bytes`, leaving `buf` empty (but with its remaining capacity) to be reused.
+    #[cfg(feature = "bytes")]
+    pub fn update_bytes(&mut self, buf: &mut BytesMut) -> Result<Bytes, Error> {
+        self.update_in_place(buf)?;
+        Ok(buf.split().freeze())
+    }
+
+    /// The number of bytes transformed so far, or the position last seeked to.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Repositions a CTR mode `Transformer` to pick up at `position` bytes into the stream.
+    ///
+    /// Returns [`Error::UnsupportedCipher`] for any other cipher, as only CTR mode allows the
+    /// keystream to be computed from an arbitrary position.
+    pub fn seek(&mut self, position: u64) -> Result<(), Error> {
+        let counter = self.counter.as_ref().ok_or(Error::UnsupportedCipher)?;
+        self.crypter = counter.crypter_at(position)?;
+        self.position = position;
+        Ok(())
+    }
+}
+
+/// The cipher behind a cryptostream: a [`Transformer`] for the keystream ciphers, whose input is
+/// transformed in place, or any other cipher, which transforms its input into a separate buffer.
+pub(crate) enum Crypter {
+    InPlace(Transformer),
+    Buffered(Box<dyn StreamCipher>),
+}
+
+impl Crypter {
+    /// Creates the cipher of an unauthenticated cryptostream, in place if `cipher` is a keystream
+    /// cipher (with a block size of one byte), as those are never padded.
+    pub fn new(
+        cipher: Cipher,
+        mode: Mode,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, Error> {
+        if cipher.block_size() == 1 {
+            return Ok(Crypter::InPlace(Transformer::keystream(
+                cipher, mode, key, iv,
+            )?));
+        }
+        Ok(Crypter::Buffered(padding::new_cipher(
+            cipher, mode, key, iv, padding,
+        )?))
+    }
+
+    /// Whether the cipher can be repositioned with [`seek()`](Self::seek).
+    pub fn is_seekable(&self) -> bool {
+        matches!(self, Crypter::InPlace(transformer) if transformer.counter.is_some())
+    }
+
+    /// Repositions the cipher to pick up at `position` bytes into the stream, as
+    /// [`Transformer::seek()`] does.
+    pub fn seek(&mut self, position: u64) -> Result<(), Error> {
+        match self {
+            Crypter::InPlace(transformer) => transformer.seek(position),
+            Crypter::Buffered(_) => Err(Error::UnsupportedCipher),
+        }
+    }
+}
+
+impl Deref for Crypter {
+    type Target = dyn StreamCipher;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Crypter::InPlace(transformer) => &*transformer.crypter,
+            Crypter::Buffered(crypter) => &**crypter,
+        }
+    }
+}
+
+impl DerefMut for Crypter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Crypter::InPlace(transformer) => &mut *transformer.crypter,
+            Crypter::Buffered(crypter) => &mut **crypter,
+        }
+    }
+}
@@ -5,15 +5,104 @@
 //! underlying `Write` stream, or use [`write::Decryptor`] to do the opposite and have decrypted
 //! plaintext written to the wrapped `Write` output each time encrypted bytes are written to the
 //! instance.
+//!
+//! A single `write()` to any of the cryptostreams in this module always consumes the entire input,
+//! transforming it a buffer at a time, so there are no short writes to account for when calling
+//! `write()` rather than `write_all()`. Should an error be returned partway through, the
+//! cryptostream is left in an unknown state and any further writes fail.
+//!
+//! Each cryptostream must be finished once all input has been written to it, which pads (or
+//! authenticates) the final block and writes it out. Prefer doing so explicitly with `finish()` (or
+//! `try_finish()`, which leaves the cryptostream in place) so that any error is reported; a
+//! cryptostream dropped without being finished is finished on drop, where an error can't be
+//! returned. [`is_finished()`](Encryptor::is_finished) reports whether a cryptostream has been
+//! finished successfully, and a [`DropGuard`] set with
+//! [`set_drop_guard()`](Encryptor::set_drop_guard) detects one that is dropped without being
+//! finished, by panicking or by flagging a shared [`Poison`] if the output is left incomplete.
+
+use crate::aead::{self, Tag, Trailer, MAX_TAG_LEN, TAG_LEN};
+use crate::backend::{self, StreamCipher};
+use crate::cipher::{Cipher, Mode};
+use crate::commit;
+use crate::etm::{self, MAC_LEN};
+use crate::header::Header;
+use crate::padding::Padding;
+use crate::password::{EncKdf, Format, Kdf, KdfHeader, KdfLimits, MAX_HEADER_LEN};
+use crate::secret::Secret;
+use crate::secretstream::{Chunker, DEFAULT_CHUNK_SIZE};
+use crate::segment::{Segmenter, Segments, DEFAULT_SEGMENT_SIZE, NONCE_PREFIX_LEN};
+use crate::transform::{Crypter, Transformer};
+use std::io::{Error, ErrorKind, IoSlice, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The default capacity of the buffer input is transformed into before being written to the
+/// underlying stream, which bounds how much input a single `write()` consumes.
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// The smallest capacity that leaves room for a block of input alongside a block of output held
+/// back by the cipher, given OpenSSL's `EVP_MAX_BLOCK_LENGTH` of 32 bytes.
+const MIN_CAPACITY: usize = 64;
+
+/// What a cryptostream does if it is dropped without having been finished, as set with
+/// `set_drop_guard()`.
+#[derive(Clone, Debug, Default)]
+pub enum DropGuard {
+    /// Finish the cryptostream, ignoring any error. This is the default.
+    #[default]
+    Finish,
+    /// Panic (unless the thread is already panicking) without finishing the cryptostream, for
+    /// catching cryptostreams that are never finished explicitly.
+    Panic,
+    /// Finish the cryptostream, flagging the [`Poison`] if that fails or the cryptostream had
+    /// already been left unusable by an earlier error, so that its output is incomplete.
+    Poison(Poison),
+}
+
+impl DropGuard {
+    /// Guards a cryptostream being dropped which has not been `finished`, with `finish` finishing
+    /// it and returning whether it did so without error.
+    fn guard(&self, finished: bool, finish: impl FnOnce() -> bool) {
+        if finished {
+            return;
+        }
+
+        match self {
+            DropGuard::Finish => {
+                finish();
+            }
+            DropGuard::Panic => {
+                if !std::thread::panicking() {
+                    panic!("A cryptostream was dropped without being finished!");
+                }
+            }
+            DropGuard::Poison(poison) => {
+                if !finish() {
+                    poison.0.store(true, Ordering::Release);
+                }
+            }
+        }
+    }
+}
 
-use openssl::error::ErrorStack;
-use openssl::symm::{Cipher, Crypter, Mode};
-use std::io::{Error, ErrorKind, Write};
+/// A flag shared with the cryptostreams guarded by [`DropGuard::Poison`], which outlives them.
+#[derive(Clone, Debug, Default)]
+pub struct Poison(Arc<AtomicBool>);
 
-const BUFFER_SIZE: usize = 4096;
+impl Poison {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a cryptostream guarded by this `Poison` was dropped without being finished and
+    /// couldn't be finished on drop either, leaving its output incomplete.
+    pub fn is_poisoned(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
 
 struct Cryptostream<W: Write> {
-    buffer: [u8; BUFFER_SIZE],
+    buffer: Secret<Box<[u8]>>,
     /// This `Option` is guaranteed to always be `Some` up until the point
     /// [`to_inner()`](self::to_inner) is called, which is the only place `None` is swapped in. As
     /// that call consumes the `Cryptostream` object, we can safely assume that `writer.unwrap()`
@@ -21,8 +110,14 @@ struct Cryptostream<W: Write> {
     writer: Option<W>,
     cipher: Cipher,
     crypter: Crypter,
+    iv: Vec<u8>,
     finalized: bool,
     never_used: bool,
+    /// Set by any error writing to or finishing the cryptostream, which can't be used any further.
+    poisoned: bool,
+    drop_guard: DropGuard,
+    /// Only set for the authenticated (AEAD) variants.
+    tag: Option<Tag>,
 }
 
 impl<W: Write> Cryptostream<W> {
@@ -32,46 +127,219 @@ impl<W: Write> Cryptostream<W> {
         cipher: Cipher,
         key: &[u8],
         iv: &[u8],
-    ) -> Result<Self, ErrorStack> {
-        let mut crypter = Crypter::new(cipher, mode, key, Some(iv))?;
-        crypter.pad(true);
+    ) -> Result<Self, crate::Error> {
+        Self::new_padded(mode, writer, cipher, key, iv, Padding::default())
+    }
+
+    pub fn new_padded(
+        mode: Mode,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, crate::Error> {
+        Self::new_buffered(mode, writer, cipher, key, iv, padding, DEFAULT_CAPACITY)
+    }
+
+    /// Creates a cryptostream transforming up to `capacity` bytes at a time.
+    pub fn new_buffered(
+        mode: Mode,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+        capacity: usize,
+    ) -> Result<Self, crate::Error> {
+        let crypter = Crypter::new(cipher, mode, key, iv, padding)?;
 
         Ok(Self {
-            buffer: [0u8; BUFFER_SIZE],
+            buffer: new_buffer(capacity),
             writer: Some(writer),
             never_used: true,
-            cipher: cipher.clone(),
-            crypter: crypter,
+            cipher,
+            crypter,
+            iv: iv.to_vec(),
             finalized: false,
+            poisoned: false,
+            drop_guard: DropGuard::default(),
+            tag: None,
         })
     }
 
-    /// Function shared by Drop and finish()
+    pub fn new_aead(
+        mode: Mode,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Self::new_aead_buffered(mode, writer, cipher, key, iv, aad, DEFAULT_CAPACITY)
+    }
+
+    /// Creates an AEAD cryptostream transforming up to `capacity` bytes at a time.
+    pub fn new_aead_buffered(
+        mode: Mode,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        capacity: usize,
+    ) -> Result<Self, crate::Error> {
+        let crypter = aead::new_crypter(mode, cipher, key, iv, aad)?;
+        Ok(Self::authenticated(
+            mode, writer, cipher, crypter, iv, TAG_LEN, capacity,
+        ))
+    }
+
+    pub fn new_etm(
+        mode: Mode,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let crypter = etm::new_crypter(mode, cipher, key, mac_key, iv)?;
+        Ok(Self::authenticated(
+            mode,
+            writer,
+            cipher,
+            crypter,
+            iv,
+            MAC_LEN,
+            DEFAULT_CAPACITY,
+        ))
+    }
+
+    /// Shared by the authenticated variants, which append a tag of `tag_len` bytes to the
+    /// ciphertext, transforming up to `capacity` bytes at a time.
+    fn authenticated(
+        mode: Mode,
+        writer: W,
+        cipher: Cipher,
+        crypter: Box<dyn StreamCipher>,
+        iv: &[u8],
+        tag_len: usize,
+        capacity: usize,
+    ) -> Self {
+        let tag = match mode {
+            Mode::Encrypt => Tag::Append(tag_len),
+            Mode::Decrypt => Tag::Verify(Trailer::new(tag_len)),
+        };
+
+        Self {
+            buffer: new_buffer(capacity),
+            writer: Some(writer),
+            never_used: true,
+            cipher,
+            crypter: Crypter::Buffered(crypter),
+            iv: iv.to_vec(),
+            finalized: false,
+            poisoned: false,
+            drop_guard: DropGuard::default(),
+            tag: Some(tag),
+        }
+    }
+
+    /// Transforms as much of `buf` as fits in the buffer, returning the number of bytes consumed.
+    fn write_chunk(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        // Crypter::update() requires `output.len() >= input.len() + block_size`
+        let block_size = self.cipher.block_size();
+        let max_read = std::cmp::min(self.buffer.len() - block_size, buf.len());
+
+        if max_read > 0 {
+            let Self {
+                buffer,
+                writer,
+                crypter,
+                tag,
+                ..
+            } = self;
+            let writer = writer.as_mut().unwrap();
+            let mut update = |input: &[u8]| {
+                let bytes_encrypted = crypter.update(input, buffer)?;
+                writer.write_all(&buffer[0..bytes_encrypted])
+            };
+
+            match tag {
+                // The tag is at the very end of the ciphertext, so we can only pass bytes through
+                // to the cipher once we know they aren't a part of it.
+                Some(Tag::Verify(trailer)) => trailer.write(&buf[0..max_read], update)?,
+                _ => update(&buf[0..max_read])?,
+            }
+
+            // Flag the crypter as having been used and needing finalizing
+            self.never_used = false;
+        }
+
+        // Regardless of how many bytes of encrypted ciphertext we wrote to the underlying stream
+        // (taking padding into consideration) we return how many bytes of *input* were processed,
+        // which can never be larger than the number of bytes passed in to us originally.
+        Ok(max_read)
+    }
+
+    /// Function shared by Drop, finish(), and try_finish()
     fn inner_finish(&mut self) -> Result<(), Error> {
+        if self.poisoned {
+            return Err(poisoned());
+        }
+        let result = self.finalize();
+        self.poison_on_error(result)
+    }
+
+    fn finalize(&mut self) -> Result<(), Error> {
         if !self.finalized {
             self.finalized = true;
 
-            // Crypter::finalize() will panic if Crypter::update() was never previously called.
-            if !self.never_used {
-                let mut buffer = [0u8; 16];
-                let bytes_written = self
-                    .crypter
-                    .finalize(&mut buffer)
-                    .map_err(|e| Error::new(ErrorKind::Other, e))?;
+            // Crypter::finalize() will panic if Crypter::update() was never previously called,
+            // but AEAD ciphers must always be finalized to authenticate even an empty stream.
+            if !self.never_used || self.tag.is_some() {
+                let mut buffer = Secret::new([0u8; 16]);
+                let bytes_written = match &self.tag {
+                    Some(Tag::Verify(trailer)) => {
+                        let tag = trailer.tag().ok_or_else(aead::truncated)?;
+                        self.crypter.set_tag(tag)?;
+                        self.crypter
+                            .finalize(&mut buffer[..])
+                            .map_err(|_| aead::authentication_failed())?
+                    }
+                    _ => self.crypter.finalize(&mut buffer[..])?,
+                };
 
-                self.writer
-                    .as_mut()
-                    .unwrap()
-                    .write(&buffer[0..bytes_written])?;
+                let writer = self.writer.as_mut().unwrap();
+                writer.write_all(&buffer[0..bytes_written])?;
+
+                if let Some(Tag::Append(len)) = self.tag {
+                    let mut tag = [0u8; MAX_TAG_LEN];
+                    self.crypter.get_tag(&mut tag[..len])?;
+                    writer.write_all(&tag[..len])?;
+                }
             }
         }
 
         self.flush()
     }
 
+    /// Whether the cryptostream has been finished without error.
+    pub fn is_finished(&self) -> bool {
+        self.finalized && !self.poisoned
+    }
+
+    /// Flags the cryptostream as unusable if `result` is an error.
+    fn poison_on_error<T>(&mut self, result: Result<T, Error>) -> Result<T, Error> {
+        self.poisoned |= result.is_err();
+        result
+    }
+
     /// Finishes writing to the underlying cryptostream, padding the final block as needed,
     /// flushing all output. Returns the wrapped `Write` instance.
     pub fn finish(mut self) -> Result<W, Error> {
+        // Any error is reported here, rather than by the drop guard.
+        self.drop_guard = DropGuard::default();
         self.inner_finish()?;
 
         // Return the original `W` instance. Since we implement `Drop`, we have to put something in
@@ -83,35 +351,117 @@ impl<W: Write> Cryptostream<W> {
     }
 }
 
-impl<W: Write> Write for Cryptostream<W> {
-    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+impl<W: Write> Cryptostream<W> {
+    /// Transforms all of `buf`, a buffer at a time, so that a single `write()` never consumes less
+    /// than the entire input (unless the cryptostream has already been finished).
+    fn transform(&mut self, buf: &[u8]) -> Result<usize, Error> {
         if self.finalized {
             return Ok(0);
         }
+        let Self {
+            buffer,
+            writer,
+            crypter,
+            ..
+        } = self;
+        if let Crypter::InPlace(transformer) = crypter {
+            return write_in_place(transformer, buffer, writer.as_mut().unwrap(), buf);
+        }
+
+        let mut consumed = 0;
+        while consumed < buf.len() {
+            consumed += self.write_chunk(&buf[consumed..])?;
+        }
+        Ok(consumed)
+    }
+
+    /// Transforms all of `bufs` in a single pass, writing the result to the underlying stream a
+    /// buffer at a time rather than a slice at a time.
+    fn transform_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        if self.finalized {
+            return Ok(0);
+        }
+        if self.tag.is_some() {
+            // The trailer withheld by the AEAD variants is fed a slice at a time, each of which is
+            // consumed in full.
+            let mut consumed = 0;
+            for buf in bufs {
+                consumed += self.transform(buf)?;
+            }
+            return Ok(consumed);
+        }
 
-        // Crypter::update() requires `output.len() >= input.len() + block_size`
         let block_size = self.cipher.block_size();
-        let max_read = std::cmp::min(BUFFER_SIZE - block_size, buf.len());
+        let Self {
+            buffer,
+            writer,
+            crypter,
+            ..
+        } = self;
+        let writer = writer.as_mut().unwrap();
+        let mut consumed = 0;
+        let mut len = 0;
 
-        if max_read > 0 {
-            let bytes_encrypted = self
-                .crypter
-                .update(&buf[0..max_read], &mut self.buffer)
-                .map_err(|e| Error::new(ErrorKind::Other, e))?;
+        if let Crypter::InPlace(transformer) = crypter {
+            // Gather the slices into the buffer, transforming it in place each time it fills up.
+            for buf in bufs {
+                let mut buf = &buf[..];
+                while !buf.is_empty() {
+                    let n = std::cmp::min(buf.len(), buffer.len() - len);
+                    buffer[len..][..n].copy_from_slice(&buf[..n]);
+                    buf = &buf[n..];
+                    len += n;
+                    consumed += n;
 
-            // Flag the crypter as having been used and needing finalizing
+                    if len == buffer.len() {
+                        transformer.update_in_place(buffer)?;
+                        writer.write_all(buffer)?;
+                        len = 0;
+                    }
+                }
+            }
+            transformer.update_in_place(&mut buffer[..len])?;
+        } else {
+            for buf in bufs {
+                let mut buf = &buf[..];
+                while !buf.is_empty() {
+                    // Crypter::update() requires `output.len() >= input.len() + block_size`
+                    if buffer.len() - len <= block_size {
+                        writer.write_all(&buffer[..len])?;
+                        len = 0;
+                    }
+                    let n = std::cmp::min(buf.len(), buffer.len() - len - block_size);
+                    len += crypter.update(&buf[..n], &mut buffer[len..])?;
+                    buf = &buf[n..];
+                    consumed += n;
+                }
+            }
+        }
+        writer.write_all(&buffer[..len])?;
+
+        // Flag the crypter as having been used and needing finalizing
+        if block_size > 1 && consumed > 0 {
             self.never_used = false;
+        }
+        Ok(consumed)
+    }
+}
 
-            self.writer
-                .as_mut()
-                .unwrap()
-                .write_all(&self.buffer[0..bytes_encrypted])?;
+impl<W: Write> Write for Cryptostream<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if self.poisoned {
+            return Err(poisoned());
         }
+        let result = self.transform(buf);
+        self.poison_on_error(result)
+    }
 
-        // Regardless of how many bytes of encrypted ciphertext we wrote to the underlying stream
-        // (taking padding into consideration) we return how many bytes of *input* were processed,
-        // which can never be larger than the number of bytes passed in to us originally.
-        Ok(max_read)
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        if self.poisoned {
+            return Err(poisoned());
+        }
+        let result = self.transform_vectored(bufs);
+        self.poison_on_error(result)
     }
 
     /// Flushes the underlying stream but does not clear all internal buffers or explicitly pad the
@@ -126,12 +476,55 @@ impl<W: Write> Write for Cryptostream<W> {
     }
 }
 
+/// Transforms all of `buf` with a keystream cipher, a `buffer` at a time, writing the result to
+/// `writer`. Such ciphers neither retain input nor pad the output, so the entire input is always
+/// consumed and the cipher never needs finalizing.
+fn write_in_place<W: Write>(
+    transformer: &mut Transformer,
+    buffer: &mut [u8],
+    writer: &mut W,
+    buf: &[u8],
+) -> Result<usize, Error> {
+    for chunk in buf.chunks(buffer.len()) {
+        let buffer = &mut buffer[..chunk.len()];
+        buffer.copy_from_slice(chunk);
+        transformer.update_in_place(buffer)?;
+        writer.write_all(buffer)?;
+    }
+
+    Ok(buf.len())
+}
+
+/// Allocates the buffer a cryptostream transforms its input into, of at least `MIN_CAPACITY` bytes.
+fn new_buffer(capacity: usize) -> Secret<Box<[u8]>> {
+    Secret::new(vec![0u8; std::cmp::max(capacity, MIN_CAPACITY)].into_boxed_slice())
+}
+
+/// The error returned by a cryptostream used after an earlier error.
+fn poisoned() -> Error {
+    Error::other("The cryptostream is no longer usable after an earlier error!")
+}
+
+/// Fails if the ciphertext ended before the key commitment at its start, flagging the
+/// cryptostream as unusable so that it isn't finished on drop either.
+fn check_committed(commitment: &Option<commit::Pending>, poisoned: &mut bool) -> Result<(), Error> {
+    if commitment.is_some() {
+        *poisoned = true;
+        return Err(aead::truncated());
+    }
+    Ok(())
+}
+
 impl<W: Write> Drop for Cryptostream<W> {
     /// Write all buffered output to the underlying stream, pad the final block if needed, and
-    /// flush everything.
+    /// flush everything, as permitted by the drop guard. Nothing is written once an error has left
+    /// the cryptostream unusable, and any error finishing it here can only be reported through the
+    /// drop guard.
     fn drop(&mut self) {
-        // We should never panic on Drop
-        let _r = self.inner_finish();
+        let drop_guard = std::mem::take(&mut self.drop_guard);
+        drop_guard.guard(self.is_finished(), || {
+            !self.poisoned && self.inner_finish().is_ok()
+        });
     }
 }
 
@@ -144,24 +537,168 @@ pub struct Encryptor<W: Write> {
 }
 
 impl<W: Write> Encryptor<W> {
-    pub fn new(writer: W, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, ErrorStack> {
+    pub fn new(writer: W, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
         Ok(Self {
             inner: Cryptostream::new(Mode::Encrypt, writer, cipher, key, iv)?,
         })
     }
 
+    /// Creates a new `Encryptor` which transforms up to `capacity` bytes (rather than 8 KiB) at a
+    /// time, bounding the size of each write to the underlying stream.
+    pub fn with_capacity(
+        capacity: usize,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_buffered(
+                Mode::Encrypt,
+                writer,
+                cipher,
+                key,
+                iv,
+                Padding::default(),
+                capacity,
+            )?,
+        })
+    }
+
+    /// Creates a new `Encryptor` which pads the plaintext with `padding` rather than PKCS#7. See
+    /// the [`padding`](crate::padding) module for details.
+    pub fn with_padding(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_padded(Mode::Encrypt, writer, cipher, key, iv, padding)?,
+        })
+    }
+
+    /// Creates a new `Encryptor` with a freshly generated random IV, which must be retrieved with
+    /// [`iv()`](Self::iv) and passed on to the decryptor.
+    pub fn with_random_iv(writer: W, cipher: Cipher, key: &[u8]) -> Result<Self, crate::Error> {
+        Self::new(writer, cipher, key, &backend::random_iv(cipher)?)
+    }
+
+    /// Creates a new `Encryptor` with a freshly generated random IV, which is written to `writer`
+    /// straight away to be read back by [`Decryptor::with_prepended_iv()`].
+    pub fn with_prepended_iv(
+        mut writer: W,
+        cipher: Cipher,
+        key: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let iv = backend::random_iv(cipher)?;
+        writer.write_all(&iv)?;
+        Self::new(writer, cipher, key, &iv)
+    }
+
+    /// Creates a new `Encryptor` which writes `header` to `writer` straight away, from which
+    /// [`read::Decryptor::auto()`](crate::read::Decryptor::auto) reads back the cipher and IV.
+    /// See the [`header`](crate::header) module for details.
+    pub fn with_header(mut writer: W, header: &Header, key: &[u8]) -> Result<Self, crate::Error> {
+        header.write(&mut writer)?;
+        Ok(Self {
+            inner: Cryptostream::new(Mode::Encrypt, writer, header.cipher(), key, header.iv())?,
+        })
+    }
+
+    /// Creates a new `Encryptor` keyed with `password`, from which the key and IV are derived
+    /// with `kdf` and a random salt. A header recording the KDF and salt is written to `writer`
+    /// straight away, to be read back by [`Decryptor::with_password()`]. See the
+    /// [`password`](crate::password) module for details.
+    pub fn with_password(
+        writer: W,
+        cipher: Cipher,
+        password: &[u8],
+        kdf: Kdf,
+    ) -> Result<Self, crate::Error> {
+        Self::with_kdf_header(writer, cipher, password, KdfHeader::generate(kdf)?)
+    }
+
+    /// Creates a new `Encryptor` producing the same output as `openssl enc` with a random salt,
+    /// keyed with `password` via `kdf`. The salt is written to `writer` straight away. See the
+    /// [`password`](crate::password) module for details.
+    pub fn with_openssl_enc(
+        writer: W,
+        cipher: Cipher,
+        password: &[u8],
+        kdf: EncKdf,
+    ) -> Result<Self, crate::Error> {
+        Self::with_kdf_header(
+            writer,
+            cipher,
+            password,
+            KdfHeader::generate_openssl_enc(kdf)?,
+        )
+    }
+
+    fn with_kdf_header(
+        mut writer: W,
+        cipher: Cipher,
+        password: &[u8],
+        header: KdfHeader,
+    ) -> Result<Self, crate::Error> {
+        let (key, iv) = header.derive(cipher, password)?;
+        writer.write_all(&header.to_bytes())?;
+
+        Ok(Self {
+            inner: Cryptostream::new(Mode::Encrypt, writer, cipher, &key, &iv)?,
+        })
+    }
+
+    /// The IV the stream is encrypted with.
+    pub fn iv(&self) -> &[u8] {
+        &self.inner.iv
+    }
+
     /// Finishes writing to the underlying cryptostream, padding the final block as needed,
     /// flushing all output. Returns the wrapped `Write` instance.
     pub fn finish(self) -> Result<W, Error> {
         self.inner.finish()
     }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        self.inner.inner_finish()
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+
+    /// Always true, as [`write_vectored()`](Write::write_vectored) encrypts several slices at
+    /// once. (`Write::is_write_vectored()` itself is not yet stable.)
+    pub fn is_write_vectored(&self) -> bool {
+        true
+    }
 }
 
 impl<W: Write> Write for Encryptor<W> {
     /// Writes decrypted bytes to the cryptostream, causing their encrypted contents to be written
     /// to the underlying `Write` object.
-    fn write(&mut self, mut buf: &[u8]) -> Result<usize, Error> {
-        self.inner.write(&mut buf)
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.inner.write(buf)
+    }
+
+    /// Encrypts as many of `bufs` as possible in a single pass, writing the ciphertext to the
+    /// underlying `Write` object in one go.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        self.inner.write_vectored(bufs)
     }
 
     /// Flushes the underlying stream but does not clear all internal buffers or explicitly pad the
@@ -177,33 +714,1380 @@ impl<W: Write> Write for Encryptor<W> {
 /// `write::Decryptor` is a stream adapter that sits atop a `Write` stream. Ciphertext written to
 /// the `Decryptor` is decrypted and written to the underlying stream.
 pub struct Decryptor<W: Write> {
-    inner: Cryptostream<W>,
+    inner: DecryptorState<W>,
+}
+
+/// A `Decryptor` created with a password or a prepended IV can't create its cipher until the
+/// header at the start of the ciphertext has been written to it. The size difference between the
+/// variants is of no concern as the header is only awaited once, at the start of the stream.
+#[allow(clippy::large_enum_variant)]
+enum DecryptorState<W: Write> {
+    AwaitingHeader(PendingHeader<W>),
+    Decrypting(Cryptostream<W>),
+}
+
+/// What precedes the ciphertext, determining its key and IV.
+enum HeaderKind {
+    Password {
+        password: Secret<Vec<u8>>,
+        format: Format,
+    },
+    Iv {
+        key: Secret<Vec<u8>>,
+    },
+}
+
+struct PendingHeader<W> {
+    /// Only `None` if creating the `Cryptostream` failed, leaving the `Decryptor` unusable.
+    writer: Option<W>,
+    cipher: Cipher,
+    kind: HeaderKind,
+    /// The header bytes written so far.
+    header: Secret<Vec<u8>>,
+    /// Handed on to the `Cryptostream` once it has been created.
+    drop_guard: DropGuard,
+}
+
+impl<W: Write> PendingHeader<W> {
+    /// Reads the header from `reader`, returning the key and IV. Fails with `UnexpectedEof` if the
+    /// header is incomplete.
+    fn key_and_iv(&self, reader: &mut &[u8]) -> Result<(Secret<Vec<u8>>, Vec<u8>), Error> {
+        match &self.kind {
+            HeaderKind::Password { password, format } => {
+                Ok(KdfHeader::read(&mut *reader, *format)?.derive(self.cipher, password)?)
+            }
+            HeaderKind::Iv { key } => {
+                let mut iv = vec![0u8; self.cipher.iv_len().unwrap_or(0)];
+                reader.read_exact(&mut iv)?;
+                Ok((Secret::new(key.to_vec()), iv))
+            }
+        }
+    }
+
+    /// Accumulates the header from `buf`, returning the number of bytes consumed and, once the
+    /// header is complete, the `Cryptostream` keyed with it.
+    fn write(&mut self, buf: &[u8]) -> Result<(usize, Option<Cryptostream<W>>), Error> {
+        let previous = self.header.len();
+        let len = std::cmp::min(buf.len(), MAX_HEADER_LEN - previous);
+        self.header.extend_from_slice(&buf[..len]);
+
+        let mut remaining = &self.header[..];
+        let (key, iv) = match self.key_and_iv(&mut remaining) {
+            Ok(key_and_iv) => key_and_iv,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok((len, None)),
+            Err(e) => return Err(e),
+        };
+        let consumed = self.header.len() - remaining.len() - previous;
+
+        let writer = self.writer.take().ok_or_else(|| {
+            Error::other("The cryptostream could not be created and is no longer usable!")
+        })?;
+        let mut inner = Cryptostream::new(Mode::Decrypt, writer, self.cipher, &key, &iv)?;
+        inner.drop_guard = std::mem::take(&mut self.drop_guard);
+
+        Ok((consumed, Some(inner)))
+    }
+}
+
+impl<W> Drop for PendingHeader<W> {
+    /// A `Decryptor` dropped before the end of its header has written nothing, which only the
+    /// drop guard can report.
+    fn drop(&mut self) {
+        self.drop_guard.guard(false, || false);
+    }
 }
 
 impl<W: Write> Decryptor<W> {
-    pub fn new(writer: W, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, ErrorStack> {
+    pub fn new(writer: W, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: DecryptorState::Decrypting(Cryptostream::new(
+                Mode::Decrypt,
+                writer,
+                cipher,
+                key,
+                iv,
+            )?),
+        })
+    }
+
+    /// Creates a new `Decryptor` which transforms up to `capacity` bytes (rather than 8 KiB) at a
+    /// time, bounding the size of each write to the underlying stream.
+    pub fn with_capacity(
+        capacity: usize,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: DecryptorState::Decrypting(Cryptostream::new_buffered(
+                Mode::Decrypt,
+                writer,
+                cipher,
+                key,
+                iv,
+                Padding::default(),
+                capacity,
+            )?),
+        })
+    }
+
+    /// Creates a new `Decryptor` which expects the plaintext to have been padded with `padding`
+    /// rather than PKCS#7. See the [`padding`](crate::padding) module for details.
+    pub fn with_padding(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        padding: Padding,
+    ) -> Result<Self, crate::Error> {
         Ok(Self {
-            inner: Cryptostream::new(Mode::Decrypt, writer, cipher, key, iv)?,
+            inner: DecryptorState::Decrypting(Cryptostream::new_padded(
+                Mode::Decrypt,
+                writer,
+                cipher,
+                key,
+                iv,
+                padding,
+            )?),
         })
     }
 
+    /// Creates a new `Decryptor` for ciphertext produced by an encryptor created with
+    /// `with_prepended_iv()`, taking the IV from the start of the ciphertext once it has been
+    /// written to the `Decryptor`.
+    pub fn with_prepended_iv(writer: W, cipher: Cipher, key: &[u8]) -> Self {
+        let key = Secret::new(key.to_vec());
+        Self::awaiting_header(writer, cipher, HeaderKind::Iv { key })
+    }
+
+    /// Creates a new `Decryptor` for ciphertext produced by an encryptor created with
+    /// `with_password()`. The key and IV are derived from `password` as recorded in the header at
+    /// the start of the ciphertext once it has been written to the `Decryptor`. Writing fails with
+    /// [`Error::InvalidKdfParameters`] if the recorded parameters exceed the default
+    /// [`KdfLimits`].
+    ///
+    /// [`Error::InvalidKdfParameters`]: crate::Error::InvalidKdfParameters
+    pub fn with_password(writer: W, cipher: Cipher, password: &[u8]) -> Self {
+        Self::with_password_and_limits(writer, cipher, password, KdfLimits::default())
+    }
+
+    /// Like [`with_password()`](Self::with_password), but accepting KDF parameters up to `limits`
+    /// rather than the defaults.
+    pub fn with_password_and_limits(
+        writer: W,
+        cipher: Cipher,
+        password: &[u8],
+        limits: KdfLimits,
+    ) -> Self {
+        Self::with_format(writer, cipher, password, Format::Native(limits))
+    }
+
+    /// Creates a new `Decryptor` for the output of `openssl enc`, deriving the key and IV from
+    /// `password` via `kdf`, which must match the options it was encrypted with, once the salt at
+    /// the start of the ciphertext has been written to it. See the [`password`](crate::password)
+    /// module for details.
+    pub fn with_openssl_enc(writer: W, cipher: Cipher, password: &[u8], kdf: EncKdf) -> Self {
+        Self::with_format(writer, cipher, password, Format::OpenSslEnc(kdf))
+    }
+
+    fn with_format(writer: W, cipher: Cipher, password: &[u8], format: Format) -> Self {
+        let password = Secret::new(password.to_vec());
+        Self::awaiting_header(writer, cipher, HeaderKind::Password { password, format })
+    }
+
+    fn awaiting_header(writer: W, cipher: Cipher, kind: HeaderKind) -> Self {
+        Self {
+            inner: DecryptorState::AwaitingHeader(PendingHeader {
+                writer: Some(writer),
+                cipher,
+                kind,
+                header: Secret::default(),
+                drop_guard: DropGuard::default(),
+            }),
+        }
+    }
+
     /// Finishes writing to the underlying cryptostream, padding the final block as needed,
     /// flushing all output. Returns the wrapped `Write` instance.
     pub fn finish(self) -> Result<W, Error> {
-        self.inner.finish()
+        match self.inner {
+            DecryptorState::Decrypting(inner) => inner.finish(),
+            // The ciphertext ended before the end of its header.
+            DecryptorState::AwaitingHeader(mut pending) => {
+                pending.drop_guard = DropGuard::default();
+                Err(crate::Error::Truncated.into())
+            }
+        }
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        match &mut self.inner {
+            DecryptorState::Decrypting(inner) => inner.inner_finish(),
+            DecryptorState::AwaitingHeader(_) => Err(crate::Error::Truncated.into()),
+        }
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        match &self.inner {
+            DecryptorState::Decrypting(inner) => inner.is_finished(),
+            DecryptorState::AwaitingHeader(_) => false,
+        }
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        match &mut self.inner {
+            DecryptorState::Decrypting(inner) => inner.drop_guard = drop_guard,
+            DecryptorState::AwaitingHeader(pending) => pending.drop_guard = drop_guard,
+        }
+    }
+
+    /// Always true, as [`write_vectored()`](Write::write_vectored) decrypts several slices at
+    /// once. (`Write::is_write_vectored()` itself is not yet stable.)
+    pub fn is_write_vectored(&self) -> bool {
+        true
     }
 }
 
 impl<W: Write> Write for Decryptor<W> {
     /// Writes encrypted bytes to the cryptostream, causing their decrypted contents to be written
     /// to the underlying `Write` object.
-    fn write(&mut self, mut buf: &[u8]) -> Result<usize, Error> {
-        self.inner.write(&mut buf)
-    }
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let pending = match &mut self.inner {
+            DecryptorState::Decrypting(inner) => return inner.write(buf),
+            DecryptorState::AwaitingHeader(pending) => pending,
+        };
+
+        let (consumed, inner) = pending.write(buf)?;
+        match inner {
+            // Pass on whatever follows the header, so that all of `buf` is consumed.
+            Some(inner) => {
+                self.inner = DecryptorState::Decrypting(inner);
+                Ok(consumed + self.write(&buf[consumed..])?)
+            }
+            None => Ok(consumed),
+        }
+    }
+
+    /// Decrypts as many of `bufs` as possible in a single pass, writing the plaintext to the
+    /// underlying `Write` object in one go.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        match &mut self.inner {
+            DecryptorState::Decrypting(inner) => inner.write_vectored(bufs),
+            // The header is awaited a slice at a time.
+            DecryptorState::AwaitingHeader(_) => {
+                let buf = bufs.iter().find(|buf| !buf.is_empty());
+                self.write(buf.map_or(&[][..], |buf| buf))
+            }
+        }
+    }
 
     /// Flushes the underlying stream but does not clear all internal buffers or explicitly pad the
     /// output blocks as that would prevent us from reading any further in the future if we are not
     /// a block boundary.
+    fn flush(&mut self) -> Result<(), Error> {
+        match &mut self.inner {
+            DecryptorState::Decrypting(inner) => inner.flush(),
+            DecryptorState::AwaitingHeader(pending) => match pending.writer.as_mut() {
+                Some(writer) => writer.flush(),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// An authenticated encrypting stream adapter that encrypts what is written to it
+///
+/// `write::AeadEncryptor` is the AEAD counterpart to [`write::Encryptor`](Encryptor), for use with
+/// authenticated ciphers such as `Cipher::aes_256_gcm()` or `Cipher::chacha20_poly1305()`.
+/// Plaintext written to the `AeadEncryptor` is encrypted and written to the underlying stream,
+/// followed by the 16-byte authentication tag when the `AeadEncryptor` is finished or dropped.
+pub struct AeadEncryptor<W: Write> {
+    inner: Cryptostream<W>,
+}
+
+impl<W: Write> AeadEncryptor<W> {
+    /// Creates a new `AeadEncryptor`, authenticating the associated data `aad` (which may be
+    /// empty) along with the ciphertext.
+    pub fn new(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_aead(Mode::Encrypt, writer, cipher, key, iv, aad)?,
+        })
+    }
+
+    /// Creates a new `AeadEncryptor` which transforms up to `capacity` bytes (rather than 8 KiB)
+    /// at a time, bounding the size of each write to the underlying stream.
+    pub fn with_capacity(
+        capacity: usize,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_aead_buffered(
+                Mode::Encrypt,
+                writer,
+                cipher,
+                key,
+                iv,
+                aad,
+                capacity,
+            )?,
+        })
+    }
+
+    /// Creates a new `AeadEncryptor` which commits to `key`, writing the commitment to `writer`
+    /// straight away so that [`AeadDecryptor::with_key_commitment()`] fails before decrypting
+    /// anything if given any other key.
+    pub fn with_key_commitment(
+        mut writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let (key, commitment) = commit::derive(cipher, key, iv)?;
+        writer.write_all(&commitment)?;
+        Self::new(writer, cipher, &key, iv, aad)
+    }
+
+    /// Finishes writing to the underlying cryptostream, appending the authentication tag and
+    /// flushing all output. Returns the wrapped `Write` instance.
+    pub fn finish(self) -> Result<W, Error> {
+        self.inner.finish()
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        self.inner.inner_finish()
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+}
+
+impl<W: Write> Write for AeadEncryptor<W> {
+    /// Writes decrypted bytes to the cryptostream, causing their encrypted contents to be written
+    /// to the underlying `Write` object.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.inner.write(buf)
+    }
+
+    /// Encrypts all of `bufs`, writing the ciphertext to the underlying `Write` object.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        self.inner.write_vectored(bufs)
+    }
+
+    /// Flushes the underlying stream. The authentication tag is only written once the
+    /// cryptostream is finished.
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+/// An authenticated decrypting stream adapter that decrypts what is written to it
+///
+/// `write::AeadDecryptor` is the AEAD counterpart to [`write::Decryptor`](Decryptor), for use with
+/// authenticated ciphers such as `Cipher::aes_256_gcm()` or `Cipher::chacha20_poly1305()`.
+/// Ciphertext followed by its 16-byte authentication tag is written to the `AeadDecryptor`, and
+/// the decrypted plaintext is written to the underlying stream.
+///
+/// The last 16 bytes written are withheld as the (potential) tag, which is verified when the
+/// `AeadDecryptor` is finished. Plaintext written to the underlying stream must not be trusted
+/// until [`finish()`](AeadDecryptor::finish) has returned successfully; a failed verification is
+/// reported as an error with kind [`ErrorKind::InvalidData`](std::io::ErrorKind::InvalidData).
+pub struct AeadDecryptor<W: Write> {
+    inner: Cryptostream<W>,
+    /// Only set if created with `with_key_commitment()`, until the commitment has been checked.
+    commitment: Option<commit::Pending>,
+}
+
+impl<W: Write> AeadDecryptor<W> {
+    /// Creates a new `AeadDecryptor`, authenticating the associated data `aad` (which must match
+    /// that provided when encrypting) along with the ciphertext.
+    pub fn new(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_aead(Mode::Decrypt, writer, cipher, key, iv, aad)?,
+            commitment: None,
+        })
+    }
+
+    /// Creates a new `AeadDecryptor` which transforms up to `capacity` bytes (rather than 8 KiB)
+    /// at a time, bounding the size of each write to the underlying stream.
+    pub fn with_capacity(
+        capacity: usize,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_aead_buffered(
+                Mode::Decrypt,
+                writer,
+                cipher,
+                key,
+                iv,
+                aad,
+                capacity,
+            )?,
+            commitment: None,
+        })
+    }
+
+    /// Creates a new `AeadDecryptor` for ciphertext produced by an encryptor created with
+    /// `with_key_commitment()`. The commitment at the start of the ciphertext is checked against
+    /// `key` as soon as it has been written, before anything is decrypted, failing with
+    /// [`Error::KeyCommitmentMismatch`](crate::Error::KeyCommitmentMismatch) if they don't match.
+    pub fn with_key_commitment(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let (key, commitment) = commit::derive(cipher, key, iv)?;
+        let mut decryptor = Self::new(writer, cipher, &key, iv, aad)?;
+        decryptor.commitment = Some(commit::Pending::new(commitment));
+        Ok(decryptor)
+    }
+
+    /// Finishes writing to the underlying cryptostream, verifying the authentication tag and
+    /// flushing all output. Returns the wrapped `Write` instance.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.inner.drop_guard = DropGuard::default();
+        self.try_finish()?;
+        // As with `Cryptostream::finish()`, the writer is taken rather than moved out, which the
+        // `Drop` implementation precludes.
+        Ok(self.inner.writer.take().unwrap())
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        check_committed(&self.commitment, &mut self.inner.poisoned)?;
+        self.inner.inner_finish()
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+}
+
+impl<W: Write> Write for AeadDecryptor<W> {
+    /// Writes encrypted bytes to the cryptostream, causing their decrypted contents to be written
+    /// to the underlying `Write` object.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let consumed = match &mut self.commitment {
+            Some(pending) => {
+                let result = pending.write(buf).map_err(Error::from);
+                let consumed = self.inner.poison_on_error(result)?;
+                if !pending.is_complete() {
+                    return Ok(consumed);
+                }
+                self.commitment = None;
+                consumed
+            }
+            None => 0,
+        };
+
+        Ok(consumed + self.inner.write(&buf[consumed..])?)
+    }
+
+    /// Decrypts all of `bufs`, writing the plaintext to the underlying stream, once the key
+    /// commitment (if any) has been read.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        if self.commitment.is_some() {
+            // The key commitment is awaited a slice at a time.
+            let buf = bufs.iter().find(|buf| !buf.is_empty());
+            return self.write(buf.map_or(&[][..], |buf| buf));
+        }
+        self.inner.write_vectored(bufs)
+    }
+
+    /// Flushes the underlying stream. The authentication tag is only verified once the
+    /// cryptostream is finished.
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for AeadDecryptor<W> {
+    /// Finishes the cryptostream only once the key commitment has been checked, leaving the
+    /// underlying stream untouched if the ciphertext ended before it.
+    fn drop(&mut self) {
+        let _ = check_committed(&self.commitment, &mut self.inner.poisoned);
+    }
+}
+
+/// An encrypting stream adapter that encrypts what is written to it, then authenticates it with a
+/// MAC
+///
+/// `write::EtmEncryptor` is the encrypt-then-MAC counterpart to [`write::Encryptor`](Encryptor),
+/// for authenticating the output of unauthenticated ciphers such as `Cipher::aes_256_cbc()`.
+/// Plaintext written to the `EtmEncryptor` is encrypted and written to the underlying stream,
+/// followed by a 32-byte HMAC-SHA256 tag over the IV and the ciphertext when the `EtmEncryptor` is
+/// finished or dropped.
+pub struct EtmEncryptor<W: Write> {
+    inner: Cryptostream<W>,
+}
+
+impl<W: Write> EtmEncryptor<W> {
+    /// Creates a new `EtmEncryptor`, computing the MAC with `mac_key`, which must be independent
+    /// of `key`.
+    pub fn new(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: Cryptostream::new_etm(Mode::Encrypt, writer, cipher, key, mac_key, iv)?,
+        })
+    }
+
+    /// Finishes writing to the underlying cryptostream, padding the final block as needed and
+    /// appending the MAC. Returns the wrapped `Write` instance.
+    pub fn finish(self) -> Result<W, Error> {
+        self.inner.finish()
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        self.inner.inner_finish()
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+}
+
+impl<W: Write> Write for EtmEncryptor<W> {
+    /// Writes decrypted bytes to the cryptostream, causing their encrypted contents to be written
+    /// to the underlying `Write` object.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.inner.write(buf)
+    }
+
+    /// Flushes the underlying stream. The MAC is only written once the cryptostream is finished.
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+/// A decrypting stream adapter that verifies the MAC of what is written to it, then decrypts it
+///
+/// `write::EtmDecryptor` is the encrypt-then-MAC counterpart to [`write::Decryptor`](Decryptor).
+/// Ciphertext followed by its 32-byte HMAC-SHA256 tag is written to the `EtmDecryptor`, and the
+/// decrypted plaintext is written to the underlying stream. The MAC is verified when the
+/// `EtmDecryptor` is finished, before the padding of the final block is checked.
+///
+/// Plaintext is written to the underlying stream as it is decrypted, with only the final block
+/// (for block ciphers) withheld until the MAC has been verified, so it must not be trusted until
+/// [`finish()`](EtmDecryptor::finish) has returned successfully. Use
+/// [`buffered()`](EtmDecryptor::buffered) to withhold all plaintext until then instead. A failed
+/// verification is reported as an error with kind
+/// [`ErrorKind::InvalidData`](std::io::ErrorKind::InvalidData).
+pub struct EtmDecryptor<W: Write> {
+    inner: Cryptostream<Release<W>>,
+}
+
+/// Where an `EtmDecryptor` writes its plaintext.
+enum Release<W: Write> {
+    Immediately(W),
+    /// The plaintext is held back until the MAC has been verified.
+    Buffered(W, Secret<Vec<u8>>),
+}
+
+impl<W: Write> Release<W> {
+    /// Writes out any plaintext held back, once the MAC has been verified.
+    fn release(&mut self) -> Result<(), Error> {
+        if let Release::Buffered(writer, plaintext) = self {
+            writer.write_all(plaintext)?;
+            plaintext.clear();
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn into_inner(mut self) -> Result<W, Error> {
+        self.release()?;
+        match self {
+            Release::Immediately(writer) | Release::Buffered(writer, _) => Ok(writer),
+        }
+    }
+}
+
+impl<W: Write> Write for Release<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        match self {
+            Release::Immediately(writer) => writer.write(buf),
+            Release::Buffered(_, plaintext) => {
+                plaintext.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        match self {
+            Release::Immediately(writer) => writer.flush(),
+            Release::Buffered(..) => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> EtmDecryptor<W> {
+    /// Creates a new `EtmDecryptor`, verifying the MAC with `mac_key`.
+    pub fn new(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let writer = Release::Immediately(writer);
+        Ok(Self {
+            inner: Cryptostream::new_etm(Mode::Decrypt, writer, cipher, key, mac_key, iv)?,
+        })
+    }
+
+    /// Creates a new `EtmDecryptor` which holds all plaintext in memory until the MAC has been
+    /// verified by [`finish()`](EtmDecryptor::finish), only then writing it to `writer`. The
+    /// plaintext is discarded if the `EtmDecryptor` is dropped without being finished.
+    pub fn buffered(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        mac_key: &[u8],
+        iv: &[u8],
+    ) -> Result<Self, crate::Error> {
+        let writer = Release::Buffered(writer, Secret::default());
+        Ok(Self {
+            inner: Cryptostream::new_etm(Mode::Decrypt, writer, cipher, key, mac_key, iv)?,
+        })
+    }
+
+    /// Finishes writing to the underlying cryptostream, verifying the MAC and flushing all
+    /// output. Returns the wrapped `Write` instance.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.inner.drop_guard = DropGuard::default();
+        self.try_finish()?;
+        // As with `Cryptostream::finish()`, the writer is taken rather than moved out, which the
+        // `Drop` implementation precludes.
+        self.inner.writer.take().unwrap().into_inner()
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        self.inner.inner_finish()?;
+        let result = self.inner.writer.as_mut().unwrap().release();
+        self.inner.poison_on_error(result)
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+}
+
+impl<W: Write> Drop for EtmDecryptor<W> {
+    /// Discards any plaintext held back by a buffered `EtmDecryptor` that hasn't been finished,
+    /// leaving the cryptostream unusable so that the drop guard reports its output as incomplete.
+    fn drop(&mut self) {
+        if let Some(Release::Buffered(..)) = &self.inner.writer {
+            self.inner.poisoned |= !self.inner.is_finished();
+        }
+    }
+}
+
+impl<W: Write> Write for EtmDecryptor<W> {
+    /// Writes encrypted bytes to the cryptostream, causing their decrypted contents to be written
+    /// to the underlying `Write` object (or buffered, if created with `buffered()`).
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.inner.write(buf)
+    }
+
+    /// Flushes the underlying stream. The MAC is only verified once the cryptostream is finished.
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+struct SegmentedCryptostream<W: Write, S: Segments = Segmenter> {
+    segmenter: S,
+    /// The input accumulated towards the next segment.
+    input: Secret<Vec<u8>>,
+    output: Secret<Vec<u8>>,
+    /// As with [`Cryptostream::writer`], this is only ever `None` after `finish()` is called.
+    writer: Option<W>,
+    /// As with [`Cryptostream::poisoned`], set by any error writing to or finishing the
+    /// cryptostream.
+    poisoned: bool,
+    drop_guard: DropGuard,
+}
+
+impl<W: Write> SegmentedCryptostream<W> {
+    pub fn new(
+        mode: Mode,
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        let segmenter = Segmenter::new(mode, cipher, key, nonce_prefix, segment_size)?;
+        Ok(SegmentedCryptostream::with_segmenter(writer, segmenter))
+    }
+}
+
+impl<W: Write, S: Segments> SegmentedCryptostream<W, S> {
+    fn with_segmenter(writer: W, segmenter: S) -> Self {
+        Self {
+            input: Secret::new(Vec::with_capacity(segmenter.input_len())),
+            output: Secret::default(),
+            segmenter,
+            writer: Some(writer),
+            poisoned: false,
+            drop_guard: DropGuard::default(),
+        }
+    }
+
+    /// Processes the accumulated input as the next segment and writes out the result.
+    fn write_segment(&mut self, last: bool) -> Result<(), Error> {
+        self.output.clear();
+        self.segmenter
+            .process(&self.input, last, &mut self.output)?;
+        self.input.clear();
+
+        self.writer.as_mut().unwrap().write_all(&self.output)
+    }
+
+    /// Function shared by Drop, finish(), and try_finish()
+    fn inner_finish(&mut self) -> Result<(), Error> {
+        if self.poisoned {
+            return Err(poisoned());
+        }
+        let result = self.finalize();
+        self.poison_on_error(result)
+    }
+
+    fn finalize(&mut self) -> Result<(), Error> {
+        if !self.segmenter.is_finished() {
+            self.write_segment(true)?;
+        }
+
+        self.flush()
+    }
+
+    /// Whether the cryptostream has been finished without error.
+    pub fn is_finished(&self) -> bool {
+        self.segmenter.is_finished() && !self.poisoned
+    }
+
+    /// Flags the cryptostream as unusable if `result` is an error.
+    fn poison_on_error<T>(&mut self, result: Result<T, Error>) -> Result<T, Error> {
+        self.poisoned |= result.is_err();
+        result
+    }
+
+    /// Accumulates all of `buf`, processing each segment once it's known not to be the last.
+    fn transform(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if self.segmenter.is_finished() {
+            return Ok(0);
+        }
+
+        // A complete segment is only processed once more input arrives, as only then do we know
+        // that it isn't the final segment of the stream.
+        let mut consumed = 0;
+        while consumed < buf.len() {
+            if self.input.len() == self.segmenter.input_len() {
+                self.write_segment(false)?;
+            }
+
+            let input_len = self.segmenter.input_len();
+            let len = std::cmp::min(buf.len() - consumed, input_len - self.input.len());
+            self.input.extend_from_slice(&buf[consumed..][..len]);
+            consumed += len;
+        }
+
+        Ok(consumed)
+    }
+
+    /// Accumulates all of `bufs` as [`transform()`](Self::transform) does, gathering the slices
+    /// into segments rather than processing them a slice at a time.
+    fn transform_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        let mut consumed = 0;
+        for buf in bufs {
+            consumed += self.transform(buf)?;
+        }
+        Ok(consumed)
+    }
+
+    /// Finishes writing to the underlying cryptostream, sealing or opening the final segment and
+    /// flushing all output. Returns the wrapped `Write` instance.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.drop_guard = DropGuard::default();
+        self.inner_finish()?;
+
+        let mut inner = None;
+        std::mem::swap::<Option<W>>(&mut self.writer, &mut inner);
+        Ok(inner.unwrap())
+    }
+}
+
+impl<W: Write, S: Segments> Write for SegmentedCryptostream<W, S> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if self.poisoned {
+            return Err(poisoned());
+        }
+        let result = self.transform(buf);
+        self.poison_on_error(result)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        if self.poisoned {
+            return Err(poisoned());
+        }
+        let result = self.transform_vectored(bufs);
+        self.poison_on_error(result)
+    }
+
+    /// Flushes the underlying stream. Any input not yet making up a complete segment remains
+    /// buffered until more is written or the cryptostream is finished.
+    fn flush(&mut self) -> Result<(), Error> {
+        match self.writer.as_mut() {
+            Some(x) => x.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<W: Write, S: Segments> Drop for SegmentedCryptostream<W, S> {
+    /// Process the final segment and flush everything as permitted by the drop guard, unless an
+    /// error has left the cryptostream unusable. Any error finishing it here can only be reported
+    /// through the drop guard.
+    fn drop(&mut self) {
+        let drop_guard = std::mem::take(&mut self.drop_guard);
+        drop_guard.guard(self.is_finished(), || {
+            !self.poisoned && self.inner_finish().is_ok()
+        });
+    }
+}
+
+/// An encrypting stream adapter that encrypts what is written to it in authenticated segments
+///
+/// `write::SegmentedEncryptor` is a stream adapter that sits atop a `Write` stream. Plaintext
+/// written to the `SegmentedEncryptor` is split into fixed-size segments, each of which is sealed
+/// with an AEAD cipher (`Cipher::aes_128_gcm()`, `Cipher::aes_256_gcm()`, or
+/// `Cipher::chacha20_poly1305()`) and written to the underlying stream followed by its
+/// authentication tag. The final segment is written when the `SegmentedEncryptor` is finished or
+/// dropped.
+///
+/// The nonce of each segment is derived from the 7-byte `nonce_prefix`, which must never be reused
+/// with the same key. Where that can't be guaranteed, seal the segments with AES-GCM-SIV from the
+/// [`siv`](crate::siv) module, which limits the damage of a reused nonce prefix. Use
+/// [`read::SegmentedDecryptor`](crate::read::SegmentedDecryptor) or
+/// [`write::SegmentedDecryptor`](SegmentedDecryptor) to decrypt the result.
+pub struct SegmentedEncryptor<W: Write> {
+    inner: SegmentedCryptostream<W>,
+}
+
+impl<W: Write> SegmentedEncryptor<W> {
+    /// Creates a new `SegmentedEncryptor` with the default segment size of 64 KiB.
+    pub fn new(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_segment_size(writer, cipher, key, nonce_prefix, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Creates a new `SegmentedEncryptor` with the default segment size which commits to `key`,
+    /// writing the commitment to `writer` straight away so that
+    /// [`SegmentedDecryptor::with_key_commitment()`] fails before decrypting anything if given
+    /// any other key.
+    pub fn with_key_commitment(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_key_commitment_and_segment_size(
+            writer,
+            cipher,
+            key,
+            nonce_prefix,
+            DEFAULT_SEGMENT_SIZE,
+        )
+    }
+
+    /// Creates a new `SegmentedEncryptor` which commits to `key` as
+    /// [`with_key_commitment()`](Self::with_key_commitment) does, sealing `segment_size` bytes of
+    /// plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_key_commitment_and_segment_size(
+        mut writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        let (key, commitment) = commit::derive(cipher, key, nonce_prefix)?;
+        writer.write_all(&commitment)?;
+        Self::with_segment_size(writer, cipher, &key, nonce_prefix, segment_size)
+    }
+
+    /// Creates a new `SegmentedEncryptor` sealing `segment_size` bytes of plaintext per segment.
+    /// The same segment size must be used to decrypt the result.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_segment_size(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: SegmentedCryptostream::new(
+                Mode::Encrypt,
+                writer,
+                cipher,
+                key,
+                nonce_prefix,
+                segment_size,
+            )?,
+        })
+    }
+
+    /// Finishes writing to the underlying cryptostream, sealing the final segment and flushing all
+    /// output. Returns the wrapped `Write` instance.
+    pub fn finish(self) -> Result<W, Error> {
+        self.inner.finish()
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        self.inner.inner_finish()
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+}
+
+impl<W: Write> Write for SegmentedEncryptor<W> {
+    /// Writes decrypted bytes to the cryptostream, causing their encrypted contents to be written
+    /// to the underlying `Write` object one segment at a time.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.inner.write(buf)
+    }
+
+    /// Encrypts all of `bufs`, gathering the slices into segments.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        self.inner.write_vectored(bufs)
+    }
+
+    /// Flushes the underlying stream but does not seal the segment currently being buffered, as
+    /// only the final segment may be shorter than the segment size.
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+/// A decrypting stream adapter that decrypts segmented ciphertext written to it
+///
+/// `write::SegmentedDecryptor` is a stream adapter that sits atop a `Write` stream. Ciphertext
+/// produced by a [`SegmentedEncryptor`] is written to the `SegmentedDecryptor`, and the plaintext
+/// of each segment is written to the underlying stream only once the segment has been
+/// authenticated. Reordered or tampered segments are reported as an error with kind
+/// [`ErrorKind::InvalidData`](std::io::ErrorKind::InvalidData), while a stream cut off at a segment
+/// boundary is reported as [`Error::Truncated`](crate::Error::Truncated).
+///
+/// The final segment is only decrypted and authenticated when the `SegmentedDecryptor` is
+/// finished, so the plaintext must not be considered complete until
+/// [`finish()`](SegmentedDecryptor::finish) has returned successfully.
+pub struct SegmentedDecryptor<W: Write> {
+    inner: SegmentedCryptostream<W>,
+    /// Only set if created with `with_key_commitment()`, until the commitment has been checked.
+    commitment: Option<commit::Pending>,
+}
+
+impl<W: Write> SegmentedDecryptor<W> {
+    /// Creates a new `SegmentedDecryptor` for ciphertext sealed with the default segment size of
+    /// 64 KiB.
+    pub fn new(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_segment_size(writer, cipher, key, nonce_prefix, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Creates a new `SegmentedDecryptor` for ciphertext sealed with `segment_size` bytes of
+    /// plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_segment_size(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        Ok(Self {
+            inner: SegmentedCryptostream::new(
+                Mode::Decrypt,
+                writer,
+                cipher,
+                key,
+                nonce_prefix,
+                segment_size,
+            )?,
+            commitment: None,
+        })
+    }
+
+    /// Creates a new `SegmentedDecryptor` for ciphertext produced by an encryptor created with
+    /// `with_key_commitment()`. The commitment at the start of the ciphertext is checked against
+    /// `key` as soon as it has been written, before any segment is decrypted, failing with
+    /// [`Error::KeyCommitmentMismatch`](crate::Error::KeyCommitmentMismatch) if they don't match.
+    pub fn with_key_commitment(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+    ) -> Result<Self, crate::Error> {
+        Self::with_key_commitment_and_segment_size(
+            writer,
+            cipher,
+            key,
+            nonce_prefix,
+            DEFAULT_SEGMENT_SIZE,
+        )
+    }
+
+    /// Creates a new `SegmentedDecryptor` which checks the key commitment as
+    /// [`with_key_commitment()`](Self::with_key_commitment) does, for ciphertext sealed with
+    /// `segment_size` bytes of plaintext per segment.
+    ///
+    /// Panics if `segment_size` is zero.
+    pub fn with_key_commitment_and_segment_size(
+        writer: W,
+        cipher: Cipher,
+        key: &[u8],
+        nonce_prefix: &[u8; NONCE_PREFIX_LEN],
+        segment_size: usize,
+    ) -> Result<Self, crate::Error> {
+        let (key, commitment) = commit::derive(cipher, key, nonce_prefix)?;
+        let mut decryptor =
+            Self::with_segment_size(writer, cipher, &key, nonce_prefix, segment_size)?;
+        decryptor.commitment = Some(commit::Pending::new(commitment));
+        Ok(decryptor)
+    }
+
+    /// Finishes writing to the underlying cryptostream, authenticating and decrypting the final
+    /// segment and flushing all output. Returns the wrapped `Write` instance.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.inner.drop_guard = DropGuard::default();
+        self.try_finish()?;
+        // As with `Cryptostream::finish()`, the writer is taken rather than moved out, which the
+        // `Drop` implementation precludes.
+        Ok(self.inner.writer.take().unwrap())
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        check_committed(&self.commitment, &mut self.inner.poisoned)?;
+        self.inner.inner_finish()
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+}
+
+impl<W: Write> Write for SegmentedDecryptor<W> {
+    /// Writes encrypted bytes to the cryptostream, causing their decrypted contents to be written
+    /// to the underlying `Write` object one authenticated segment at a time.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let consumed = match &mut self.commitment {
+            Some(pending) => {
+                let result = pending.write(buf).map_err(Error::from);
+                let consumed = self.inner.poison_on_error(result)?;
+                if !pending.is_complete() {
+                    return Ok(consumed);
+                }
+                self.commitment = None;
+                consumed
+            }
+            None => 0,
+        };
+
+        Ok(consumed + self.inner.write(&buf[consumed..])?)
+    }
+
+    /// Decrypts all of `bufs`, gathering the slices into segments, once the key commitment (if
+    /// any) has been read.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        if self.commitment.is_some() {
+            // The key commitment is awaited a slice at a time.
+            let buf = bufs.iter().find(|buf| !buf.is_empty());
+            return self.write(buf.map_or(&[][..], |buf| buf));
+        }
+        self.inner.write_vectored(bufs)
+    }
+
+    /// Flushes the underlying stream but does not decrypt the segment currently being buffered,
+    /// as it cannot be authenticated until it is complete.
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for SegmentedDecryptor<W> {
+    /// Finishes the cryptostream only once the key commitment has been checked, leaving the
+    /// underlying stream untouched if the ciphertext ended before it.
+    fn drop(&mut self) {
+        let _ = check_committed(&self.commitment, &mut self.inner.poisoned);
+    }
+}
+
+/// An encrypting stream adapter that encrypts what is written to it as a libsodium secretstream
+///
+/// `write::SecretStreamEncryptor` is a stream adapter that sits atop a `Write` stream. Plaintext
+/// written to the `SecretStreamEncryptor` is encrypted with libsodium's
+/// `crypto_secretstream_xchacha20poly1305` construction, a chunk at a time, and written to the
+/// underlying stream after the random secretstream header. The final chunk, tagged
+/// [`Tag::Final`](crate::secretstream::Tag::Final), is written when the `SecretStreamEncryptor`
+/// is finished or dropped. See the [`secretstream`](crate::secretstream) module for the format.
+pub struct SecretStreamEncryptor<W: Write> {
+    inner: SegmentedCryptostream<W, Chunker>,
+}
+
+impl<W: Write> SecretStreamEncryptor<W> {
+    /// Creates a new `SecretStreamEncryptor` with the default chunk size of 4 KiB.
+    pub fn new(writer: W, key: &[u8]) -> Result<Self, crate::Error> {
+        Self::with_chunk_size(writer, key, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new `SecretStreamEncryptor` encrypting `chunk_size` bytes of plaintext per
+    /// message. The same chunk size must be used to decrypt the result.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(writer: W, key: &[u8], chunk_size: usize) -> Result<Self, crate::Error> {
+        let chunker = Chunker::new(Mode::Encrypt, key, chunk_size)?;
+        Ok(Self {
+            inner: SegmentedCryptostream::with_segmenter(writer, chunker),
+        })
+    }
+
+    /// Finishes writing to the underlying cryptostream, encrypting the final chunk and flushing
+    /// all output. Returns the wrapped `Write` instance.
+    pub fn finish(self) -> Result<W, Error> {
+        self.inner.finish()
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        self.inner.inner_finish()
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+}
+
+impl<W: Write> Write for SecretStreamEncryptor<W> {
+    /// Writes decrypted bytes to the cryptostream, causing their encrypted contents to be written
+    /// to the underlying `Write` object one chunk at a time.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.inner.write(buf)
+    }
+
+    /// Encrypts all of `bufs`, gathering the slices into chunks.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        self.inner.write_vectored(bufs)
+    }
+
+    /// Flushes the underlying stream but does not encrypt the chunk currently being buffered, as
+    /// only the final chunk may be shorter than the chunk size.
+    fn flush(&mut self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+}
+
+/// A decrypting stream adapter that decrypts a libsodium secretstream written to it
+///
+/// `write::SecretStreamDecryptor` is a stream adapter that sits atop a `Write` stream. A
+/// secretstream (as produced by a [`SecretStreamEncryptor`] or libsodium's
+/// `crypto_secretstream_xchacha20poly1305_push()` in fixed-size chunks) is written to the
+/// `SecretStreamDecryptor`, and the plaintext of each chunk is written to the underlying stream
+/// only once the chunk has been authenticated. A stream which ends without a chunk tagged
+/// [`Tag::Final`](crate::secretstream::Tag::Final) is reported as
+/// [`Error::Truncated`](crate::Error::Truncated).
+///
+/// The final chunk is only decrypted and authenticated when the `SecretStreamDecryptor` is
+/// finished, so the plaintext must not be considered complete until
+/// [`finish()`](SecretStreamDecryptor::finish) has returned successfully.
+pub struct SecretStreamDecryptor<W: Write> {
+    inner: SegmentedCryptostream<W, Chunker>,
+}
+
+impl<W: Write> SecretStreamDecryptor<W> {
+    /// Creates a new `SecretStreamDecryptor` for a secretstream encrypted with the default chunk
+    /// size of 4 KiB.
+    pub fn new(writer: W, key: &[u8]) -> Result<Self, crate::Error> {
+        Self::with_chunk_size(writer, key, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new `SecretStreamDecryptor` for a secretstream encrypted with `chunk_size` bytes
+    /// of plaintext per message.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(writer: W, key: &[u8], chunk_size: usize) -> Result<Self, crate::Error> {
+        let chunker = Chunker::new(Mode::Decrypt, key, chunk_size)?;
+        Ok(Self {
+            inner: SegmentedCryptostream::with_segmenter(writer, chunker),
+        })
+    }
+
+    /// Finishes writing to the underlying cryptostream, authenticating and decrypting the final
+    /// chunk and flushing all output. Returns the wrapped `Write` instance.
+    pub fn finish(self) -> Result<W, Error> {
+        self.inner.finish()
+    }
+
+    /// Finishes the cryptostream as [`finish()`](Self::finish) does, reporting any error but
+    /// leaving the cryptostream (and the wrapped `Write` instance) in place. Once finished, writes
+    /// to the cryptostream consume nothing.
+    pub fn try_finish(&mut self) -> Result<(), Error> {
+        self.inner.inner_finish()
+    }
+
+    /// Whether the cryptostream has been finished (with [`try_finish()`](Self::try_finish))
+    /// without error.
+    pub fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Sets what happens if the cryptostream is dropped without being finished. See
+    /// [`DropGuard`].
+    pub fn set_drop_guard(&mut self, drop_guard: DropGuard) {
+        self.inner.drop_guard = drop_guard;
+    }
+}
+
+impl<W: Write> Write for SecretStreamDecryptor<W> {
+    /// Writes encrypted bytes to the cryptostream, causing their decrypted contents to be written
+    /// to the underlying `Write` object one authenticated chunk at a time.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        self.inner.write(buf)
+    }
+
+    /// Decrypts all of `bufs`, gathering the slices into chunks.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize, Error> {
+        self.inner.write_vectored(bufs)
+    }
+
+    /// Flushes the underlying stream but does not decrypt the chunk currently being buffered, as
+    /// it cannot be authenticated until it is complete.
     fn flush(&mut self) -> Result<(), Error> {
         self.inner.flush()
     }